@@ -8,6 +8,115 @@ function renderToString(app) {
   const rendered = (new Type(app.props)).render();
   return addon.renderToString(rendered);
 }
+// Thrown when a render exceeds `maxDepth`, `maxBytes` or `timeout`. Has the
+// exceeded `limit` and the `componentStack` at the time.
+class RenderLimitError extends Error {}
+RenderLimitError.prototype.name = 'RenderLimitError';
+
+// Thrown for an unsafe URL when the URL policy's action is 'throw'. Has the
+// `attribute`, the `url` and the `componentStack` at the time.
+class UnsafeUrlError extends Error {}
+UnsafeUrlError.prototype.name = 'UnsafeUrlError';
+
+// Thrown for raw HTML that wasn't marked with `trustHTML`, in a render with
+// `trustedHTMLOnly`. Has the `tag` and the `componentStack` at the time.
+class UntrustedHtmlError extends Error {}
+UntrustedHtmlError.prototype.name = 'UntrustedHtmlError';
+
+// Thrown for an unsafe inline style when the style safety mode is 'throw'.
+// Has the `property`, its `value`, the `threat` and the `componentStack`.
+class UnsafeStyleError extends Error {}
+UnsafeStyleError.prototype.name = 'UnsafeStyleError';
+
+// Thrown for an invalid tag or attribute name when the invalid name action
+// is 'throw'. Has the `kind` ('tag' or 'attribute'), the `invalidName`, the
+// element's `tag` and the `componentStack`.
+class InvalidNameError extends Error {}
+InvalidNameError.prototype.name = 'InvalidNameError';
+
+const errorClasses = {
+  RenderLimitError,
+  UnsafeUrlError,
+  UntrustedHtmlError,
+  UnsafeStyleError,
+  InvalidNameError,
+};
+
+// The native renderer looks the HTML up under this symbol. A symbol can't
+// come from JSON, so CMS data can't pass itself off as trusted.
+const TRUSTED_HTML = Symbol.for('react-neon-ssr.trustedHTML');
+
+// Marks `html` as trusted, for `dangerouslySetInnerHTML={{__html: ...}}`. It
+// is never sanitized, and allowed in renders with `trustedHTMLOnly`. React
+// itself renders it as the plain string.
+function trustHTML(html) {
+  html = String(html);
+  return Object.freeze({[TRUSTED_HTML]: html, toString: () => html});
+}
+
+let reportUnsafeUrl = null;
+
+// Like the native `setUrlPolicy`, plus an optional `report` called with the
+// `{tag, attribute, url, componentStack}` of every unsafe URL rendered.
+function setUrlPolicy(policy) {
+  addon.setUrlPolicy(policy);
+  reportUnsafeUrl = policy.report || null;
+}
+
+// Unsafe URLs only come back with a detailed result, so one is asked for
+// while they are reported.
+function renderReporting(render, element, options) {
+  const detailed = typeof options === 'object' && options !== null;
+  const result = render(element, detailed ? options : {route: options});
+  result.unsafeUrls.forEach(unsafeUrl => reportUnsafeUrl(unsafeUrl));
+  return detailed ? result : result.html;
+}
+
+// `renderDocument` takes an object either way, and says `detailed`.
+function renderDocumentReporting(render, element, options) {
+  options = options || {};
+  const result = render(element, Object.assign({}, options, {detailed: true}));
+  result.unsafeUrls.forEach(unsafeUrl => reportUnsafeUrl(unsafeUrl));
+  return options.detailed ? result : result.html;
+}
+
+function withErrorClasses(render, reporting = renderReporting) {
+  return function(element, options) {
+    try {
+      if (reportUnsafeUrl) {
+        return reporting(render, element, options);
+      }
+      return render(element, options);
+    } catch (err) {
+      if (err instanceof Error && errorClasses.hasOwnProperty(err.name)) {
+        Object.setPrototypeOf(err, errorClasses[err.name].prototype);
+      }
+      throw err;
+    }
+  };
+}
 module.exports = {
-  renderToString: addon.renderToString,
+  renderToString: withErrorClasses(addon.renderToString),
+  renderToStaticMarkup: withErrorClasses(addon.renderToStaticMarkup),
+  renderDocument: withErrorClasses(addon.renderDocument, renderDocumentReporting),
+  RenderLimitError,
+  UnsafeUrlError,
+  UntrustedHtmlError,
+  UnsafeStyleError,
+  InvalidNameError,
+  trustHTML,
+  // Element types, for `<Head>` and `<JsonScript>` in JSX.
+  Head: 'Head',
+  JsonScript: 'JsonScript',
+  setUrlPolicy,
+  setSanitizer: addon.setSanitizer,
+  setStyleSafety: addon.setStyleSafety,
+  setInvalidNameAction: addon.setInvalidNameAction,
+  serializeForScript: addon.serializeForScript,
+  nativeComponents: addon.nativeComponents,
+  cacheStats: addon.cacheStats,
+  resizeCache: addon.resizeCache,
+  clearCaches: addon.clearCaches,
+  componentCacheStats: addon.componentCacheStats,
+  clearComponentCache: addon.clearComponentCache,
 }
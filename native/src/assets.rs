@@ -0,0 +1,205 @@
+/**
+ * Scripts and stylesheets from a bundler's asset manifest. With
+ * `RenderOptions::assets` the renderer writes the tags for the entrypoints
+ * itself: a `<link rel="stylesheet">` for each stylesheet and a
+ * `<link rel="preload" as="script">` for each script into the head (see
+ * `head`), and a `<script defer>` for each script at the end of the body.
+ *
+ * Manifests come in a few shapes, all of them understood:
+ *
+ *   {"main.js": "static/js/main.js", "main.css": "static/css/main.css"}
+ *   {"entrypoints": ["static/js/main.js", "static/css/main.css"], "files": ...}
+ *   {"entrypoints": {"main": {"assets": {"js": [...], "css": [...]}}}}
+ *
+ * The first two have a single entrypoint, `main` in the second. Files are
+ * told apart by extension, anything but scripts and stylesheets is ignored.
+ */
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use element::{Element, Node, PropValue, Props};
+use serde_json;
+
+const ENTRYPOINTS: &str = "entrypoints";
+const MAIN: &str = "main";
+
+/// The files of an entrypoint, in the order they are loaded.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entrypoint {
+    pub scripts: Vec<String>,
+    pub styles: Vec<String>,
+}
+
+impl Entrypoint {
+    fn add(&mut self, file: &str) {
+        let path = file.split(['?', '#']).next().unwrap_or("");
+        let files = if path.ends_with(".js") || path.ends_with(".mjs") {
+            &mut self.scripts
+        } else if path.ends_with(".css") {
+            &mut self.styles
+        } else {
+            return;
+        };
+        if !files.iter().any(|other| other == file) {
+            files.push(file.to_string());
+        }
+    }
+
+    fn add_all(&mut self, files: &PropValue) {
+        match *files {
+            PropValue::String(ref file) => self.add(file),
+            PropValue::Array(ref files) => {
+                for file in files {
+                    self.add_all(file);
+                }
+            },
+            PropValue::Object(ref files) => {
+                for (_, file) in files {
+                    self.add_all(file);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AssetManifest {
+    pub entrypoints: BTreeMap<String, Entrypoint>,
+}
+
+impl AssetManifest {
+    pub fn new() -> AssetManifest {
+        AssetManifest::default()
+    }
+
+    pub fn parse(json: &str) -> serde_json::Result<AssetManifest> {
+        let manifest: PropValue = serde_json::from_str(json)?;
+        Ok(AssetManifest::from_prop_value(&manifest))
+    }
+
+    /// A parsed manifest, in any of the shapes above.
+    pub fn from_prop_value(manifest: &PropValue) -> AssetManifest {
+        let mut entrypoints = BTreeMap::new();
+        let props = match *manifest {
+            PropValue::Object(ref props) => props,
+            _ => return AssetManifest { entrypoints },
+        };
+        match props.get(ENTRYPOINTS) {
+            Some(PropValue::Object(named)) => {
+                // Scripts and stylesheets, whatever the keys: `js` and `css`,
+                // maybe under `assets`.
+                for (name, files) in named {
+                    entrypoints.entry(name.clone()).or_default().add_all(files);
+                }
+            },
+            Some(files @ PropValue::Array(_)) => {
+                entrypoints.entry(MAIN.to_string()).or_default().add_all(files);
+            },
+            _ => add_flat_files(&mut entrypoints, props),
+        }
+        AssetManifest { entrypoints }
+    }
+
+    pub fn entrypoint(mut self, name: &str, entrypoint: Entrypoint) -> AssetManifest {
+        self.entrypoints.insert(name.to_string(), entrypoint);
+        self
+    }
+
+    /**
+     * The files of `entrypoints`, each once, with `public_path` in front of
+     * their paths. Fails for a name that isn't in the manifest.
+     */
+    pub fn assets(
+        &self,
+        entrypoints: &[&str],
+        public_path: &str,
+    ) -> Result<Assets, UnknownEntrypoint> {
+        let mut files = Entrypoint::default();
+        for name in entrypoints {
+            let entrypoint = match self.entrypoints.get(*name) {
+                Some(entrypoint) => entrypoint,
+                None => return Err(UnknownEntrypoint(name.to_string())),
+            };
+            for file in entrypoint.styles.iter().chain(&entrypoint.scripts) {
+                files.add(with_public_path(public_path, file).as_str());
+            }
+        }
+        Ok(Assets { scripts: files.scripts, styles: files.styles })
+    }
+}
+
+// `{"main.js": ..., "main.css": ..., "main.js.map": ...}`, keyed by the
+// entrypoint's name and the file's extension.
+fn add_flat_files(entrypoints: &mut BTreeMap<String, Entrypoint>, props: &Props) {
+    for (key, file) in props {
+        let file = match *file {
+            PropValue::String(ref file) if !file.is_empty() => file,
+            _ => continue,
+        };
+        let name = match key.rfind('.') {
+            Some(dot) if &key[dot..] == ".js" || &key[dot..] == ".css" => &key[..dot],
+            _ => continue,
+        };
+        let entrypoint = entrypoints.entry(name.to_string()).or_default();
+        if key.ends_with(".js") {
+            entrypoint.scripts.push(file.clone());
+        } else {
+            entrypoint.styles.push(file.clone());
+        }
+    }
+}
+
+fn with_public_path(public_path: &str, file: &str) -> String {
+    let absolute = file.starts_with("//") || file.find(':').is_some_and(|colon| {
+        file[..colon].chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    if public_path.is_empty() || absolute {
+        return file.to_string();
+    }
+    format!("{}/{}", public_path.trim_end_matches('/'), file.trim_start_matches('/'))
+}
+
+/// An entrypoint that isn't in the manifest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownEntrypoint(pub String);
+
+impl fmt::Display for UnknownEntrypoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown entrypoint `{}` in the asset manifest", self.0)
+    }
+}
+
+impl Error for UnknownEntrypoint {}
+
+/// The files to write tags for, see `AssetManifest::assets`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Assets {
+    pub scripts: Vec<String>,
+    pub styles: Vec<String>,
+}
+
+impl Assets {
+    /// The stylesheet and preload links for the head.
+    pub fn head(&self) -> Vec<Node> {
+        let styles = self.styles.iter().map(|href| {
+            Element::new("link").prop("rel", "stylesheet").prop("href", href.as_str()).into()
+        });
+        let preloads = self.scripts.iter().map(|href| {
+            Element::new("link")
+                .prop("rel", "preload")
+                .prop("href", href.as_str())
+                .prop("as", "script")
+                .into()
+        });
+        styles.chain(preloads).collect()
+    }
+
+    /// The scripts for the end of the body.
+    pub fn scripts(&self) -> Vec<Node> {
+        self.scripts.iter().map(|src| {
+            Element::new("script").prop("src", src.as_str()).prop("defer", true).into()
+        }).collect()
+    }
+}
@@ -0,0 +1,167 @@
+extern crate react_neon_ssr;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use react_neon_ssr::{Node, RenderOptions, render_with_options};
+use react_neon_ssr::json;
+use react_neon_ssr::name_safety::InvalidNameAction;
+
+const USAGE: &str = "\
+Usage: react-neon-ssr [OPTIONS] [FILE...]
+
+Render JSON element trees to HTML. Reads each FILE, or stdin when no FILE is
+given (or FILE is `-`, at most once), and writes the HTML to stdout. Exits with
+1 for a tree that can't be rendered, e.g. one with an invalid tag or attribute
+name, and with 2 for bad arguments.
+
+Options:
+    -s, --static         Render static markup (no data-reactroot, no text
+                         separators), like renderToStaticMarkup
+    -o, --out-dir DIR    Write FILE to DIR/<name>.html instead of stdout. Files
+                         with the same name can't go to the same DIR
+    -h, --help           Print this help";
+
+struct Options {
+    static_markup: bool,
+    out_dir: Option<PathBuf>,
+    inputs: Vec<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        static_markup: false,
+        out_dir: None,
+        inputs: Vec::new(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            "-s" | "--static" => options.static_markup = true,
+            "-o" | "--out-dir" => {
+                let dir = args.next()
+                    .ok_or_else(|| format!("{} requires a directory", arg))?;
+                options.out_dir = Some(PathBuf::from(dir));
+            },
+            "-" => options.inputs.push(arg),
+            _ if arg.starts_with('-') => {
+                return Err(format!("unknown option `{}`", arg));
+            },
+            _ => options.inputs.push(arg),
+        }
+    }
+    if options.inputs.is_empty() {
+        options.inputs.push("-".to_string());
+    }
+    if options.inputs.iter().filter(|i| *i == "-").count() > 1 {
+        return Err("stdin (`-`) can only be read once".to_string());
+    }
+    if let Some(ref out_dir) = options.out_dir {
+        if options.inputs.iter().any(|i| i == "-") {
+            return Err("--out-dir can not be used with stdin".to_string());
+        }
+        let mut outputs = HashMap::new();
+        for input in &options.inputs {
+            let path = output_path(out_dir, input.as_str());
+            if let Some(other) = outputs.insert(path.clone(), input) {
+                return Err(format!(
+                    "`{}` and `{}` would both be written to {}",
+                    other,
+                    input,
+                    path.display(),
+                ));
+            }
+        }
+    }
+    Ok(options)
+}
+
+fn read_tree(input: &str) -> Result<Node, String> {
+    if input == "-" {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|err| format!("<stdin>: {}", err))?;
+        json::from_str(source.as_str())
+            .map_err(|err| format!("<stdin>: invalid element tree: {}", err))
+    } else {
+        let file = File::open(input)
+            .map_err(|err| format!("{}: {}", input, err))?;
+        json::from_reader(io::BufReader::new(file))
+            .map_err(|err| format!("{}: invalid element tree: {}", input, err))
+    }
+}
+
+// Invalid names fail the render instead of being left out.
+fn render(node: &Node, static_markup: bool, input: &str) -> Result<String, String> {
+    let options = RenderOptions {
+        static_markup,
+        invalid_names: Some(InvalidNameAction::Abort),
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options)
+        .map(|result| result.html)
+        .map_err(|err| format!("{}: {}", input, err))
+}
+
+fn write_html<W: Write>(html: &str, mut writer: W, newline: bool) -> io::Result<()> {
+    writer.write_all(html.as_bytes())?;
+    if newline {
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+fn output_path(out_dir: &Path, input: &str) -> PathBuf {
+    let stem = Path::new(input)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "index".to_string());
+    out_dir.join(format!("{}.html", stem))
+}
+
+fn run(options: Options) -> Result<(), String> {
+    if let Some(ref out_dir) = options.out_dir {
+        fs::create_dir_all(out_dir)
+            .map_err(|err| format!("{}: {}", out_dir.display(), err))?;
+    }
+    let stdout = io::stdout();
+    let mut stdout = BufWriter::new(stdout.lock());
+    for input in &options.inputs {
+        let node = read_tree(input.as_str())?;
+        let name = if input == "-" { "<stdin>" } else { input.as_str() };
+        let html = render(&node, options.static_markup, name)?;
+        match options.out_dir {
+            Some(ref out_dir) => {
+                let path = output_path(out_dir, input.as_str());
+                File::create(&path)
+                    .and_then(|file| write_html(&html, BufWriter::new(file), false))
+                    .map_err(|err| format!("{}: {}", path.display(), err))?;
+            },
+            None => {
+                write_html(&html, &mut stdout, true)
+                    .map_err(|err| format!("<stdout>: {}", err))?;
+            },
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|err| {
+        eprintln!("react-neon-ssr: {}\n\n{}", err, USAGE);
+        process::exit(2);
+    });
+    if let Err(err) = run(options) {
+        eprintln!("react-neon-ssr: {}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,188 @@
+/**
+ * Bounded caches for values derived from prop, style and tag names.
+ *
+ * Those names come from user code, and sometimes from user data, so the
+ * caches must not grow forever. Each thread keeps its own entries, so
+ * lookups never take a lock. Only the capacity, a clear generation and the
+ * hit/miss counters are shared, as atomics. Memory use is bounded by
+ * `capacity` entries per rendering thread.
+ *
+ * When a cache is full the oldest entry is evicted first.
+ */
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub struct Cache {
+    pub name: &'static str,
+    capacity: AtomicUsize,
+    generation: AtomicUsize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    evictions: AtomicUsize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub capacity: usize,
+    pub hits: usize,
+    pub misses: usize,
+    pub evictions: usize,
+}
+
+pub static STYLE_NAME_CACHE: Cache = Cache::new("styleName", 1024);
+pub static VALIDATED_TAG_CACHE: Cache = Cache::new("validatedTag", 256);
+pub static ILLEGAL_TAG_CACHE: Cache = Cache::new("illegalTag", 256);
+pub static VALIDATED_ATTRIBUTE_NAME_CACHE: Cache = Cache::new(
+    "validatedAttributeName", 1024
+);
+pub static ILLEGAL_ATTRIBUTE_NAME_CACHE: Cache = Cache::new(
+    "illegalAttributeName", 256
+);
+
+static CACHES: [&Cache; 5] = [
+    &STYLE_NAME_CACHE,
+    &VALIDATED_TAG_CACHE,
+    &ILLEGAL_TAG_CACHE,
+    &VALIDATED_ATTRIBUTE_NAME_CACHE,
+    &ILLEGAL_ATTRIBUTE_NAME_CACHE,
+];
+
+impl Cache {
+    const fn new(name: &'static str, capacity: usize) -> Cache {
+        Cache {
+            name,
+            capacity: AtomicUsize::new(capacity),
+            generation: AtomicUsize::new(0),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            evictions: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            name: self.name,
+            capacity: self.capacity.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Caches always keep at least one entry per thread.
+    pub fn resize(&self, capacity: usize) {
+        self.capacity.store(capacity.max(1), Ordering::Relaxed);
+    }
+
+    /// Drops the entries of every thread, the next lookup on each thread
+    /// starts empty. Counters are kept.
+    pub fn clear(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/**
+ * One thread's entries of a `Cache`, keep it in a `thread_local!`.
+ */
+pub struct LocalCache<V> {
+    entries: HashMap<String, V>,
+    // Insertion order, for eviction.
+    order: VecDeque<String>,
+    generation: usize,
+}
+
+impl<V> Default for LocalCache<V> {
+    fn default() -> LocalCache<V> {
+        LocalCache {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            generation: 0,
+        }
+    }
+}
+
+impl<V> LocalCache<V> {
+    pub fn new() -> LocalCache<V> {
+        LocalCache::default()
+    }
+
+    fn sync(&mut self, cache: &Cache) {
+        let generation = cache.generation.load(Ordering::Relaxed);
+        if self.generation != generation {
+            self.entries.clear();
+            self.order.clear();
+            self.generation = generation;
+        }
+    }
+
+    pub fn contains(&mut self, cache: &Cache, key: &str) -> bool {
+        self.sync(cache);
+        let found = self.entries.contains_key(key);
+        let counter = if found { &cache.hits } else { &cache.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    pub fn insert(&mut self, cache: &Cache, key: &str, value: V) {
+        self.sync(cache);
+        let capacity = cache.capacity.load(Ordering::Relaxed);
+        if self.entries.contains_key(key) {
+            self.entries.insert(key.to_string(), value);
+            return;
+        }
+        while self.order.len() >= capacity {
+            match self.order.pop_front() {
+                Some(oldest) => self.entries.remove(&oldest),
+                None => break,
+            };
+            cache.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        self.entries.insert(key.to_string(), value);
+        self.order.push_back(key.to_string());
+    }
+
+    /// The cached value for `key`, computing and caching it on a miss.
+    pub fn get_or_insert_with<F>(&mut self, cache: &Cache, key: &str, make: F) -> &V
+        where F: FnOnce(&str) -> V
+    {
+        if !self.contains(cache, key) {
+            let value = make(key);
+            self.insert(cache, key, value);
+        }
+        &self.entries[key]
+    }
+}
+
+fn find(name: &str) -> Option<&'static Cache> {
+    CACHES.iter().cloned().find(|cache| cache.name == name)
+}
+
+pub fn stats() -> Vec<CacheStats> {
+    CACHES.iter().map(|cache| cache.stats()).collect()
+}
+
+/**
+ * Set the per-thread capacity of the cache called `name`, returns false if
+ * there is no such cache. Shrinking takes effect as threads insert new
+ * entries.
+ */
+pub fn resize(name: &str, capacity: usize) -> bool {
+    find(name).map(|cache| cache.resize(capacity)).is_some()
+}
+
+/**
+ * Clear the cache called `name`, or every cache if `name` is `None`.
+ * Returns false if there is no such cache.
+ */
+pub fn clear(name: Option<&str>) -> bool {
+    match name {
+        Some(name) => find(name).map(|cache| cache.clear()).is_some(),
+        None => {
+            for cache in CACHES.iter() {
+                cache.clear();
+            }
+            true
+        },
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use element::{Node, Props};
+use script_json;
+use serde_json;
+
+/**
+ * A component implemented in Rust. It must be a pure function of its props,
+ * the renderer may call it from any thread.
+ *
+ * Registered components are used from JS like any other element type, by
+ * name: `React.createElement("PriceTable", {rows})`. Their props are passed
+ * as structured `PropValue`s, `children` are already converted.
+ */
+pub trait Component: Send + Sync {
+    fn render(&self, props: &Props, children: &[Node]) -> Node;
+
+    /// Components that render the same markup for the same key return one
+    /// here, and the renderer reuses their HTML, see `component_cache`.
+    /// Keys only have to be unique per component.
+    fn cache_key(&self, _props: &Props, _children: &[Node]) -> Option<String> {
+        None
+    }
+
+    /// How long cached markup stays valid, forever by default.
+    fn cache_ttl(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl<F> Component for F
+    where F: Fn(&Props, &[Node]) -> Node + Send + Sync
+{
+    fn render(&self, props: &Props, children: &[Node]) -> Node {
+        self(props, children)
+    }
+}
+
+/**
+ * Caches a component's markup by its props and children, for components
+ * whose output depends on nothing else.
+ */
+pub struct Pure<C> {
+    component: C,
+    ttl: Option<Duration>,
+}
+
+impl<C: Component> Pure<C> {
+    pub fn new(component: C) -> Pure<C> {
+        Pure {
+            component,
+            ttl: None,
+        }
+    }
+
+    pub fn ttl(mut self, ttl: Duration) -> Pure<C> {
+        self.ttl = Some(ttl);
+        self
+    }
+}
+
+impl<C: Component> Component for Pure<C> {
+    fn render(&self, props: &Props, children: &[Node]) -> Node {
+        self.component.render(props, children)
+    }
+
+    fn cache_key(&self, props: &Props, children: &[Node]) -> Option<String> {
+        props_cache_key(props, children)
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+}
+
+/**
+ * A key that is equal exactly when `props` and `children` are, `None` if
+ * they can't be serialized.
+ */
+pub fn props_cache_key(props: &Props, children: &[Node]) -> Option<String> {
+    let props = serde_json::to_string(props).ok()?;
+    let children = serde_json::to_string(children).ok()?;
+    Some(props + "\u{0}" + children.as_str())
+}
+
+lazy_static! {
+    // Starts out with the built-in components.
+    static ref REGISTRY: Arc<RwLock<HashMap<String, Arc<dyn Component>>>> = {
+        let mut registry: HashMap<String, Arc<dyn Component>> = HashMap::new();
+        registry.insert("JsonScript".to_string(), Arc::new(script_json::json_script));
+        Arc::new(RwLock::new(registry))
+    };
+}
+
+/**
+ * Native component names must start with an uppercase letter, like JS
+ * component names, so they never shadow a DOM tag.
+ */
+pub fn is_component_name(name: &str) -> bool {
+    name.chars().next().map(|c| c.is_ascii_uppercase()).unwrap_or(false)
+}
+
+/**
+ * Register `component` under `name`, replacing any previous registration.
+ */
+pub fn register<C: Component + 'static>(name: &str, component: C) {
+    assert!(
+        is_component_name(name),
+        "Native component names must start with an uppercase letter: {}", name
+    );
+    REGISTRY.write().unwrap().insert(name.to_string(), Arc::new(component));
+}
+
+pub fn unregister(name: &str) -> bool {
+    REGISTRY.write().unwrap().remove(name).is_some()
+}
+
+pub fn get(name: &str) -> Option<Arc<dyn Component>> {
+    if !is_component_name(name) {
+        return None;
+    }
+    REGISTRY.read().unwrap().get(name).cloned()
+}
+
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = REGISTRY.read().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
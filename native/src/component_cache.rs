@@ -0,0 +1,258 @@
+/**
+ * Caches for the output of pure components, so a header that renders the
+ * same for every request is only rendered once.
+ *
+ * Components opt in with a cache key: `Component::cache_key` for native
+ * components, a static `cacheKey` for JS ones. The renderer keeps the HTML
+ * of each keyed subtree per rendering context (static markup, whether it is
+ * the root, namespace), along with whether it starts and ends with text, so
+ * text separators and `data-reactroot` come out as if it had been rendered.
+ *
+ * JS components are also cached one step earlier, as converted trees, so a
+ * hit doesn't call into JS at all.
+ *
+ * Entries expire after their TTL and the oldest are evicted once a cache
+ * holds more than `max_bytes` of output. Expired entries are dropped first.
+ */
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use element::{CachedNode, Node};
+use stats::RenderStats;
+use trusted_html::HtmlInjection;
+
+const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/**
+ * A component's markup, rendered as if nothing preceded it.
+ */
+#[derive(Debug)]
+pub struct CachedHtml {
+    pub html: String,
+    // Whether the first thing written was text, `None` if nothing was.
+    pub leading_text: Option<bool>,
+    pub trailing_text: bool,
+    // How deep the markup goes, relative to where it starts, see `Limits`.
+    pub depth: usize,
+    // The raw HTML in it, with component stacks relative to it.
+    pub injections: Vec<HtmlInjection>,
+    // What the markup holds, if counted, with `max_depth` relative to it.
+    pub stats: Option<RenderStats>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentCacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub max_bytes: usize,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+struct Entry<V> {
+    value: Arc<V>,
+    size: usize,
+    expires: Option<Instant>,
+    // Tells this entry apart from earlier ones under the same key.
+    generation: u64,
+}
+
+struct Store<V> {
+    entries: HashMap<String, Entry<V>>,
+    // Keys in insertion order, for eviction. Removing an entry leaves its
+    // key behind, it is skipped once its generation is gone.
+    order: VecDeque<(u64, String)>,
+    // Keys of entries with a TTL, the first to expire on top, skipped the
+    // same way.
+    expiry: BinaryHeap<Reverse<(Instant, u64, String)>>,
+    generation: u64,
+    bytes: usize,
+}
+
+impl<V> Store<V> {
+    fn new() -> Store<V> {
+        Store {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            expiry: BinaryHeap::new(),
+            generation: 0,
+            bytes: 0,
+        }
+    }
+
+    fn is_current(&self, generation: u64, key: &str) -> bool {
+        self.entries.get(key).is_some_and(|entry| entry.generation == generation)
+    }
+
+    fn get(&mut self, key: &str) -> Option<Arc<V>> {
+        let expired = match self.entries.get(key) {
+            Some(entry) => entry.expires.is_some_and(|at| at <= Instant::now()),
+            None => return None,
+        };
+        if expired {
+            self.remove(key);
+            return None;
+        }
+        self.entries.get(key).map(|entry| entry.value.clone())
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.bytes -= entry.size;
+        }
+    }
+
+    fn remove_expired(&mut self, now: Instant) {
+        while self.expiry.peek().is_some_and(|Reverse((expires, _, _))| *expires <= now) {
+            let Reverse((_, generation, key)) = self.expiry.pop().unwrap();
+            if self.is_current(generation, key.as_str()) {
+                self.remove(key.as_str());
+            }
+        }
+    }
+
+    // Drops the keys left behind by removed entries once they make up most
+    // of `order` or `expiry`.
+    fn compact(&mut self) {
+        let live = self.entries.len();
+        let entries = &self.entries;
+        let is_current = |generation: u64, key: &str| {
+            entries.get(key).is_some_and(|entry| entry.generation == generation)
+        };
+        if self.order.len() > 2 * live + 16 {
+            self.order.retain(|(generation, key)| is_current(*generation, key));
+        }
+        if self.expiry.len() > 2 * live + 16 {
+            self.expiry.retain(|Reverse((_, generation, key))| is_current(*generation, key));
+        }
+    }
+
+    fn insert(&mut self, key: String, value: Arc<V>, size: usize, ttl: Option<Duration>) {
+        let max_bytes = MAX_BYTES.load(Ordering::Relaxed);
+        if size > max_bytes {
+            return;
+        }
+        let now = Instant::now();
+        self.remove(key.as_str());
+        // Expired entries go first, so they don't push out live ones.
+        self.remove_expired(now);
+        while self.bytes + size > max_bytes {
+            match self.order.pop_front() {
+                Some((generation, oldest)) => {
+                    if self.is_current(generation, oldest.as_str()) {
+                        self.remove(oldest.as_str());
+                    }
+                },
+                None => break,
+            }
+        }
+        self.generation += 1;
+        let generation = self.generation;
+        let expires = ttl.map(|ttl| now + ttl);
+        if let Some(expires) = expires {
+            self.expiry.push(Reverse((expires, generation, key.clone())));
+        }
+        self.bytes += size;
+        self.order.push_back((generation, key.clone()));
+        self.entries.insert(key, Entry {
+            value,
+            size,
+            expires,
+            generation,
+        });
+        self.compact();
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.expiry.clear();
+        self.bytes = 0;
+    }
+}
+
+static MAX_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_BYTES);
+static HITS: AtomicUsize = AtomicUsize::new(0);
+static MISSES: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref HTML: Mutex<Store<CachedHtml>> = Mutex::new(Store::new());
+    static ref NODES: Mutex<Store<CachedNode>> = Mutex::new(Store::new());
+}
+
+fn count(found: bool) {
+    let counter = if found { &HITS } else { &MISSES };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn get_html(key: &str) -> Option<Arc<CachedHtml>> {
+    let html = HTML.lock().unwrap().get(key);
+    count(html.is_some());
+    html
+}
+
+pub fn insert_html(key: String, html: Arc<CachedHtml>, ttl: Option<Duration>) {
+    let size = key.len() + html.html.len();
+    HTML.lock().unwrap().insert(key, html, size, ttl);
+}
+
+pub fn get_node(key: &str) -> Option<Arc<CachedNode>> {
+    let node = NODES.lock().unwrap().get(key);
+    count(node.is_some());
+    node
+}
+
+pub fn insert_node(node: Arc<CachedNode>) {
+    let size = node.key.len() + node_size(&node.node);
+    let key = node.key.clone();
+    let ttl = node.ttl;
+    NODES.lock().unwrap().insert(key, node, size, ttl);
+}
+
+// Roughly the bytes a tree holds, its strings dominate.
+fn node_size(node: &Node) -> usize {
+    match *node {
+        Node::Element(ref element) => {
+            let props: usize = element.props
+                .iter()
+                .map(|(name, value)| name.len() + value.to_string().len())
+                .sum();
+            let children: usize = element.children.iter().map(node_size).sum();
+            element.tag.len() + props + children
+        },
+        Node::Text(ref text) | Node::RawHtml(ref text) | Node::TrustedHtml(ref text) => {
+            text.len()
+        },
+        Node::Fragment(ref nodes) => nodes.iter().map(node_size).sum(),
+        Node::Cached(ref cached) => node_size(&cached.node),
+        Node::Component(ref component) => node_size(&component.node),
+    }
+}
+
+pub fn stats() -> ComponentCacheStats {
+    let html = HTML.lock().unwrap();
+    let nodes = NODES.lock().unwrap();
+    ComponentCacheStats {
+        entries: html.entries.len() + nodes.entries.len(),
+        bytes: html.bytes + nodes.bytes,
+        max_bytes: MAX_BYTES.load(Ordering::Relaxed),
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    }
+}
+
+/**
+ * Limit the HTML and the converted trees to `max_bytes` each. Shrinking
+ * takes effect as new entries are inserted.
+ */
+pub fn set_max_bytes(max_bytes: usize) {
+    MAX_BYTES.store(max_bytes, Ordering::Relaxed);
+}
+
+pub fn clear() {
+    HTML.lock().unwrap().clear();
+    NODES.lock().unwrap().clear();
+}
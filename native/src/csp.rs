@@ -0,0 +1,44 @@
+/**
+ * Content Security Policy support. A render can stamp a per-request nonce on
+ * its `<script>` and `<style>` elements, and hash the bodies of the inline
+ * ones for a policy that lists them instead.
+ */
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use sha2::{Digest, Sha256};
+
+/**
+ * Hash sources of the inline scripts and styles rendered, like
+ * `sha256-B2yPHKaXnvFWtRChIbabYmUBFZdVfKKXHbWtWidDVF8=`, in render order and
+ * without duplicates. Quoted, they go in `script-src` and `style-src`.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InlineHashes {
+    pub scripts: Vec<String>,
+    pub styles: Vec<String>,
+}
+
+impl InlineHashes {
+    pub fn new() -> InlineHashes {
+        InlineHashes::default()
+    }
+
+    /// Adds the body of an inline `script` or `style` element.
+    pub fn add(&mut self, tag: &str, body: &str) {
+        let hashes = match tag {
+            "script" => &mut self.scripts,
+            "style" => &mut self.styles,
+            _ => return,
+        };
+        let source = sha256_source(body);
+        if !hashes.contains(&source) {
+            hashes.push(source);
+        }
+    }
+}
+
+/// The CSP hash source of `body`, without its quotes.
+pub fn sha256_source(body: &str) -> String {
+    let digest = Sha256::digest(body.as_bytes());
+    format!("sha256-{}", STANDARD.encode(digest))
+}
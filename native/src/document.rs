@@ -0,0 +1,69 @@
+/**
+ * A complete HTML document around the app. React can't render a doctype, so
+ * servers used to prepend one by hand. With `RenderOptions::document` the
+ * renderer writes the shell itself:
+ *
+ *   <!DOCTYPE html><html lang="en"><head>{head}</head>
+ *   <body class="app">{app}{scripts}</body></html>
+ *
+ * The `head` elements are collected like those of `<Head>` elements (see
+ * `head`), and come first, so the app's replace them: they work as defaults.
+ * The `scripts` are written after the app. Neither are roots, the app is
+ * rendered into the body as if it were the whole tree, and should not render
+ * `<html>` itself.
+ */
+use element::{Node, PropValue, Props};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    // `<!DOCTYPE html>` by default, `None` for no doctype.
+    pub doctype: Option<String>,
+    pub html_attributes: Props,
+    pub body_attributes: Props,
+    pub head: Vec<Node>,
+    pub scripts: Vec<Node>,
+}
+
+impl Default for Document {
+    fn default() -> Document {
+        Document {
+            doctype: Some("html".to_string()),
+            html_attributes: Props::new(),
+            body_attributes: Props::new(),
+            head: Vec::new(),
+            scripts: Vec::new(),
+        }
+    }
+}
+
+impl Document {
+    pub fn new() -> Document {
+        Document::default()
+    }
+
+    pub fn html_attribute<S, V>(mut self, name: S, value: V) -> Document
+        where S: Into<String>,
+              V: Into<PropValue>,
+    {
+        self.html_attributes.insert(name, value.into());
+        self
+    }
+
+    pub fn body_attribute<S, V>(mut self, name: S, value: V) -> Document
+        where S: Into<String>,
+              V: Into<PropValue>,
+    {
+        self.body_attributes.insert(name, value.into());
+        self
+    }
+
+    pub fn head(mut self, node: Node) -> Document {
+        self.head.push(node);
+        self
+    }
+
+    pub fn script(mut self, node: Node) -> Document {
+        self.scripts.push(node);
+        self
+    }
+}
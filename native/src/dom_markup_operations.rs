@@ -1,32 +1,25 @@
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
+use std::cell::RefCell;
 
 use regex::Regex;
-use neon::scope::RootScope;
-use neon::mem::Handle;
-use neon::js::{
-    JsValue,
-    JsNull,
-    JsBoolean,
-    JsNumber,
-};
 
+use cache::{
+    LocalCache,
+    ILLEGAL_ATTRIBUTE_NAME_CACHE,
+    VALIDATED_ATTRIBUTE_NAME_CACHE,
+};
+use element::PropValue;
+use sink::Sink;
 
 use util::dom_property::{
     ATTRIBUTE_NAME_CHAR,
     ATTRIBUTE_NAME_START_CHAR,
-    ID_ATTRIBUTE_NAME,
     ROOT_ATTRIBUTE_NAME,
     get_property_info,
     should_attribute_accept_boolean_value,
     should_set_attribute,
 };
 use util::dom_property::PropertyInfo;
-use util::{
-    not,
-    is_nan,
-    quote_attribute_value_for_browser
-};
+use util::quote_attribute_value_for_browser_into;
 
 
 lazy_static! {
@@ -41,32 +34,54 @@ lazy_static! {
         );
         Regex::new(pattern.as_str()).unwrap()
     };
-    static ref ILLEGAL_ATTRIBUTE_NAME_CACHE: Arc<Mutex<HashSet<String>>> = {
-        Arc::new(Mutex::new(HashSet::new()))
+}
+
+thread_local! {
+    static VALIDATED_ATTRIBUTE_NAMES: RefCell<LocalCache<()>> = {
+        RefCell::new(LocalCache::new())
     };
-    static ref VALIDATED_ATTRIBUTE_NAME_CACHE: Arc<Mutex<HashSet<String>>> = {
-        Arc::new(Mutex::new(HashSet::new()))
+    static ILLEGAL_ATTRIBUTE_NAMES: RefCell<LocalCache<()>> = {
+        RefCell::new(LocalCache::new())
     };
 }
 
-fn is_attribute_name_safe(attribute_name: &str) -> bool {
-    let mut validated_cache = VALIDATED_ATTRIBUTE_NAME_CACHE.lock().unwrap();
-    if validated_cache.contains(attribute_name) {
+/**
+ * Whether `attribute_name` can be written as is. With `warn`, the first time
+ * an illegal name is seen on this thread prints a warning.
+ */
+pub fn is_attribute_name_safe(attribute_name: &str, warn: bool) -> bool {
+    VALIDATED_ATTRIBUTE_NAMES.with(|validated_cache| {
+        ILLEGAL_ATTRIBUTE_NAMES.with(|illegal_cache| {
+            check_attribute_name(
+                &mut validated_cache.borrow_mut(),
+                &mut illegal_cache.borrow_mut(),
+                attribute_name,
+                warn,
+            )
+        })
+    })
+}
+
+fn check_attribute_name(
+    validated_cache: &mut LocalCache<()>,
+    illegal_cache: &mut LocalCache<()>,
+    attribute_name: &str,
+    warn: bool,
+) -> bool {
+    if validated_cache.contains(&VALIDATED_ATTRIBUTE_NAME_CACHE, attribute_name) {
         return true;
     }
-    let mut illegal_cache = ILLEGAL_ATTRIBUTE_NAME_CACHE.lock().unwrap();
-    if illegal_cache.contains(attribute_name) {
-        return true;
+    if illegal_cache.contains(&ILLEGAL_ATTRIBUTE_NAME_CACHE, attribute_name) {
+        return false;
     }
     if VALID_ATTRIBUTE_NAME_REGEX.is_match(attribute_name) {
-        validated_cache.insert(attribute_name.to_string());
+        validated_cache.insert(&VALIDATED_ATTRIBUTE_NAME_CACHE, attribute_name, ());
         return true;
     }
-    // TODO:
-    // if (__DEV__) {
-    //     warning(false, 'Invalid attribute name: `%s`', attributeName);
-    // }
-    illegal_cache.insert(attribute_name.to_string());
+    if warn {
+        eprintln!("Warning: Invalid attribute name: `{}`", attribute_name);
+    }
+    illegal_cache.insert(&ILLEGAL_ATTRIBUTE_NAME_CACHE, attribute_name, ());
     false
 }
 
@@ -74,91 +89,75 @@ fn is_attribute_name_safe(attribute_name: &str) -> bool {
 // TODO: Find a better place for this.
 fn should_ignore_value(
     info: &PropertyInfo,
-    value: Handle<JsValue>
+    value: &PropValue
 ) -> bool {
-    value.is_a::<JsNull>()
-        || (info.has_boolean_value && not(value))
-        || (info.has_numeric_value && is_nan(value))
-        || (info.has_positive_numeric_value
-            && value.downcast::<JsNumber>().unwrap().value() < 1.0)
+    value.is_null()
+        || (info.has_boolean_value && !value.is_truthy())
+        || (info.has_numeric_value && value.to_number().is_nan())
+        || (info.has_positive_numeric_value && value.to_number() < 1.0)
         || (info.has_overloaded_boolean_value
-            && value.downcast::<JsBoolean>().unwrap().value() == false)
+            && *value == PropValue::Bool(false))
 }
 
-/**
- * Creates markup for the ID property.
- *
- * @param {string} id Unescaped ID.
- * @return {string} Markup string.
- */
-pub fn create_markup_for_id(
-    scope: &mut RootScope,
-    id: Handle<JsValue>
-) -> String {
-    let quoted_id = quote_attribute_value_for_browser(scope, id);
-    format!("{}={}", ID_ATTRIBUTE_NAME, quoted_id)
+// ` name="value"`, quoting straight into the output.
+fn write_attribute(out: &mut dyn Sink, name: &str, value: &PropValue) {
+    out.write_char(' ');
+    out.write_str(name);
+    out.write_char('=');
+    quote_attribute_value_for_browser_into(out, value);
 }
 
-pub fn create_markup_for_root() -> String {
-    MARKUP_FOR_ROOT.clone()
+pub fn create_markup_for_root(out: &mut dyn Sink) {
+    out.write_char(' ');
+    out.write_str(MARKUP_FOR_ROOT.as_str());
 }
 
 /**
- * Creates markup for a property.
+ * Writes markup for a property, including the space separating it from the
+ * previous one. Writes nothing if the property was invalid or its value is
+ * ignored.
  *
  * @param {string} name
  * @param {*} value
- * @return {?string} Markup string, or null if the property was invalid.
  */
 pub fn create_markup_for_property(
-    scope: &mut RootScope,
+    out: &mut dyn Sink,
     name: &str,
-    value: Handle<JsValue>
-) -> Option<String> {
-    // TODO:
+    value: &PropValue
+) {
     let property_info = get_property_info(name);
     if let Some(info) = property_info {
         if should_ignore_value(info, value) {
-            return Some("".to_string());
+            return;
         }
-        let is_boolean = value.is_a::<JsBoolean>();
+        let is_boolean = matches!(*value, PropValue::Bool(_));
         if info.has_boolean_value || (
             info.has_overloaded_boolean_value
-                && is_boolean
-                && value.downcast::<JsBoolean>().unwrap().value() == true
+                && *value == PropValue::Bool(true)
         ) {
-            return Some(format!("{}=\"\"", info.attribute_name));
+            out.write_char(' ');
+            out.write_str(info.attribute_name.as_str());
+            out.write_str("=\"\"");
         } else if !is_boolean || should_attribute_accept_boolean_value(name) {
-            let quoted_value = quote_attribute_value_for_browser(scope, value);
-            return Some(format!("{}={}", info.attribute_name, quoted_value));
-        }
-    } else if should_set_attribute(name, value) {
-        if value.is_a::<JsNull>() {
-            return Some("".to_string());
+            write_attribute(out, &info.attribute_name, value);
         }
-        let quoted_value = quote_attribute_value_for_browser(scope, value);
-        return Some(format!("{}={}", name, quoted_value));
+    } else if should_set_attribute(name, value) && !value.is_null() {
+        write_attribute(out, name, value);
     }
-    None
 }
 
 /**
- * Creates markup for a custom property.
+ * Writes markup for a custom property, like `create_markup_for_property`.
  *
  * @param {string} name
  * @param {*} value
- * @return {string} Markup string, or empty string if the property was invalid.
  */
 pub fn create_markup_for_custom_attribute(
-    scope: &mut RootScope,
+    out: &mut dyn Sink,
     name: &str,
-    value: Handle<JsValue>,
-) -> String {
-    // TODO:
-    if !is_attribute_name_safe(name) || value.is_a::<JsNull>() {
-        "".to_string()
-    } else {
-        let quoted_value = quote_attribute_value_for_browser(scope, value);
-        format!("{}={}", name, quoted_value)
+    value: &PropValue,
+) {
+    if is_attribute_name_safe(name, false) && !value.is_null() {
+        write_attribute(out, name, value);
     }
 }
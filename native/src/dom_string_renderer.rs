@@ -1,22 +1,599 @@
-use neon::vm::{Call, JsResult};
+use std::time::{Duration, Instant};
+
+use neon::vm::{Call, JsResult, VmResult};
+use neon::scope::RootScope;
+use neon::mem::{Handle, Managed};
+use neon::js::error::{JsError, Kind};
 use neon::js::{
+    JsArray,
+    JsBoolean,
+    JsNull,
+    JsNumber,
+    JsObject,
     JsString,
+    JsUndefined,
+    JsValue,
+    Object,
 };
 
-use partial_renderer::{ReadSize, DomServerRenderer};
+use component;
+use assets::AssetManifest;
+use csp::InlineHashes;
+use document::Document;
+use element::{Node, PropValue, Props};
+use error::component_stack_string;
+use js_element::{Conversion, throw_render_error, to_node};
+use neon_runtime::raw::Local;
+use name_safety::{self, InvalidName, InvalidNameAction};
+use partial_renderer::{RenderOptions, render_with_options};
+use profile::Profile;
+use stats::RenderStats;
+use style_safety::{self, UnsafeStyle, UnsafeStyleAction};
+use trusted_html::HtmlInjection;
+use sanitize::{self, Sanitizer};
+use script_json::to_script_json;
+use url_policy::{self, UnsafeUrl, UnsafeUrlAction, UrlPolicy};
+use util::js_value::to_prop_value;
+
+// `renderToString(element, options)`. `options` is either a route, which
+// sizes the output buffer after previous renders of the same route, or an
+// object `{route, profile, trace, stats, maxDepth, maxBytes, timeout, nonce,
+// inlineHashes, trustedHTMLOnly, htmlInjections, styleSafety, invalidNames,
+// collectHead, assets}`, in which case the result is an object too: `{html,
+// profile, trace, stats, unsafeUrls, unsafeStyles, invalidNames,
+// inlineHashes, htmlInjections, head, scripts}`, `head` being the collected
+// head markup if the tree had no `<head>` for it, `scripts` the asset scripts
+// if it had no `<body>`. `assets` is `{manifest, entrypoints, publicPath}`,
+// see `read_assets`. `trace` is `true` for the trace's JSON in the result, or
+// a path to write it to. A render past one of the limits throws a
+// `RenderLimitError`, one with an unsafe URL may throw an `UnsafeUrlError`
+// (see `setUrlPolicy`), one with an unsafe style an `UnsafeStyleError` (see
+// `setStyleSafety`) and one with an invalid tag or attribute name an
+// `InvalidNameError` (see `setInvalidNameAction`). One with `trustedHTMLOnly` throws an
+// `UntrustedHtmlError` for HTML that wasn't marked with `trustHTML`.
+fn render(call: Call, static_markup: bool) -> JsResult<JsValue> {
+    let element = call.arguments.require(call.scope, 0)?.to_raw();
+    let mut options = RenderOptions {
+        static_markup,
+        ..RenderOptions::default()
+    };
+    let mut detailed = false;
+    let mut trace_path = None;
+    if let Some(arg) = call.arguments.get(call.scope, 1) {
+        if let Some(route) = arg.downcast::<JsString>() {
+            options.route = Some(route.value());
+        } else if let Some(object) = arg.downcast::<JsObject>() {
+            trace_path = read_options(call.scope, object, &mut options)?;
+            detailed = true;
+        }
+    }
+    let mut cx = conversion(detailed, &options);
+    let node = to_node(call.scope, element, &mut cx)?;
+    render_node(call.scope, &node, &options, detailed, trace_path)
+}
+
+/**
+ * `renderDocument(element, options)`, a complete HTML document with the
+ * element rendered into its body, see `document`. `options` are those of
+ * `renderToString` and `{staticMarkup, doctype, htmlAttributes,
+ * bodyAttributes, head, scripts, detailed}`: `doctype` is `'html'` unless
+ * given, `null` for none, `head` and `scripts` are elements or arrays of
+ * them. The result is the HTML, or with `detailed`, an object like
+ * `renderToString`'s.
+ */
+pub fn render_document(call: Call) -> JsResult<JsValue> {
+    let element = call.arguments.require(call.scope, 0)?.to_raw();
+    let mut options = RenderOptions::default();
+    let mut document = Document::new();
+    let mut detailed = false;
+    let mut trace_path = None;
+    let object = call.arguments.get(call.scope, 1)
+        .and_then(|arg| arg.downcast::<JsObject>());
+    if let Some(object) = object {
+        trace_path = read_options(call.scope, object, &mut options)?;
+        options.static_markup = object.get(call.scope, "staticMarkup")?
+            .downcast::<JsBoolean>()
+            .map_or(false, |static_markup| static_markup.value());
+        detailed = object.get(call.scope, "detailed")?
+            .downcast::<JsBoolean>()
+            .map_or(false, |detailed| detailed.value());
+        let doctype = object.get(call.scope, "doctype")?;
+        if let Some(doctype) = doctype.downcast::<JsString>() {
+            document.doctype = Some(doctype.value());
+        } else if doctype.is_a::<JsNull>() {
+            document.doctype = None;
+        }
+        let attributes = object.get(call.scope, "htmlAttributes")?.to_raw();
+        document.html_attributes = attributes_from_js(call.scope, attributes)?;
+        let attributes = object.get(call.scope, "bodyAttributes")?.to_raw();
+        document.body_attributes = attributes_from_js(call.scope, attributes)?;
+    }
+    let mut cx = conversion(detailed, &options);
+    if let Some(object) = object {
+        let head = object.get(call.scope, "head")?.to_raw();
+        document.head.push(to_node(call.scope, head, &mut cx)?);
+        let scripts = object.get(call.scope, "scripts")?.to_raw();
+        document.scripts.push(to_node(call.scope, scripts, &mut cx)?);
+    }
+    let node = to_node(call.scope, element, &mut cx)?;
+    options.document = Some(document);
+    render_node(call.scope, &node, &options, detailed, trace_path)
+}
+
+// The props of a plain object, none for anything else.
+fn attributes_from_js(scope: &mut RootScope, value: Local) -> VmResult<Props> {
+    match to_prop_value(scope, value)? {
+        Some(PropValue::Object(props)) => Ok(props),
+        _ => Ok(Props::new()),
+    }
+}
+
+fn conversion(detailed: bool, options: &RenderOptions) -> Conversion {
+    // Unsafe URLs and styles are reported with the JS components they are in.
+    let mark = detailed
+        || url_policy::policy().action == UnsafeUrlAction::Abort
+        || style_safety::action() == Some(UnsafeStyleAction::Abort)
+        || name_safety::action() == InvalidNameAction::Abort;
+    Conversion::new(mark, options.limits.clone())
+}
+
+// Renders `node`, into the HTML, or a detailed result.
+fn render_node<'a>(
+    scope: &mut RootScope<'a>,
+    node: &Node,
+    options: &RenderOptions,
+    detailed: bool,
+    trace_path: Option<String>,
+) -> JsResult<'a, JsValue> {
+    let result = match render_with_options(node, options) {
+        Ok(result) => result,
+        Err(error) => return throw_render_error(scope, &error),
+    };
+    if let (Some(path), Some(trace)) = (trace_path.as_ref(), result.trace.as_ref()) {
+        if let Err(err) = trace.write_to_file(path) {
+            let message = format!("Could not write the trace to {}: {}", path, err);
+            return JsError::throw(Kind::Error, message.as_str());
+        }
+    }
+    let html = JsString::new_or_throw(scope, result.html.as_str())?;
+    if !detailed {
+        return Ok(html.upcast());
+    }
+    let object: Handle<JsObject> = JsObject::new(scope);
+    object.set("html", html)?;
+    if let Some(ref profile) = result.profile {
+        object.set("profile", profiles_to_js(scope, profile)?)?;
+    }
+    if let Some(ref stats) = result.stats {
+        object.set("stats", stats_to_js(scope, stats)?)?;
+    }
+    if let (None, Some(trace)) = (trace_path, result.trace) {
+        let trace = JsString::new_or_throw(scope, trace.to_json().as_str())?;
+        object.set("trace", trace)?;
+    }
+    object.set("unsafeUrls", unsafe_urls_to_js(scope, &result.unsafe_urls)?)?;
+    object.set("unsafeStyles", unsafe_styles_to_js(scope, &result.unsafe_styles)?)?;
+    object.set("invalidNames", invalid_names_to_js(scope, &result.invalid_names)?)?;
+    if let Some(ref hashes) = result.inline_hashes {
+        object.set("inlineHashes", inline_hashes_to_js(scope, hashes)?)?;
+    }
+    if let Some(ref head) = result.head {
+        object.set("head", JsString::new_or_throw(scope, head.as_str())?)?;
+    }
+    if let Some(ref scripts) = result.scripts {
+        object.set("scripts", JsString::new_or_throw(scope, scripts.as_str())?)?;
+    }
+    if let Some(ref injections) = result.html_injections {
+        object.set("htmlInjections", html_injections_to_js(scope, injections)?)?;
+    }
+    Ok(object.upcast())
+}
+
+// Returns where to write the trace, if anywhere.
+fn read_options(
+    scope: &mut RootScope,
+    object: Handle<JsObject>,
+    options: &mut RenderOptions,
+) -> VmResult<Option<String>> {
+    if let Some(route) = object.get(scope, "route")?.downcast::<JsString>() {
+        options.route = Some(route.value());
+    }
+    options.profile = object.get(scope, "profile")?
+        .downcast::<JsBoolean>()
+        .map_or(false, |profile| profile.value());
+    options.stats = object.get(scope, "stats")?
+        .downcast::<JsBoolean>()
+        .map_or(false, |stats| stats.value());
+    if let Some(nonce) = object.get(scope, "nonce")?.downcast::<JsString>() {
+        options.nonce = Some(nonce.value());
+    }
+    options.inline_hashes = object.get(scope, "inlineHashes")?
+        .downcast::<JsBoolean>()
+        .map_or(false, |inline_hashes| inline_hashes.value());
+    options.trusted_html_only = object.get(scope, "trustedHTMLOnly")?
+        .downcast::<JsBoolean>()
+        .map_or(false, |trusted_html_only| trusted_html_only.value());
+    options.html_injections = object.get(scope, "htmlInjections")?
+        .downcast::<JsBoolean>()
+        .map_or(false, |html_injections| html_injections.value());
+    if let Some(action) = object.get(scope, "styleSafety")?.downcast::<JsString>() {
+        options.style_safety = Some(unsafe_style_action(action.value().as_str())?);
+    }
+    options.collect_head = object.get(scope, "collectHead")?
+        .downcast::<JsBoolean>()
+        .map_or(false, |collect_head| collect_head.value());
+    if let Some(action) = object.get(scope, "invalidNames")?.downcast::<JsString>() {
+        options.invalid_names = Some(invalid_name_action(action.value().as_str())?);
+    }
+    if let Some(assets) = object.get(scope, "assets")?.downcast::<JsObject>() {
+        read_assets(scope, assets, options)?;
+    }
+    let max_depth = object.get(scope, "maxDepth")?.downcast::<JsNumber>();
+    options.limits.max_depth = max_depth.map(|max_depth| max_depth.value() as usize);
+    let max_bytes = object.get(scope, "maxBytes")?.downcast::<JsNumber>();
+    options.limits.max_bytes = max_bytes.map(|max_bytes| max_bytes.value() as usize);
+    if let Some(timeout) = object.get(scope, "timeout")?.downcast::<JsNumber>() {
+        let timeout = Duration::from_millis(timeout.value().max(0.0) as u64);
+        options.limits.deadline = Some(Instant::now() + timeout);
+    }
+    let trace = object.get(scope, "trace")?;
+    if let Some(path) = trace.downcast::<JsString>() {
+        options.trace = true;
+        return Ok(Some(path.value()));
+    }
+    options.trace = trace.downcast::<JsBoolean>().map_or(false, |trace| trace.value());
+    Ok(None)
+}
+
+// `{manifest, entrypoints, publicPath}`: the manifest is parsed or JSON (see
+// `assets`), the entrypoints a name or an array of them, `main` by default.
+// Throws a `TypeError` for JSON that doesn't parse or an unknown entrypoint.
+fn read_assets(
+    scope: &mut RootScope,
+    object: Handle<JsObject>,
+    options: &mut RenderOptions,
+) -> VmResult<()> {
+    let manifest = object.get(scope, "manifest")?;
+    let manifest = match manifest.downcast::<JsString>() {
+        Some(json) => match AssetManifest::parse(json.value().as_str()) {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                let message = format!("Invalid asset manifest: {}", err);
+                return JsError::throw(Kind::TypeError, message.as_str());
+            },
+        },
+        None => {
+            let manifest = to_prop_value(scope, manifest.to_raw())?;
+            AssetManifest::from_prop_value(&manifest.unwrap_or(PropValue::Null))
+        },
+    };
+    let entrypoints = object.get(scope, "entrypoints")?;
+    let entrypoints = match entrypoints.downcast::<JsString>() {
+        Some(entrypoint) => vec![entrypoint.value()],
+        None => strings_from_js(scope, entrypoints)?
+            .unwrap_or_else(|| vec!["main".to_string()]),
+    };
+    let entrypoints: Vec<&str> = entrypoints.iter().map(String::as_str).collect();
+    let public_path = object.get(scope, "publicPath")?
+        .downcast::<JsString>()
+        .map_or(String::new(), |public_path| public_path.value());
+    match manifest.assets(&entrypoints, public_path.as_str()) {
+        Ok(assets) => options.assets = Some(assets),
+        Err(err) => return JsError::throw(Kind::TypeError, err.to_string().as_str()),
+    }
+    Ok(())
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1e3 + duration.subsec_nanos() as f64 / 1e6
+}
+
+// `[{name, selfTime, totalTime, calls, bytes, children}, ...]`, times in ms.
+fn profiles_to_js<'a>(
+    scope: &mut RootScope<'a>,
+    profiles: &[Profile],
+) -> JsResult<'a, JsArray> {
+    let array: Handle<JsArray> = JsArray::new(scope, profiles.len() as u32);
+    for (i, profile) in profiles.iter().enumerate() {
+        let object: Handle<JsObject> = JsObject::new(scope);
+        object.set("name", JsString::new_or_throw(scope, profile.name.as_str())?)?;
+        object.set("selfTime", JsNumber::new(scope, milliseconds(profile.self_time)))?;
+        object.set("totalTime", JsNumber::new(scope, milliseconds(profile.total_time)))?;
+        object.set("calls", JsNumber::new(scope, profile.calls as f64))?;
+        object.set("bytes", JsNumber::new(scope, profile.bytes as f64))?;
+        object.set("children", profiles_to_js(scope, &profile.children)?)?;
+        array.set(i as u32, object)?;
+    }
+    Ok(array)
+}
+
+// `{elements, textNodes, maxDepth, styleBytes, innerHTMLBytes, attributes,
+// components}`, `attributes` counts by prop name, `components` is
+// `[{name, bytes}, ...]`.
+fn stats_to_js<'a>(
+    scope: &mut RootScope<'a>,
+    stats: &RenderStats,
+) -> JsResult<'a, JsObject> {
+    let object: Handle<JsObject> = JsObject::new(scope);
+    object.set("elements", JsNumber::new(scope, stats.elements as f64))?;
+    object.set("textNodes", JsNumber::new(scope, stats.text_nodes as f64))?;
+    object.set("maxDepth", JsNumber::new(scope, stats.max_depth as f64))?;
+    object.set("styleBytes", JsNumber::new(scope, stats.style_bytes as f64))?;
+    object.set("innerHTMLBytes", JsNumber::new(scope, stats.inner_html_bytes as f64))?;
+    let attributes: Handle<JsObject> = JsObject::new(scope);
+    for (name, count) in &stats.attributes {
+        attributes.set(name.as_str(), JsNumber::new(scope, *count as f64))?;
+    }
+    object.set("attributes", attributes)?;
+    let components: Handle<JsArray> = JsArray::new(scope, stats.components.len() as u32);
+    for (i, &(ref name, bytes)) in stats.components.iter().enumerate() {
+        let component: Handle<JsObject> = JsObject::new(scope);
+        component.set("name", JsString::new_or_throw(scope, name.as_str())?)?;
+        component.set("bytes", JsNumber::new(scope, bytes as f64))?;
+        components.set(i as u32, component)?;
+    }
+    object.set("components", components)?;
+    Ok(object)
+}
+
+// `[{tag, attribute, url, componentStack}, ...]`.
+fn unsafe_urls_to_js<'a>(
+    scope: &mut RootScope<'a>,
+    unsafe_urls: &[UnsafeUrl],
+) -> JsResult<'a, JsArray> {
+    let array: Handle<JsArray> = JsArray::new(scope, unsafe_urls.len() as u32);
+    for (i, unsafe_url) in unsafe_urls.iter().enumerate() {
+        let object: Handle<JsObject> = JsObject::new(scope);
+        object.set("tag", JsString::new_or_throw(scope, unsafe_url.tag.as_str())?)?;
+        object.set("attribute", JsString::new_or_throw(scope, unsafe_url.attribute.as_str())?)?;
+        object.set("url", JsString::new_or_throw(scope, unsafe_url.url.as_str())?)?;
+        let stack = component_stack_string(&unsafe_url.component_stack);
+        object.set("componentStack", JsString::new_or_throw(scope, stack.as_str())?)?;
+        array.set(i as u32, object)?;
+    }
+    Ok(array)
+}
+
+// `[{tag, property, value, threat, componentStack}, ...]`.
+fn unsafe_styles_to_js<'a>(
+    scope: &mut RootScope<'a>,
+    unsafe_styles: &[UnsafeStyle],
+) -> JsResult<'a, JsArray> {
+    let array: Handle<JsArray> = JsArray::new(scope, unsafe_styles.len() as u32);
+    for (i, unsafe_style) in unsafe_styles.iter().enumerate() {
+        let object: Handle<JsObject> = JsObject::new(scope);
+        object.set("tag", JsString::new_or_throw(scope, unsafe_style.tag.as_str())?)?;
+        object.set("property", JsString::new_or_throw(scope, unsafe_style.property.as_str())?)?;
+        object.set("value", JsString::new_or_throw(scope, unsafe_style.value.as_str())?)?;
+        object.set("threat", JsString::new_or_throw(scope, unsafe_style.threat.name())?)?;
+        let stack = component_stack_string(&unsafe_style.component_stack);
+        object.set("componentStack", JsString::new_or_throw(scope, stack.as_str())?)?;
+        array.set(i as u32, object)?;
+    }
+    Ok(array)
+}
+
+// `[{kind, name, tag, componentStack}, ...]`, `kind` being 'tag' or
+// 'attribute'.
+fn invalid_names_to_js<'a>(
+    scope: &mut RootScope<'a>,
+    invalid_names: &[InvalidName],
+) -> JsResult<'a, JsArray> {
+    let array: Handle<JsArray> = JsArray::new(scope, invalid_names.len() as u32);
+    for (i, invalid_name) in invalid_names.iter().enumerate() {
+        let object: Handle<JsObject> = JsObject::new(scope);
+        object.set("kind", JsString::new_or_throw(scope, invalid_name.kind.name())?)?;
+        object.set("name", JsString::new_or_throw(scope, invalid_name.name.as_str())?)?;
+        object.set("tag", JsString::new_or_throw(scope, invalid_name.tag.as_str())?)?;
+        let stack = component_stack_string(&invalid_name.component_stack);
+        object.set("componentStack", JsString::new_or_throw(scope, stack.as_str())?)?;
+        array.set(i as u32, object)?;
+    }
+    Ok(array)
+}
+
+// `[{tag, trusted, bytes, component, componentStack}, ...]`, `component`
+// being the one that injected the HTML.
+fn html_injections_to_js<'a>(
+    scope: &mut RootScope<'a>,
+    injections: &[HtmlInjection],
+) -> JsResult<'a, JsArray> {
+    let array: Handle<JsArray> = JsArray::new(scope, injections.len() as u32);
+    for (i, injection) in injections.iter().enumerate() {
+        let object: Handle<JsObject> = JsObject::new(scope);
+        if let Some(ref tag) = injection.tag {
+            object.set("tag", JsString::new_or_throw(scope, tag.as_str())?)?;
+        }
+        object.set("trusted", JsBoolean::new(scope, injection.trusted))?;
+        object.set("bytes", JsNumber::new(scope, injection.bytes as f64))?;
+        let component = injection.component_stack.first().map_or("", String::as_str);
+        object.set("component", JsString::new_or_throw(scope, component)?)?;
+        let stack = component_stack_string(&injection.component_stack);
+        object.set("componentStack", JsString::new_or_throw(scope, stack.as_str())?)?;
+        array.set(i as u32, object)?;
+    }
+    Ok(array)
+}
+
+fn strings_to_js<'a>(scope: &mut RootScope<'a>, strings: &[String]) -> JsResult<'a, JsArray> {
+    let array: Handle<JsArray> = JsArray::new(scope, strings.len() as u32);
+    for (i, string) in strings.iter().enumerate() {
+        array.set(i as u32, JsString::new_or_throw(scope, string.as_str())?)?;
+    }
+    Ok(array)
+}
+
+// `{scripts, styles}`, arrays of `sha256-...` sources.
+fn inline_hashes_to_js<'a>(
+    scope: &mut RootScope<'a>,
+    hashes: &InlineHashes,
+) -> JsResult<'a, JsObject> {
+    let object: Handle<JsObject> = JsObject::new(scope);
+    object.set("scripts", strings_to_js(scope, &hashes.scripts)?)?;
+    object.set("styles", strings_to_js(scope, &hashes.styles)?)?;
+    Ok(object)
+}
+
+pub fn render_to_string(call: Call) -> JsResult<JsValue> {
+    render(call, false)
+}
+
+pub fn render_to_static_markup(call: Call) -> JsResult<JsValue> {
+    render(call, true)
+}
+
+/**
+ * Names of the registered native components, usable as element types.
+ */
+pub fn native_components(call: Call) -> JsResult<JsArray> {
+    let names = component::names();
+    let array: Handle<JsArray> = JsArray::new(call.scope, names.len() as u32);
+    for (i, name) in names.iter().enumerate() {
+        let name = JsString::new_or_throw(call.scope, name.as_str())?;
+        array.set(i as u32, name)?;
+    }
+    Ok(array)
+}
+
+// An array of strings, `None` for anything else.
+fn strings_from_js(
+    scope: &mut RootScope,
+    value: Handle<JsValue>,
+) -> VmResult<Option<Vec<String>>> {
+    let array = match value.downcast::<JsArray>() {
+        Some(array) => array,
+        None => return Ok(None),
+    };
+    let mut strings = Vec::new();
+    for item in array.to_vec(scope)? {
+        strings.push(item.check::<JsString>()?.value());
+    }
+    Ok(Some(strings))
+}
+
+/**
+ * `setUrlPolicy({allowedSchemes, action, replacement})` sets the policy for
+ * URL attributes. `allowedSchemes` is an array of schemes, or `null` for any
+ * but the unsafe ones. `action` is what to do with an unsafe URL: `'drop'`
+ * the attribute (the default), `'replace'` it with `replacement`, or
+ * `'throw'` an `UnsafeUrlError`.
+ */
+pub fn set_url_policy(call: Call) -> JsResult<JsUndefined> {
+    let object = call.arguments.require(call.scope, 0)?.check::<JsObject>()?;
+    let mut policy = UrlPolicy::default();
+    let schemes = object.get(call.scope, "allowedSchemes")?;
+    policy.allowed_schemes = strings_from_js(call.scope, schemes)?.map(|schemes| {
+        schemes.iter().map(|scheme| scheme.to_lowercase()).collect()
+    });
+    let action = object.get(call.scope, "action")?
+        .downcast::<JsString>()
+        .map_or("drop".to_string(), |action| action.value());
+    policy.action = match action.as_str() {
+        "drop" => UnsafeUrlAction::Drop,
+        "replace" => {
+            let replacement = object.get(call.scope, "replacement")?.check::<JsString>()?;
+            UnsafeUrlAction::Replace(replacement.value())
+        },
+        "throw" => UnsafeUrlAction::Abort,
+        _ => {
+            let message = format!("Unknown unsafe URL action `{}`", action);
+            return JsError::throw(Kind::TypeError, message.as_str());
+        },
+    };
+    url_policy::set_policy(policy);
+    Ok(JsUndefined::new())
+}
+
+fn unsafe_style_action(action: &str) -> VmResult<UnsafeStyleAction> {
+    match action {
+        "drop" => Ok(UnsafeStyleAction::Drop),
+        "throw" => Ok(UnsafeStyleAction::Abort),
+        _ => {
+            let message = format!("Unknown unsafe style action `{}`", action);
+            JsError::throw(Kind::TypeError, message.as_str())
+        },
+    }
+}
+
+/**
+ * `setStyleSafety(action)` turns the style safety mode on for all renders,
+ * see `style_safety`: unsafe style declarations are `'drop'`ped, or
+ * `'throw'` an `UnsafeStyleError`. `setStyleSafety(null)` turns it off.
+ */
+pub fn set_style_safety(call: Call) -> JsResult<JsUndefined> {
+    let action = match call.arguments.require(call.scope, 0)?.downcast::<JsString>() {
+        Some(action) => Some(unsafe_style_action(action.value().as_str())?),
+        None => None,
+    };
+    style_safety::set_action(action);
+    Ok(JsUndefined::new())
+}
+
+fn invalid_name_action(action: &str) -> VmResult<InvalidNameAction> {
+    match action {
+        "warn" => Ok(InvalidNameAction::Warn),
+        "throw" => Ok(InvalidNameAction::Abort),
+        _ => {
+            let message = format!("Unknown invalid name action `{}`", action);
+            JsError::throw(Kind::TypeError, message.as_str())
+        },
+    }
+}
+
+/**
+ * `setInvalidNameAction(action)` sets what renders do with invalid tag and
+ * attribute names, see `name_safety`: leave them out with a `'warn'`ing (the
+ * default), or `'throw'` an `InvalidNameError`.
+ */
+pub fn set_invalid_name_action(call: Call) -> JsResult<JsUndefined> {
+    let action = call.arguments.require(call.scope, 0)?.check::<JsString>()?.value();
+    name_safety::set_action(invalid_name_action(action.as_str())?);
+    Ok(JsUndefined::new())
+}
 
-pub fn render_to_string(call: Call) -> JsResult<JsString> {
-    let mut renderer = DomServerRenderer::new(call, false);
-    let html = renderer
-        .read(ReadSize::Infinity)
-        .unwrap_or_else(|| "".to_string());
-    Ok(JsString::new(renderer.call.scope, html.as_str()).unwrap())
+/**
+ * `setSanitizer({tags, attributes, tagAttributes, urlSchemes})` cleans raw
+ * HTML from then on, `setSanitizer(null)` stops. Each is an array of names,
+ * `tagAttributes` an object of them by tag, e.g. `{a: ['href', 'rel']}`.
+ * Missing ones keep their defaults, see `sanitize::Sanitizer`.
+ */
+pub fn set_sanitizer(call: Call) -> JsResult<JsUndefined> {
+    let object = match call.arguments.require(call.scope, 0)?.downcast::<JsObject>() {
+        Some(object) => object,
+        None => {
+            sanitize::set_sanitizer(None);
+            return Ok(JsUndefined::new());
+        },
+    };
+    let mut sanitizer = Sanitizer::default();
+    let tags = object.get(call.scope, "tags")?;
+    sanitizer.tags = strings_from_js(call.scope, tags)?;
+    let attributes = object.get(call.scope, "attributes")?;
+    sanitizer.attributes = strings_from_js(call.scope, attributes)?;
+    if let Some(by_tag) = object.get(call.scope, "tagAttributes")?.downcast::<JsObject>() {
+        let mut tag_attributes = Vec::new();
+        for tag in by_tag.get_own_property_names(call.scope)?.to_vec(call.scope)? {
+            let tag = tag.check::<JsString>()?.value();
+            let attributes = by_tag.get(call.scope, tag.as_str())?;
+            for attribute in strings_from_js(call.scope, attributes)?.unwrap_or_default() {
+                tag_attributes.push((tag.clone(), attribute));
+            }
+        }
+        sanitizer.tag_attributes = Some(tag_attributes);
+    }
+    let url_schemes = object.get(call.scope, "urlSchemes")?;
+    sanitizer.url_schemes = strings_from_js(call.scope, url_schemes)?;
+    sanitize::set_sanitizer(Some(sanitizer));
+    Ok(JsUndefined::new())
 }
 
-pub fn render_to_static_markup(call: Call) -> JsResult<JsString> {
-    let mut renderer = DomServerRenderer::new(call, true);
-    let html = renderer
-        .read(ReadSize::Infinity)
-        .unwrap_or_else(|| "".to_string());
-    Ok(JsString::new(renderer.call.scope, html.as_str()).unwrap())
+/**
+ * `serializeForScript(value)`, `value` as JSON that is safe inside a
+ * `<script>` element, see `script_json`. Takes what props take, like
+ * `JSON.stringify` except that `undefined` serializes as `null`.
+ */
+pub fn serialize_for_script(call: Call) -> JsResult<JsString> {
+    let value = call.arguments.require(call.scope, 0)?.to_raw();
+    let value = to_prop_value(call.scope, value)?.unwrap_or(PropValue::Null);
+    JsString::new_or_throw(call.scope, to_script_json(&value).as_str())
 }
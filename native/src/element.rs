@@ -0,0 +1,306 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::slice;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::vec;
+
+use util::number_to_string;
+
+/**
+ * An owned element tree, independent of any JavaScript engine.
+ *
+ * JS components have already been rendered away by the time a tree is built,
+ * so the renderer only sees host elements, native components (by name), text,
+ * raw HTML and the cached output of components. For profiles, traces and
+ * stats, the output of each JS component is marked so the renderer can
+ * attribute to it.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+    Fragment(Vec<Node>),
+    // Markup injected verbatim, e.g. from `dangerouslySetInnerHTML`. It goes
+    // through the sanitizer if there is one, see `sanitize`.
+    RawHtml(String),
+    // Raw HTML that is never sanitized.
+    TrustedHtml(String),
+    // A component's output whose HTML can be reused, see `component_cache`.
+    Cached(Arc<CachedNode>),
+    // A JS component's output, only kept when observing a render. Renders
+    // like `node`.
+    Component(Box<RenderedComponent>),
+}
+
+/**
+ * The output of a cacheable component. Equal keys promise equal output.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct CachedNode {
+    pub key: String,
+    pub ttl: Option<Duration>,
+    pub node: Node,
+}
+
+/**
+ * What a JS component rendered to, and when and for how long rendering and
+ * converting it (children included) ran.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderedComponent {
+    pub name: String,
+    pub started: Instant,
+    pub elapsed: Duration,
+    pub node: Node,
+}
+
+/**
+ * A host (DOM) element. `props` never contains `children` or
+ * `dangerouslySetInnerHTML`, those live in `children`.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Element {
+    pub tag: String,
+    pub props: Props,
+    pub children: Vec<Node>,
+}
+
+/**
+ * A prop value, typed the way JavaScript would see it. `undefined`,
+ * functions and symbols are never stored, they are dropped while converting.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<PropValue>),
+    Object(Props),
+}
+
+/// Ordered prop map, iteration follows insertion order like `for...in`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Props(Vec<(String, PropValue)>);
+
+impl Node {
+    pub fn empty() -> Node {
+        Node::Fragment(Vec::new())
+    }
+
+    pub fn text<S: Into<String>>(text: S) -> Node {
+        Node::Text(text.into())
+    }
+}
+
+impl Element {
+    pub fn new<S: Into<String>>(tag: S) -> Element {
+        Element {
+            tag: tag.into(),
+            props: Props::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn prop<S, V>(mut self, name: S, value: V) -> Element
+        where S: Into<String>,
+              V: Into<PropValue>,
+    {
+        self.props.insert(name, value.into());
+        self
+    }
+
+    pub fn child(mut self, child: Node) -> Element {
+        self.children.push(child);
+        self
+    }
+}
+
+impl From<Element> for Node {
+    fn from(element: Element) -> Node {
+        Node::Element(element)
+    }
+}
+
+impl PropValue {
+    pub fn is_null(&self) -> bool {
+        *self == PropValue::Null
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            PropValue::String(ref s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// `!!value`
+    pub fn is_truthy(&self) -> bool {
+        match *self {
+            PropValue::Null => false,
+            PropValue::Bool(b) => b,
+            PropValue::Number(n) => n != 0.0 && !n.is_nan(),
+            PropValue::String(ref s) => !s.is_empty(),
+            PropValue::Array(_) | PropValue::Object(_) => true,
+        }
+    }
+
+    /// `Number(value)`
+    pub fn to_number(&self) -> f64 {
+        match *self {
+            PropValue::Null => 0.0,
+            PropValue::Bool(b) => if b { 1.0 } else { 0.0 },
+            PropValue::Number(n) => n,
+            PropValue::String(ref s) => string_to_number(s),
+            PropValue::Array(_) => string_to_number(self.to_string().as_str()),
+            PropValue::Object(_) => f64::NAN,
+        }
+    }
+}
+
+fn string_to_number(s: &str) -> f64 {
+    let s = s.trim();
+    if s.is_empty() {
+        return 0.0;
+    }
+    let (negative, unsigned) = if let Some(unsigned) = s.strip_prefix('-') {
+        (true, unsigned)
+    } else if let Some(unsigned) = s.strip_prefix('+') {
+        (false, unsigned)
+    } else {
+        (false, s)
+    };
+    let radix = match unsigned.get(..2) {
+        Some("0x") | Some("0X") if unsigned.len() == s.len() => 16,
+        Some("0o") | Some("0O") if unsigned.len() == s.len() => 8,
+        Some("0b") | Some("0B") if unsigned.len() == s.len() => 2,
+        _ => 10,
+    };
+    let value = if radix != 10 {
+        u64::from_str_radix(&unsigned[2..], radix)
+            .map(|n| n as f64)
+            .unwrap_or(f64::NAN)
+    } else if unsigned == "Infinity" {
+        f64::INFINITY
+    } else if unsigned.chars().all(|c| c.is_ascii_digit() || "eE.+-".contains(c)) {
+        unsigned.parse::<f64>().unwrap_or(f64::NAN)
+    } else {
+        f64::NAN
+    };
+    if negative { -value } else { value }
+}
+
+/// `'' + value`
+impl fmt::Display for PropValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PropValue::Null => f.write_str("null"),
+            PropValue::Bool(b) => write!(f, "{}", b),
+            PropValue::Number(n) => f.write_str(number_to_string(n).as_str()),
+            PropValue::String(ref s) => f.write_str(s),
+            PropValue::Array(ref items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    // Array.prototype.join renders null as an empty string.
+                    if !item.is_null() {
+                        write!(f, "{}", item)?;
+                    }
+                }
+                Ok(())
+            }
+            PropValue::Object(_) => f.write_str("[object Object]"),
+        }
+    }
+}
+
+impl From<bool> for PropValue {
+    fn from(value: bool) -> PropValue {
+        PropValue::Bool(value)
+    }
+}
+
+impl From<f64> for PropValue {
+    fn from(value: f64) -> PropValue {
+        PropValue::Number(value)
+    }
+}
+
+impl<'a> From<&'a str> for PropValue {
+    fn from(value: &'a str) -> PropValue {
+        PropValue::String(value.to_string())
+    }
+}
+
+impl From<String> for PropValue {
+    fn from(value: String) -> PropValue {
+        PropValue::String(value)
+    }
+}
+
+impl Props {
+    pub fn new() -> Props {
+        Props(Vec::new())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PropValue> {
+        self.0.iter().find(|entry| entry.0 == name).map(|entry| &entry.1)
+    }
+
+    /// Sets `name`, keeping its original position if it was already set.
+    pub fn insert<S: Into<String>>(&mut self, name: S, value: PropValue) {
+        let name = name.into();
+        if let Some(entry) = self.0.iter_mut().find(|entry| entry.0 == name) {
+            entry.1 = value;
+            return;
+        }
+        self.0.push((name, value));
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PropValue> {
+        let index = self.0.iter().position(|entry| entry.0 == name);
+        index.map(|i| self.0.remove(i).1)
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, (String, PropValue)> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromIterator<(String, PropValue)> for Props {
+    fn from_iter<I: IntoIterator<Item = (String, PropValue)>>(iter: I) -> Props {
+        let mut props = Props::new();
+        for (name, value) in iter {
+            props.insert(name, value);
+        }
+        props
+    }
+}
+
+impl IntoIterator for Props {
+    type Item = (String, PropValue);
+    type IntoIter = vec::IntoIter<(String, PropValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Props {
+    type Item = &'a (String, PropValue);
+    type IntoIter = slice::Iter<'a, (String, PropValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
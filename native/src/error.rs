@@ -0,0 +1,102 @@
+/**
+ * Why a render was aborted. Its partial output is dropped.
+ */
+use std::error::Error;
+use std::fmt;
+
+use limits::LimitExceeded;
+use name_safety::InvalidName;
+use style_safety::UnsafeStyle;
+use trusted_html::UntrustedHtml;
+use url_policy::UnsafeUrl;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenderError {
+    LimitExceeded(LimitExceeded),
+    // An unsafe URL, with a policy of `UnsafeUrlAction::Abort`.
+    UnsafeUrl(UnsafeUrl),
+    // Raw HTML that isn't trusted, with `RenderOptions::trusted_html_only`.
+    UntrustedHtml(UntrustedHtml),
+    // An unsafe style, with `UnsafeStyleAction::Abort`.
+    UnsafeStyle(UnsafeStyle),
+    // An invalid tag or attribute name, with `InvalidNameAction::Abort`.
+    InvalidName(InvalidName),
+}
+
+impl RenderError {
+    /// The `name` of the error thrown to JS.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            RenderError::LimitExceeded(_) => "RenderLimitError",
+            RenderError::UnsafeUrl(_) => "UnsafeUrlError",
+            RenderError::UntrustedHtml(_) => "UntrustedHtmlError",
+            RenderError::UnsafeStyle(_) => "UnsafeStyleError",
+            RenderError::InvalidName(_) => "InvalidNameError",
+        }
+    }
+
+    /// The components being rendered at the time, innermost first.
+    pub fn component_stack(&self) -> &[String] {
+        match *self {
+            RenderError::LimitExceeded(ref error) => &error.component_stack,
+            RenderError::UnsafeUrl(ref error) => &error.component_stack,
+            RenderError::UntrustedHtml(ref error) => &error.component_stack,
+            RenderError::UnsafeStyle(ref error) => &error.component_stack,
+            RenderError::InvalidName(ref error) => &error.component_stack,
+        }
+    }
+}
+
+/// A component stack as React formats it, `"\n    in Inner\n    in Outer"`.
+pub fn component_stack_string(component_stack: &[String]) -> String {
+    let mut stack = String::new();
+    for name in component_stack {
+        stack.push_str("\n    in ");
+        stack.push_str(name.as_str());
+    }
+    stack
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RenderError::LimitExceeded(ref error) => error.fmt(f),
+            RenderError::UnsafeUrl(ref error) => error.fmt(f),
+            RenderError::UntrustedHtml(ref error) => error.fmt(f),
+            RenderError::UnsafeStyle(ref error) => error.fmt(f),
+            RenderError::InvalidName(ref error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for RenderError {}
+
+impl From<LimitExceeded> for RenderError {
+    fn from(error: LimitExceeded) -> RenderError {
+        RenderError::LimitExceeded(error)
+    }
+}
+
+impl From<UnsafeUrl> for RenderError {
+    fn from(error: UnsafeUrl) -> RenderError {
+        RenderError::UnsafeUrl(error)
+    }
+}
+
+impl From<UntrustedHtml> for RenderError {
+    fn from(error: UntrustedHtml) -> RenderError {
+        RenderError::UntrustedHtml(error)
+    }
+}
+
+impl From<UnsafeStyle> for RenderError {
+    fn from(error: UnsafeStyle) -> RenderError {
+        RenderError::UnsafeStyle(error)
+    }
+}
+
+impl From<InvalidName> for RenderError {
+    fn from(error: InvalidName) -> RenderError {
+        RenderError::InvalidName(error)
+    }
+}
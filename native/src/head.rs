@@ -0,0 +1,84 @@
+/**
+ * Head management. Pages set their `<title>` and `<meta>` tags deep inside
+ * route components, but they belong in `<head>`. With
+ * `RenderOptions::collect_head`, the children of `<Head>` elements are
+ * collected as the tree is rendered, along with those of the `<head>`
+ * element itself, and all of them are written into the `<head>`:
+ *
+ *   <Head><title>Product</title><meta name="description" content="..." /></Head>
+ *
+ * Elements that set the same thing replace each other, the last one wins:
+ * `<title>`, `<base>`, `<meta>` with the same `charSet`, `name`, `property`
+ * or `httpEquiv`, and `<link>` with the same `rel` and `href`.
+ *
+ * Without `collect_head`, `<Head>` renders its children in place.
+ */
+use element::Element;
+
+/// The element type whose children are collected.
+pub const HEAD: &str = "Head";
+
+/**
+ * The head markup collected so far, in order, each entry with the key it
+ * is de-duplicated by.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct HeadCollector {
+    entries: Vec<(Option<String>, String)>,
+    // Where in the output the markup goes, once the `<head>` is rendered.
+    pub position: Option<usize>,
+}
+
+impl HeadCollector {
+    pub fn new() -> HeadCollector {
+        HeadCollector::default()
+    }
+
+    /// Adds the markup of a head element, replacing any earlier one with the
+    /// same key.
+    pub fn add(&mut self, key: Option<String>, markup: String) {
+        if markup.is_empty() {
+            return;
+        }
+        if key.is_some() {
+            self.entries.retain(|(other, _)| *other != key);
+        }
+        self.entries.push((key, markup));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn markup(&self) -> String {
+        self.entries.iter().map(|(_, markup)| markup.as_str()).collect()
+    }
+}
+
+/// What `element` sets in the head, if it is one of a kind.
+pub fn head_key(element: &Element) -> Option<String> {
+    let prop = |name: &str| element.props.get(name).filter(|value| !value.is_null());
+    match element.tag.to_lowercase().as_str() {
+        "title" => Some("title".to_string()),
+        "base" => Some("base".to_string()),
+        "meta" => {
+            if prop("charSet").is_some() || prop("charset").is_some() {
+                return Some("meta charset".to_string());
+            }
+            let attributes = [
+                ("name", "name"),
+                ("property", "property"),
+                ("httpEquiv", "http-equiv"),
+                ("http-equiv", "http-equiv"),
+            ];
+            attributes.iter().filter_map(|&(name, attribute)| {
+                prop(name).map(|value| format!("meta {}={}", attribute, value))
+            }).next()
+        },
+        "link" => match (prop("rel"), prop("href")) {
+            (Some(rel), Some(href)) => Some(format!("link {} {}", rel, href)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,92 @@
+use neon::vm::{Call, JsResult};
+use neon::mem::Handle;
+use neon::js::{
+    JsArray,
+    JsBoolean,
+    JsNumber,
+    JsObject,
+    JsString,
+    Object,
+};
+use neon::js::error::{JsError, Kind};
+
+use cache;
+use component_cache;
+
+/**
+ * `cacheStats()`: `[{name, capacity, hits, misses, evictions}, ...]`
+ */
+pub fn cache_stats(call: Call) -> JsResult<JsArray> {
+    let stats = cache::stats();
+    let array: Handle<JsArray> = JsArray::new(call.scope, stats.len() as u32);
+    for (i, stats) in stats.iter().enumerate() {
+        let object: Handle<JsObject> = JsObject::new(call.scope);
+        object.set("name", JsString::new_or_throw(call.scope, stats.name)?)?;
+        object.set("capacity", JsNumber::new(call.scope, stats.capacity as f64))?;
+        object.set("hits", JsNumber::new(call.scope, stats.hits as f64))?;
+        object.set("misses", JsNumber::new(call.scope, stats.misses as f64))?;
+        object.set("evictions", JsNumber::new(call.scope, stats.evictions as f64))?;
+        array.set(i as u32, object)?;
+    }
+    Ok(array)
+}
+
+/**
+ * `resizeCache(name, capacity)`, returns false for an unknown cache.
+ */
+pub fn resize_cache(call: Call) -> JsResult<JsBoolean> {
+    let name = call.arguments.require(call.scope, 0)?.check::<JsString>()?.value();
+    let capacity = call.arguments.require(call.scope, 1)?.check::<JsNumber>()?.value();
+    if !(capacity >= 0.0) {
+        return JsError::throw(Kind::RangeError, "Cache capacity must be a non-negative number");
+    }
+    let resized = cache::resize(name.as_str(), capacity as usize);
+    Ok(JsBoolean::new(call.scope, resized))
+}
+
+/**
+ * `clearCaches(name)`, or every cache without a name. Returns false for an
+ * unknown cache.
+ */
+pub fn clear_caches(call: Call) -> JsResult<JsBoolean> {
+    let name = call.arguments
+        .get(call.scope, 0)
+        .and_then(|name| name.downcast::<JsString>())
+        .map(|name| name.value());
+    let cleared = cache::clear(name.as_ref().map(|name| name.as_str()));
+    Ok(JsBoolean::new(call.scope, cleared))
+}
+
+/**
+ * `componentCacheStats()`: `{entries, bytes, maxBytes, hits, misses}` of the
+ * cached component output.
+ */
+pub fn component_cache_stats(call: Call) -> JsResult<JsObject> {
+    let stats = component_cache::stats();
+    let object: Handle<JsObject> = JsObject::new(call.scope);
+    object.set("entries", JsNumber::new(call.scope, stats.entries as f64))?;
+    object.set("bytes", JsNumber::new(call.scope, stats.bytes as f64))?;
+    object.set("maxBytes", JsNumber::new(call.scope, stats.max_bytes as f64))?;
+    object.set("hits", JsNumber::new(call.scope, stats.hits as f64))?;
+    object.set("misses", JsNumber::new(call.scope, stats.misses as f64))?;
+    Ok(object)
+}
+
+/**
+ * `clearComponentCache(maxBytes)`, drops every cached component and
+ * optionally sets a new size limit.
+ */
+pub fn clear_component_cache(call: Call) -> JsResult<JsBoolean> {
+    let max_bytes = call.arguments
+        .get(call.scope, 0)
+        .and_then(|max_bytes| max_bytes.downcast::<JsNumber>())
+        .map(|max_bytes| max_bytes.value());
+    if let Some(max_bytes) = max_bytes {
+        if !(max_bytes >= 0.0) {
+            return JsError::throw(Kind::RangeError, "Cache size must be a non-negative number");
+        }
+        component_cache::set_max_bytes(max_bytes as usize);
+    }
+    component_cache::clear();
+    Ok(JsBoolean::new(call.scope, true))
+}
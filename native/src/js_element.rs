@@ -0,0 +1,559 @@
+use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use neon_runtime::mem::same_handle;
+use neon_runtime::raw::Local;
+use neon::scope::{RootScope, Scope};
+use neon::vm::VmResult;
+use neon::mem::{Handle, Managed};
+use neon::js::{Value, Variant, Object};
+use neon::js::{
+    JsFunction,
+    JsNull,
+    JsNumber,
+    JsObject,
+    JsString,
+    JsValue,
+};
+use neon::js::error::{throw, JsError, Kind};
+
+use serde_json;
+
+use component;
+use component_cache;
+use element::{CachedNode, Element, Node, PropValue, Props, RenderedComponent};
+use error::{RenderError, component_stack_string};
+use limits::{LimitExceeded, Limits};
+use util::number_to_string;
+use util::js_value::{
+    get_raw,
+    get_fn,
+    not,
+    to_data_value,
+    to_string,
+    to_prop_value,
+};
+
+const CHILDREN: &str = "children";
+const DANGEROUSLY_SET_INNER_HTML: &str = "dangerouslySetInnerHTML";
+const HTML: &str = "__html";
+const STYLE: &str = "style";
+const CACHE_KEY: &str = "cacheKey";
+const CACHE_TTL: &str = "cacheTTL";
+const TRUSTED_HTML: &str = "trustedHTML";
+// `lib/index.js`'s `trustHTML` keeps the HTML under this symbol, which JSON
+// can't forge.
+const TRUSTED_HTML_SYMBOL: &str = "react-neon-ssr.trustedHTML";
+// A `WeakMap` from cached components to their ids, on the global object.
+const CACHE_IDS_SYMBOL: &str = "react-neon-ssr.cacheIds";
+const ANONYMOUS: &str = "Anonymous";
+
+/**
+ * The state of converting one tree.
+ */
+pub struct Conversion {
+    // Keep each component's output as a `Node::Component` along with the
+    // time it took, for profiles, traces and stats.
+    pub mark: bool,
+    pub limits: Limits,
+    depth: usize,
+    component_stack: Vec<String>,
+    // Whether the component being converted has a static `trustedHTML`, so
+    // the raw HTML it renders isn't sanitized.
+    trusted_html: bool,
+    // What that component was passed, e.g. `props.children`, which its trust
+    // doesn't cover.
+    passed_in: Vec<Local>,
+}
+
+impl Conversion {
+    pub fn new(mark: bool, limits: Limits) -> Conversion {
+        Conversion {
+            mark,
+            limits,
+            depth: 0,
+            component_stack: Vec::new(),
+            trusted_html: false,
+            passed_in: Vec::new(),
+        }
+    }
+}
+
+/**
+ * Throws an `Error` named after the `RenderError` (`RenderLimitError`,
+ * `UnsafeUrlError`, `UntrustedHtmlError`, `UnsafeStyleError`,
+ * `InvalidNameError`), with its details and a React style `componentStack`.
+ * `lib/index.js` gives each its own class.
+ */
+pub fn throw_render_error<T>(scope: &mut RootScope, error: &RenderError) -> VmResult<T> {
+    let message = error.to_string();
+    let js_error = JsError::new(scope, Kind::Error, message.as_str())?;
+    js_error.set("name", JsString::new_or_throw(scope, error.name())?)?;
+    match *error {
+        RenderError::LimitExceeded(ref error) => {
+            js_error.set("limit", JsString::new_or_throw(scope, error.limit.name())?)?;
+        },
+        RenderError::UnsafeUrl(ref error) => {
+            js_error.set("attribute", JsString::new_or_throw(scope, error.attribute.as_str())?)?;
+            js_error.set("url", JsString::new_or_throw(scope, error.url.as_str())?)?;
+        },
+        RenderError::UnsafeStyle(ref error) => {
+            js_error.set("property", JsString::new_or_throw(scope, error.property.as_str())?)?;
+            js_error.set("value", JsString::new_or_throw(scope, error.value.as_str())?)?;
+            js_error.set("threat", JsString::new_or_throw(scope, error.threat.name())?)?;
+        },
+        RenderError::InvalidName(ref error) => {
+            js_error.set("kind", JsString::new_or_throw(scope, error.kind.name())?)?;
+            js_error.set("tag", JsString::new_or_throw(scope, error.tag.as_str())?)?;
+            js_error.set("invalidName", JsString::new_or_throw(scope, error.name.as_str())?)?;
+        },
+        RenderError::UntrustedHtml(ref error) => {
+            if let Some(ref tag) = error.tag {
+                js_error.set("tag", JsString::new_or_throw(scope, tag.as_str())?)?;
+            }
+        },
+    }
+    let stack = component_stack_string(error.component_stack());
+    js_error.set("componentStack", JsString::new_or_throw(scope, stack.as_str())?)?;
+    throw(js_error)
+}
+
+/**
+ * Converts a JavaScript React element (or any renderable child: strings,
+ * numbers, arrays, null) into an owned `Node` tree. Composite components are
+ * rendered along the way, so this is the only place that calls into JS.
+ *
+ * Throws a `RenderLimitError` if the conversion exceeds `cx.limits`.
+ */
+pub fn to_node(scope: &mut RootScope, value: Local, cx: &mut Conversion) -> VmResult<Node> {
+    let value = JsValue::from_raw(value).as_value(scope);
+    match value.variant() {
+        Variant::String(s) => Ok(Node::Text(s.value())),
+        Variant::Integer(i) => Ok(Node::Text(i.value().to_string())),
+        Variant::Number(n) => Ok(Node::Text(number_to_string(n.value()))),
+        Variant::Array(array) => {
+            let mut nodes = Vec::new();
+            for item in array.to_vec(scope)? {
+                nodes.push(to_node(scope, item.to_raw(), cx)?);
+            }
+            Ok(Node::Fragment(nodes))
+        },
+        Variant::Object(obj) => {
+            if let Err(limit) = cx.limits.check(cx.depth + 1, 0) {
+                let error = RenderError::LimitExceeded(LimitExceeded {
+                    limit,
+                    component_stack: cx.component_stack.iter().rev().cloned().collect(),
+                });
+                return throw_render_error(scope, &error);
+            }
+            cx.depth += 1;
+            let is_passed_in = cx.trusted_html && cx.passed_in.iter()
+                .any(|&passed| unsafe { same_handle(passed, obj.to_raw()) });
+            let node = if is_passed_in {
+                cx.trusted_html = false;
+                let node = element_to_node(scope, obj.to_raw(), cx);
+                cx.trusted_html = true;
+                node
+            } else {
+                element_to_node(scope, obj.to_raw(), cx)
+            };
+            cx.depth -= 1;
+            node
+        },
+        // null, undefined and booleans render nothing.
+        _ => Ok(Node::empty()),
+    }
+}
+
+fn element_to_node(
+    scope: &mut RootScope,
+    element: Local,
+    cx: &mut Conversion,
+) -> VmResult<Node> {
+    let type_raw = get_raw(scope, element, "type");
+    let props_raw = get_raw(scope, element, "props");
+    let type_val = JsValue::from_raw(type_raw).as_value(scope);
+    match type_val.variant() {
+        Variant::String(tag) => {
+            let tag = tag.value();
+            // Native components get their props as structured values.
+            let structured = component::get(tag.as_str()).is_some();
+            let (props, children) = host_props(scope, props_raw, structured, cx)?;
+            Ok(Node::Element(Element {
+                tag,
+                props,
+                children,
+            }))
+        },
+        Variant::Function(_) => {
+            let trusted_html = is_trusted_html(scope, type_raw);
+            let passed_in = if trusted_html {
+                passed_in(scope, props_raw)?
+            } else {
+                Vec::new()
+            };
+            let trusted_html = ::std::mem::replace(&mut cx.trusted_html, trusted_html);
+            let passed_in = ::std::mem::replace(&mut cx.passed_in, passed_in);
+            let node = function_to_node(scope, type_raw, props_raw, cx);
+            cx.trusted_html = trusted_html;
+            cx.passed_in = passed_in;
+            node
+        },
+        // Symbol types, i.e. `React.Fragment`.
+        Variant::Other(_) => {
+            let children = get_raw(scope, props_raw, CHILDREN);
+            to_node(scope, children, cx)
+        },
+        _ => JsError::throw(
+            Kind::TypeError,
+            "Objects are not valid as a React child",
+        ),
+    }
+}
+
+// `Component.trustedHTML === true`
+fn is_trusted_html(scope: &mut RootScope, component: Local) -> bool {
+    match JsValue::from_raw(get_raw(scope, component, TRUSTED_HTML)).as_value(scope).variant() {
+        Variant::Boolean(trusted) => trusted.value(),
+        _ => false,
+    }
+}
+
+// The objects among a component's props, and in arrays among them: elements
+// it was given rather than rendered itself.
+fn passed_in(scope: &mut RootScope, props: Local) -> VmResult<Vec<Local>> {
+    let mut values = Vec::new();
+    let names = JsObject::from_raw(props)
+        .get_own_property_names(scope)?
+        .to_vec(scope)?;
+    for name in names {
+        let name = to_string(scope, name.deref());
+        let value = get_raw(scope, props, name.as_str());
+        push_passed_in(scope, value, &mut values)?;
+    }
+    Ok(values)
+}
+
+fn push_passed_in(scope: &mut RootScope, value: Local, values: &mut Vec<Local>) -> VmResult<()> {
+    match JsValue::from_raw(value).as_value(scope).variant() {
+        Variant::Array(array) => {
+            for item in array.to_vec(scope)? {
+                push_passed_in(scope, item.to_raw(), values)?;
+            }
+        },
+        Variant::Object(_) => values.push(value),
+        _ => {},
+    }
+    Ok(())
+}
+
+// Calls a function or class component, marking its output if asked to.
+fn function_to_node(
+    scope: &mut RootScope,
+    type_raw: Local,
+    props_raw: Local,
+    cx: &mut Conversion,
+) -> VmResult<Node> {
+    if !cx.mark && cx.limits.is_empty() {
+        return component_to_node(scope, type_raw, props_raw, cx);
+    }
+    let started = Instant::now();
+    let name = get_component_name(scope, type_raw)
+        .unwrap_or_else(|| ANONYMOUS.to_string());
+    cx.component_stack.push(name);
+    let node = component_to_node(scope, type_raw, props_raw, cx);
+    let name = cx.component_stack.pop().unwrap();
+    let node = node?;
+    if !cx.mark {
+        return Ok(node);
+    }
+    Ok(Node::Component(Box::new(RenderedComponent {
+        name,
+        started,
+        elapsed: started.elapsed(),
+        node,
+    })))
+}
+
+fn component_to_node(
+    scope: &mut RootScope,
+    component: Local,
+    props: Local,
+    cx: &mut Conversion,
+) -> VmResult<Node> {
+    let (key, ttl) = match cache_key(scope, component, props)? {
+        Some(key) => key,
+        None => {
+            let rendered = render_component(scope, component, props)?;
+            return to_node(scope, rendered, cx);
+        },
+    };
+    if let Some(cached) = component_cache::get_node(key.as_str()) {
+        return Ok(Node::Cached(cached));
+    }
+    let rendered = render_component(scope, component, props)?;
+    let node = without_components(to_node(scope, rendered, cx)?);
+    let cached = Arc::new(CachedNode { key, ttl, node });
+    component_cache::insert_node(cached.clone());
+    Ok(Node::Cached(cached))
+}
+
+// Cached trees outlive the render, its profile doesn't belong in them.
+fn without_components(node: Node) -> Node {
+    match node {
+        Node::Component(component) => without_components(component.node),
+        Node::Element(mut element) => {
+            element.children = element.children
+                .into_iter()
+                .map(without_components)
+                .collect();
+            Node::Element(element)
+        },
+        Node::Fragment(nodes) => {
+            Node::Fragment(nodes.into_iter().map(without_components).collect())
+        },
+        node => node,
+    }
+}
+
+/**
+ * `displayName`, or else the function's name. `None` for anonymous
+ * components.
+ */
+pub fn get_component_name(scope: &mut RootScope, component: Local) -> Option<String> {
+    ["displayName", "name"].iter()
+        .filter_map(|key| {
+            let name = get_raw(scope, component, key);
+            match JsValue::from_raw(name).as_value(scope).variant() {
+                Variant::String(name) => Some(name.value()),
+                _ => None,
+            }
+        })
+        .find(|name| !name.is_empty())
+}
+
+fn should_construct(scope: &mut RootScope, component: Local) -> bool {
+    let prototype = JsObject
+        ::from_raw(component)
+        .get(scope, "prototype")
+        .unwrap();
+    if prototype.is_a::<JsObject>() {
+        let is_react_component = JsObject
+            ::from_raw(prototype.to_raw())
+            .get(scope, "isReactComponent")
+            .unwrap();
+        !not(is_react_component)
+    } else {
+        false
+    }
+}
+
+static NEXT_CACHE_ID: AtomicUsize = AtomicUsize::new(1);
+
+/**
+ * The cache key of a component element, from the component's static
+ * `cacheKey`: either a function of the props returning a string (or null
+ * not to cache), or `true` to key by the props themselves, which then must
+ * be plain data: props holding functions are never cached. `cacheTTL` is in
+ * milliseconds.
+ *
+ * Keys are prefixed with the component's name and an id for the component
+ * function itself, so components with the same (e.g. minified) name don't
+ * share entries. Anonymous components are never cached.
+ */
+fn cache_key(
+    scope: &mut RootScope,
+    component: Local,
+    props: Local,
+) -> VmResult<Option<(String, Option<Duration>)>> {
+    let name = match get_component_name(scope, component) {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let cache_key = get_raw(scope, component, CACHE_KEY);
+    let key = match JsValue::from_raw(cache_key).as_value(scope).variant() {
+        Variant::Function(_) => {
+            let props = JsValue::from_raw(props).as_value(scope);
+            let key = JsFunction::<JsObject>::from_raw(cache_key)
+                .call(scope, JsNull::new(), vec![props])?;
+            match key.variant() {
+                Variant::Null(_) | Variant::Undefined(_) => return Ok(None),
+                _ => to_string(scope, key.deref()),
+            }
+        },
+        Variant::Boolean(b) if b.value() => {
+            // Element children can't be told apart once converted.
+            let props = match to_data_value(scope, props)? {
+                Some(PropValue::Object(props)) => props,
+                _ => return Ok(None),
+            };
+            match props.get(CHILDREN) {
+                Some(&PropValue::Object(_)) | Some(&PropValue::Array(_)) => {
+                    return Ok(None);
+                },
+                _ => {},
+            }
+            match serde_json::to_string(&props) {
+                Ok(key) => key,
+                Err(_) => return Ok(None),
+            }
+        },
+        _ => return Ok(None),
+    };
+    let ttl = match JsValue::from_raw(get_raw(scope, component, CACHE_TTL))
+        .as_value(scope)
+        .variant()
+    {
+        Variant::Integer(ms) => Some(Duration::from_millis(ms.value() as u64)),
+        Variant::Number(ms) if ms.value() >= 0.0 => {
+            Some(Duration::from_millis(ms.value() as u64))
+        },
+        _ => None,
+    };
+    let id = cache_id(scope, component)?;
+    Ok(Some((format!("{}#{}\u{0}{}", name, id, key), ttl)))
+}
+
+// The id of a component function, the same for as long as it lives.
+fn cache_id(scope: &mut RootScope, component: Local) -> VmResult<usize> {
+    let ids = cache_ids(scope)?;
+    let component = JsValue::from_raw(component).as_value(scope);
+    let get = ids.get(scope, "get")?.check::<JsFunction>()?;
+    if let Some(id) = get.call(scope, ids, vec![component])?.downcast::<JsNumber>() {
+        return Ok(id.value() as usize);
+    }
+    let id = NEXT_CACHE_ID.fetch_add(1, Ordering::Relaxed);
+    let set = ids.get(scope, "set")?.check::<JsFunction>()?;
+    let value = JsNumber::new(scope, id as f64).upcast::<JsValue>();
+    set.call(scope, ids, vec![component, value])?;
+    Ok(id)
+}
+
+fn cache_ids<'a>(scope: &mut RootScope<'a>) -> VmResult<Handle<'a, JsObject>> {
+    let global = scope.global();
+    let key = symbol_for(scope, CACHE_IDS_SYMBOL)?;
+    if let Some(ids) = global.get(scope, key)?.downcast::<JsObject>() {
+        return Ok(ids);
+    }
+    let weak_map = global.get(scope, "WeakMap")?.check::<JsFunction>()?;
+    let ids = weak_map.construct(scope, Vec::<Handle<JsValue>>::new())?;
+    global.set(key, ids)?;
+    Ok(ids)
+}
+
+fn render_component(
+    scope: &mut RootScope,
+    component: Local,
+    props: Local,
+) -> VmResult<Local> {
+    let props = JsValue::from_raw(props).as_value(scope);
+    let rendered = if should_construct(scope, component) {
+        let instance: Handle<JsObject> = JsFunction::from_raw(component)
+            .construct(scope, vec![props])?;
+        let render_fn = get_fn(scope, instance.to_raw(), "render");
+        let no_args: Vec<Handle<JsValue>> = Vec::new();
+        render_fn.call(scope, instance, no_args)?
+    } else {
+        JsFunction::<JsObject>::from_raw(component)
+            .call(scope, JsNull::new(), vec![props])?
+    };
+    Ok(rendered.to_raw())
+}
+
+fn host_props(
+    scope: &mut RootScope,
+    props: Local,
+    structured: bool,
+    cx: &mut Conversion,
+) -> VmResult<(Props, Vec<Node>)> {
+    let mut result = Props::new();
+    let mut children = Vec::new();
+    let mut inner_html = None;
+    let names = JsObject::from_raw(props)
+        .get_own_property_names(scope)?
+        .to_vec(scope)?;
+    for name in names {
+        let name = to_string(scope, name.deref());
+        let value = get_raw(scope, props, name.as_str());
+        match name.as_str() {
+            CHILDREN => {
+                children = match to_node(scope, value, cx)? {
+                    Node::Fragment(nodes) => nodes,
+                    node => vec![node],
+                };
+            },
+            DANGEROUSLY_SET_INNER_HTML => {
+                inner_html = get_inner_html(scope, value)?;
+            },
+            _ if structured || name == STYLE => {
+                if let Some(value) = to_prop_value(scope, value)? {
+                    result.insert(name, value);
+                }
+            },
+            _ => {
+                if let Some(value) = to_attribute_value(scope, value)? {
+                    result.insert(name, value);
+                }
+            },
+        }
+    }
+    if let Some((html, trusted)) = inner_html {
+        let html = if trusted || cx.trusted_html {
+            Node::TrustedHtml(html)
+        } else {
+            Node::RawHtml(html)
+        };
+        children = vec![html];
+    }
+    Ok((result, children))
+}
+
+// `dangerouslySetInnerHTML.__html` as a string, and whether it was marked
+// with `trustHTML`.
+fn get_inner_html(scope: &mut RootScope, value: Local) -> VmResult<Option<(String, bool)>> {
+    if !JsValue::from_raw(value).as_value(scope).is_a::<JsObject>() {
+        return Ok(None);
+    }
+    let html = get_raw(scope, value, HTML);
+    Ok(match JsValue::from_raw(html).as_value(scope).variant() {
+        Variant::Null(_) | Variant::Undefined(_) => None,
+        Variant::Object(object) => match get_trusted_html(scope, object)? {
+            Some(trusted) => Some((trusted, true)),
+            None => Some((to_string(scope, object.deref()), false)),
+        },
+        _ => Some((to_string(scope, &JsValue::from_raw(html)), false)),
+    })
+}
+
+// The HTML of a `trustHTML(html)` object, under
+// `Symbol.for(TRUSTED_HTML_SYMBOL)`.
+fn get_trusted_html(scope: &mut RootScope, object: Handle<JsObject>) -> VmResult<Option<String>> {
+    let key = symbol_for(scope, TRUSTED_HTML_SYMBOL)?;
+    Ok(object.get(scope, key)?.downcast::<JsString>().map(|html| html.value()))
+}
+
+// `Symbol.for(name)`
+fn symbol_for<'a>(scope: &mut RootScope<'a>, name: &str) -> VmResult<Handle<'a, JsValue>> {
+    let symbol = scope.global().get(scope, "Symbol")?.check::<JsFunction>()?;
+    let symbol_for = symbol.get(scope, "for")?.check::<JsFunction>()?;
+    let name = JsString::new_or_throw(scope, name)?;
+    symbol_for.call(scope, symbol, vec![name])
+}
+
+// Attributes only ever see the string form of objects and arrays, which may
+// come from a custom `toString` (e.g. `URL`), so let JS do the conversion.
+fn to_attribute_value(
+    scope: &mut RootScope,
+    value: Local,
+) -> VmResult<Option<PropValue>> {
+    match JsValue::from_raw(value).as_value(scope).variant() {
+        Variant::Object(_) | Variant::Array(_) => {
+            Ok(Some(PropValue::String(
+                to_string(scope, &JsValue::from_raw(value))
+            )))
+        },
+        _ => to_prop_value(scope, value),
+    }
+}
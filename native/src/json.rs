@@ -0,0 +1,360 @@
+/**
+ * JSON element trees.
+ *
+ * The format mirrors what `React.createElement` produces, so a tree can be
+ * dumped from JS with `JSON.stringify(element)` and rendered without JS:
+ *
+ *   {"type": "div", "props": {"className": "box", "children": [
+ *     "Hello ",
+ *     {"type": "b", "props": {"children": "world"}}
+ *   ]}}
+ *
+ * - An object with a string `type` is a DOM element. `props` is optional,
+ *   other keys (`key`, `ref`, `$$typeof`, ...) are ignored.
+ * - `props.children` is any child, `props.dangerouslySetInnerHTML` is
+ *   `{"__html": "..."}`.
+ * - Strings and numbers are text, arrays are fragments, `null` and booleans
+ *   render nothing.
+ *
+ * Prop order is preserved, so attributes come out in document order.
+ *
+ * Trees serialize back to the same format with `to_string`. Raw HTML is only
+ * representable as the single child of an element.
+ *
+ * serde_json refuses input nested more than `MAX_NESTING` levels deep, so a
+ * hostile tree can't overflow the stack. An element with an array of children
+ * takes three levels (itself, its props and the array), which caps trees at
+ * about 42 elements deep, or 64 with single children. Deeper input fails with
+ * an error saying so.
+ *
+ * JSON input is never trusted: `dangerouslySetInnerHTML` always becomes
+ * `Node::RawHtml`, so it is sanitized and refused with `trusted_html_only`
+ * like any other plain string. Trusted HTML serializes the same way, and
+ * comes back untrusted.
+ */
+use std::fmt;
+use std::io::Read;
+
+use serde::de::{
+    self,
+    Deserialize,
+    Deserializer,
+    IgnoredAny,
+    MapAccess,
+    SeqAccess,
+    Visitor,
+};
+use serde::ser::{self, Serialize, Serializer, SerializeMap};
+use serde_json;
+
+use element::{Element, Node, PropValue, Props};
+use partial_renderer::{render_to_string, render_to_static_markup};
+use util::number_to_string;
+
+const CHILDREN: &str = "children";
+const DANGEROUSLY_SET_INNER_HTML: &str = "dangerouslySetInnerHTML";
+const HTML: &str = "__html";
+
+// serde_json's recursion limit.
+pub const MAX_NESTING: usize = 128;
+
+pub fn from_str(json: &str) -> serde_json::Result<Node> {
+    serde_json::from_str(json).map_err(too_deep)
+}
+
+pub fn from_reader<R: Read>(reader: R) -> serde_json::Result<Node> {
+    serde_json::from_reader(reader).map_err(too_deep)
+}
+
+// serde_json only says "recursion limit exceeded".
+fn too_deep(error: serde_json::Error) -> serde_json::Error {
+    if !error.to_string().starts_with("recursion limit exceeded") {
+        return error;
+    }
+    de::Error::custom(format_args!(
+        "element tree nested more than {} JSON levels deep at line {} column {}",
+        MAX_NESTING,
+        error.line(),
+        error.column(),
+    ))
+}
+
+pub fn to_string(node: &Node) -> serde_json::Result<String> {
+    serde_json::to_string(node)
+}
+
+/**
+ * Render a JSON element tree to HTML that React can hydrate on the client.
+ */
+pub fn render_json_to_string(json: &str) -> serde_json::Result<String> {
+    from_str(json).map(|node| render_to_string(&node))
+}
+
+/**
+ * Render a JSON element tree to static HTML.
+ */
+pub fn render_json_to_static_markup(json: &str) -> serde_json::Result<String> {
+    from_str(json).map(|node| render_to_static_markup(&node))
+}
+
+impl<'de> Deserialize<'de> for PropValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(PropValueVisitor)
+    }
+}
+
+struct PropValueVisitor;
+
+impl<'de> Visitor<'de> for PropValueVisitor {
+    type Value = PropValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a prop value")
+    }
+
+    fn visit_unit<E>(self) -> Result<PropValue, E> {
+        Ok(PropValue::Null)
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<PropValue, E> {
+        Ok(PropValue::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<PropValue, E> {
+        Ok(PropValue::Number(value as f64))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<PropValue, E> {
+        Ok(PropValue::Number(value as f64))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<PropValue, E> {
+        Ok(PropValue::Number(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<PropValue, E> {
+        Ok(PropValue::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<PropValue, E> {
+        Ok(PropValue::String(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<PropValue, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(PropValue::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<PropValue, A::Error> {
+        let mut props = Props::new();
+        while let Some((name, value)) = map.next_entry::<String, PropValue>()? {
+            props.insert(name, value);
+        }
+        Ok(PropValue::Object(props))
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an element, string, number, array, boolean or null")
+    }
+
+    fn visit_unit<E>(self) -> Result<Node, E> {
+        Ok(Node::empty())
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Node, E> {
+        Ok(Node::empty())
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Node, E> {
+        Ok(Node::Text(value.to_string()))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Node, E> {
+        Ok(Node::Text(value.to_string()))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Node, E> {
+        Ok(Node::Text(number_to_string(value)))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Node, E> {
+        Ok(Node::Text(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Node, E> {
+        Ok(Node::Text(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+        let mut nodes = Vec::new();
+        while let Some(node) = seq.next_element()? {
+            nodes.push(node);
+        }
+        Ok(Node::Fragment(nodes))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+        let mut tag: Option<String> = None;
+        let mut props: Option<ElementProps> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "type" => tag = Some(map.next_value()?),
+                "props" => props = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                },
+            }
+        }
+        let tag = tag.ok_or_else(|| de::Error::missing_field("type"))?;
+        let props = props.unwrap_or_default();
+        let children = match props.inner_html {
+            Some(html) => vec![Node::RawHtml(html)],
+            None => props.children,
+        };
+        Ok(Node::Element(Element {
+            tag,
+            props: props.props,
+            children,
+        }))
+    }
+}
+
+#[derive(Default)]
+struct ElementProps {
+    props: Props,
+    children: Vec<Node>,
+    inner_html: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for ElementProps {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ElementPropsVisitor)
+    }
+}
+
+struct ElementPropsVisitor;
+
+impl<'de> Visitor<'de> for ElementPropsVisitor {
+    type Value = ElementProps;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a props object")
+    }
+
+    fn visit_unit<E>(self) -> Result<ElementProps, E> {
+        Ok(ElementProps::default())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<ElementProps, A::Error> {
+        let mut result = ElementProps::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                CHILDREN => {
+                    result.children = match map.next_value()? {
+                        Node::Fragment(nodes) => nodes,
+                        node => vec![node],
+                    };
+                },
+                DANGEROUSLY_SET_INNER_HTML => {
+                    result.inner_html = match map.next_value()? {
+                        PropValue::Object(inner) => match inner.get(HTML) {
+                            None | Some(&PropValue::Null) => None,
+                            Some(html) => Some(html.to_string()),
+                        },
+                        _ => None,
+                    };
+                },
+                _ => {
+                    let value: PropValue = map.next_value()?;
+                    result.props.insert(key, value);
+                },
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Serialize for PropValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            PropValue::Null => serializer.serialize_unit(),
+            PropValue::Bool(b) => serializer.serialize_bool(b),
+            PropValue::Number(n) => serializer.serialize_f64(n),
+            PropValue::String(ref s) => serializer.serialize_str(s),
+            PropValue::Array(ref items) => items.serialize(serializer),
+            PropValue::Object(ref props) => props.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for Props {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (name, value) in self {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Node {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Node::Element(ref element) => element.serialize(serializer),
+            Node::Text(ref text) => serializer.serialize_str(text),
+            Node::Fragment(ref nodes) => nodes.serialize(serializer),
+            Node::Cached(ref cached) => cached.node.serialize(serializer),
+            Node::Component(ref component) => component.node.serialize(serializer),
+            Node::RawHtml(_) | Node::TrustedHtml(_) => Err(ser::Error::custom(
+                "raw HTML can only be the only child of an element",
+            )),
+        }
+    }
+}
+
+impl Serialize for Element {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("type", &self.tag)?;
+        map.serialize_entry("props", &SerializeProps(self))?;
+        map.end()
+    }
+}
+
+struct SerializeProps<'a>(&'a Element);
+
+impl<'a> Serialize for SerializeProps<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let element = self.0;
+        let mut map = serializer.serialize_map(None)?;
+        for (name, value) in &element.props {
+            map.serialize_entry(name, value)?;
+        }
+        match element.children.as_slice() {
+            [] => {},
+            [Node::RawHtml(ref html)] | [Node::TrustedHtml(ref html)] => {
+                let mut inner = Props::new();
+                inner.insert(HTML, PropValue::String(html.clone()));
+                map.serialize_entry(DANGEROUSLY_SET_INNER_HTML, &inner)?;
+            },
+            [ref child] => map.serialize_entry(CHILDREN, child)?,
+            children => map.serialize_entry(CHILDREN, children)?,
+        }
+        map.end()
+    }
+}
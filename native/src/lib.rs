@@ -1,48 +1,99 @@
+#[cfg(feature = "node")]
 #[macro_use]
 extern crate neon;
+#[cfg(feature = "node")]
 extern crate neon_runtime;
+extern crate ammonia;
+extern crate base64;
 #[macro_use]
 extern crate lazy_static;
 #[macro_use]
 extern crate maplit;
 extern crate regex;
+extern crate serde;
+extern crate serde_json;
+extern crate sha2;
+#[cfg(test)]
+extern crate proptest;
 
+pub mod assets;
+pub mod cache;
+pub mod component;
+pub mod component_cache;
+pub mod csp;
+pub mod document;
+pub mod element;
+pub mod error;
+pub mod head;
+pub mod json;
+pub mod limits;
+pub mod name_safety;
+pub mod profile;
+pub mod sanitize;
+pub mod script_json;
+pub mod sink;
+pub mod stats;
+pub mod style_safety;
+pub mod trace;
+pub mod trusted_html;
+pub mod url_policy;
 mod dom_markup_operations;
+#[cfg(feature = "node")]
 mod dom_string_renderer;
+#[cfg(feature = "node")]
+mod js_cache;
+#[cfg(feature = "node")]
+mod js_element;
 mod partial_renderer;
 mod util;
 
-use std::ops::Deref;
-use std::error::Error;
-
-use neon_runtime::raw::Local;
-use neon::scope::{RootScope, Scope};
-use neon::vm::{This, Call, JsResult};
-use neon::mem::{
-    Managed, Handle
-};
-use neon::js::class::{Class, JsClass};
-use neon::js::{Value, Object};
-use neon::js::{
-    JsArray,
-    JsBoolean,
-    JsFunction,
-    JsNull,
-    JsNumber,
-    JsObject,
-    JsString,
-    JsUndefined,
-    JsValue,
+pub use component::Component;
+pub use document::Document;
+pub use element::{
+    Element,
+    Node,
+    PropValue,
+    Props,
 };
-
-use dom_string_renderer::{
+pub use error::RenderError;
+pub use limits::{LimitExceeded, Limits};
+pub use partial_renderer::{
+    DomServerRenderer,
+    ReadSize,
+    RenderOptions,
+    RenderResult,
+    render_into,
+    render_route,
     render_to_string,
     render_to_static_markup,
+    render_with_options,
+};
+pub use sink::{ChunkQueue, Sink, WriteSink};
+pub use url_policy::{UnsafeUrl, UnsafeUrlAction, UrlPolicy};
+pub use json::{
+    render_json_to_string,
+    render_json_to_static_markup,
 };
 
 
+#[cfg(feature = "node")]
 register_module!(m, {
-    m.export("renderToString", render_to_string)?;
-    m.export("renderToStaticMarkup", render_to_static_markup)?;
+    // Register native components here, they can then be used from JS as
+    // `React.createElement("PriceTable", props)`:
+    // component::register("PriceTable", price_table::render);
+    m.export("nativeComponents", dom_string_renderer::native_components)?;
+    m.export("renderToString", dom_string_renderer::render_to_string)?;
+    m.export("renderToStaticMarkup", dom_string_renderer::render_to_static_markup)?;
+    m.export("renderDocument", dom_string_renderer::render_document)?;
+    m.export("cacheStats", js_cache::cache_stats)?;
+    m.export("resizeCache", js_cache::resize_cache)?;
+    m.export("clearCaches", js_cache::clear_caches)?;
+    m.export("componentCacheStats", js_cache::component_cache_stats)?;
+    m.export("clearComponentCache", js_cache::clear_component_cache)?;
+    m.export("setUrlPolicy", dom_string_renderer::set_url_policy)?;
+    m.export("setSanitizer", dom_string_renderer::set_sanitizer)?;
+    m.export("setStyleSafety", dom_string_renderer::set_style_safety)?;
+    m.export("setInvalidNameAction", dom_string_renderer::set_invalid_name_action)?;
+    m.export("serializeForScript", dom_string_renderer::serialize_for_script)?;
     Ok(())
 });
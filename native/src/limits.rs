@@ -0,0 +1,93 @@
+/**
+ * Limits on a single render, so one pathological request (a huge list, a
+ * component that renders itself forever) can't block the process.
+ *
+ * They are checked as elements and components are converted and rendered.
+ * A render that exceeds one is aborted, its partial output dropped.
+ */
+use std::error::Error;
+use std::fmt;
+use std::time::Instant;
+
+use error::component_stack_string;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Limits {
+    // How deep elements and components may nest.
+    pub max_depth: Option<usize>,
+    pub max_bytes: Option<usize>,
+    pub deadline: Option<Instant>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit {
+    Depth(usize),
+    Bytes(usize),
+    Deadline,
+}
+
+/**
+ * A render was aborted. `component_stack` lists the components being
+ * rendered at the time, innermost first.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub component_stack: Vec<String>,
+}
+
+impl Limits {
+    pub fn is_empty(&self) -> bool {
+        *self == Limits::default()
+    }
+
+    /// Checks an element or component `depth` levels deep, with `bytes`
+    /// written so far.
+    pub fn check(&self, depth: usize, bytes: usize) -> Result<(), Limit> {
+        match self.max_depth {
+            Some(max_depth) if depth > max_depth => return Err(Limit::Depth(max_depth)),
+            _ => {},
+        }
+        match self.max_bytes {
+            Some(max_bytes) if bytes > max_bytes => return Err(Limit::Bytes(max_bytes)),
+            _ => {},
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(Limit::Deadline),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Limit {
+    /// `depth`, `bytes` or `deadline`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Limit::Depth(_) => "depth",
+            Limit::Bytes(_) => "bytes",
+            Limit::Deadline => "deadline",
+        }
+    }
+}
+
+impl LimitExceeded {
+    /// The stack as React formats it, `"\n    in Inner\n    in Outer"`.
+    pub fn component_stack_string(&self) -> String {
+        component_stack_string(&self.component_stack)
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.limit {
+            Limit::Depth(max) => write!(f, "Render exceeded the maximum depth of {}", max)?,
+            Limit::Bytes(max) => {
+                write!(f, "Render exceeded the maximum output size of {} bytes", max)?
+            },
+            Limit::Deadline => write!(f, "Render exceeded its deadline")?,
+        }
+        f.write_str(self.component_stack_string().as_str())
+    }
+}
+
+impl Error for LimitExceeded {}
@@ -0,0 +1,78 @@
+/**
+ * Tag and attribute names are written as they are, so a name from user data
+ * (a tag picked in a CMS, props spread from a query string) could inject
+ * markup: `<a onclick=alert(1) b="">`. Names are always checked, in release
+ * builds too. Invalid ones are left out with a warning, or fail the render.
+ */
+use std::error::Error;
+use std::fmt;
+use std::sync::RwLock;
+
+use error::component_stack_string;
+
+lazy_static! {
+    static ref ACTION: RwLock<InvalidNameAction> = RwLock::new(InvalidNameAction::Warn);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InvalidNameAction {
+    // Leave the element or attribute out, and print a warning the first time
+    // a name is seen.
+    Warn,
+    // Abort the render with a `RenderError::InvalidName`. Renders that can't
+    // fail warn instead.
+    Abort,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NameKind {
+    Tag,
+    Attribute,
+}
+
+impl NameKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            NameKind::Tag => "tag",
+            NameKind::Attribute => "attribute",
+        }
+    }
+}
+
+/**
+ * An invalid name found while rendering. `tag` is the element's, which is
+ * the name itself for a tag.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidName {
+    pub kind: NameKind,
+    pub name: String,
+    pub tag: String,
+    pub component_stack: Vec<String>,
+}
+
+impl fmt::Display for InvalidName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            NameKind::Tag => write!(f, "Invalid tag: {}", self.name)?,
+            NameKind::Attribute => {
+                write!(f, "Invalid attribute name on <{}>: `{}`", self.tag, self.name)?
+            },
+        }
+        f.write_str(component_stack_string(&self.component_stack).as_str())
+    }
+}
+
+impl Error for InvalidName {}
+
+/**
+ * What renders do with invalid names unless `RenderOptions::invalid_names`
+ * says otherwise, `Warn` by default.
+ */
+pub fn action() -> InvalidNameAction {
+    *ACTION.read().unwrap()
+}
+
+pub fn set_action(action: InvalidNameAction) {
+    *ACTION.write().unwrap() = action;
+}
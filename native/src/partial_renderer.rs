@@ -1,60 +1,54 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::cmp::{PartialOrd, Ordering};
-use std::time::{Instant, Duration};
-use std::ops::Deref;
-use std::sync::{Arc, Mutex};
-use std::collections::{HashSet, HashMap};
-
-use regex::{Regex, Captures};
-use neon_runtime::raw::Local;
-use neon::scope::{RootScope, Scope};
-use neon::vm::{This, Call, JsResult};
-use neon::mem::{
-    Managed, Handle
-};
-use neon::js::class::{Class, JsClass};
-use neon::js::{Value, Variant, Object};
-use neon::js::{
-    JsArray,
-    JsBoolean,
-    JsFunction,
-    JsNull,
-    JsNumber,
-    JsObject,
-    JsString,
-    JsUndefined,
-    JsValue,
-};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
 
-use util::{
-    duration_str,
-    not,
-    get_raw,
-    get_obj,
-    get_fn,
-    to_string,
+use regex::Regex;
 
+use assets::Assets;
+use cache::{LocalCache, ILLEGAL_TAG_CACHE, STYLE_NAME_CACHE, VALIDATED_TAG_CACHE};
+use component::{self, Component};
+use component_cache::{self, CachedHtml};
+use csp::InlineHashes;
+use document::Document;
+use element::{Element, Node, PropValue, Props};
+use error::RenderError;
+use head::{self, HeadCollector, head_key};
+use limits::{Limit, LimitExceeded, Limits};
+use name_safety::{self, InvalidName, InvalidNameAction, NameKind};
+use profile::{Profile, Profiler};
+use sanitize::{self, Sanitizer};
+use stats::RenderStats;
+use style_safety::{self, UnsafeStyle, UnsafeStyleAction};
+use trace::{self, Trace, Tracer};
+use trusted_html::{HtmlInjection, UntrustedHtml};
+use sink::{CountingSink, Sink, capacity_hint, record_capacity_hint};
+use url_policy::{self, UnsafeUrl, UnsafeUrlAction, UrlPolicy};
+use util::{
     hyphenate_style_name,
 
     get_intrinsic_namespace,
     get_child_namespace,
     is_custom_component,
-    escape_text_content_for_browser,
+    escape_html_into,
     dangerous_style_value,
 };
-use util::dom_namespaces::{
-    HTML_NAMESPACE,
-    NAMESPACES
-};
-use util::dom_property::PROPERTIES;
+use util::dom_namespaces::HTML_NAMESPACE;
 use util::omitted_close_tags::OMITTED_CLOSE_TAGS;
+use util::url_attribute_config::is_url_property;
 use dom_markup_operations::{
+    is_attribute_name_safe,
     create_markup_for_root,
     create_markup_for_property,
     create_markup_for_custom_attribute,
 };
 
 const STYLE: &str = "style";
-const VALUE_PROP_NAMES: [&'static str; 2] = ["value", "defaultValue"];
+const ROOT_ATTRIBUTE: &str = "data-reactroot";
+const VALUE_PROP_NAMES: [&str; 2] = ["value", "defaultValue"];
+const CHECKED_PROP_NAMES: [&str; 2] = ["checked", "defaultChecked"];
 
 lazy_static! {
     static ref RESERVED_PROPS: HashSet<&'static str> = hashset! {
@@ -66,400 +60,231 @@ lazy_static! {
     static ref NEWLINE_EATING_TAGS: HashSet<&'static str> = hashset! {
         "listing", "pre", "textarea",
     };
-    static ref STYLE_NAME_CACHE: Arc<Mutex<HashMap<String, String>>> = {
-        Arc::new(Mutex::new(HashMap::new()))
-    };
     // We accept any tag to be rendered but since this gets injected into arbitrary
     // HTML, we want to make sure that it's a safe tag.
     // http://www.w3.org/TR/REC-xml/#NT-Name
     static ref VALID_TAG_REGEX: Regex = {
         Regex::new(r"^[a-zA-Z][a-zA-Z:_\.\-\d]*$").unwrap() // Simplified subset
     };
-    static ref VALIDATED_TAG_CACHE: Arc<Mutex<HashSet<String>>> = {
-        Arc::new(Mutex::new(HashSet::new()))
-    };
 }
 
-
-fn get_children(scope: &mut RootScope, props: Local) -> Vec<JsValue> {
-    let children_raw = get_raw(scope, props, "children");
-    let val = JsValue::from_raw(children_raw).as_value(scope);
-    let mut children = Vec::new();
-    // NOTE: Can not use variant() here, beacuse a JsArray is also a JsObject.
-    if val.is_a::<JsArray>() {
-        for inner_val in JsArray::from_raw(val.to_raw()).to_vec(scope).unwrap() {
-            if inner_val.is_a::<JsArray>() {
-                children.extend(
-                    JsArray::from_raw(inner_val.to_raw())
-                        .to_vec(scope)
-                        .unwrap()
-                        .iter()
-                        .map(|v| v.deref())
-                );
-            } else if inner_val.is_a::<JsObject>()
-                || inner_val.is_a::<JsString>()
-                || inner_val.is_a::<JsNumber>()
-            {
-                children.push(*inner_val);
-            } else if inner_val.is_a::<JsUndefined>()
-                || inner_val.is_a::<JsNull>()
-                || inner_val.is_a::<JsBoolean>()
-            {
-            } else {
-                println!(
-                    "[WARN]: Inner Children={}",
-                    to_string(scope, &JsValue::from_raw(inner_val.to_raw()))
-                );
-                // panic!("Unexpected children type");
-            }
-        }
-    } else if val.is_a::<JsObject>()
-        || val.is_a::<JsString>()
-        || val.is_a::<JsNumber>()
-    {
-        children.push(*val);
-    } else if val.is_a::<JsUndefined>()
-        || val.is_a::<JsNull>()
-        || val.is_a::<JsBoolean>()
-    {
-    } else {
-        println!(
-            "[WARN]: Children={}",
-            to_string(scope, &JsValue::from_raw(children_raw))
-        );
-        // panic!("Unexpected children type");
-    }
-    children
+thread_local! {
+    static STYLE_NAMES: RefCell<LocalCache<String>> = RefCell::new(LocalCache::new());
+    static VALIDATED_TAGS: RefCell<LocalCache<()>> = RefCell::new(LocalCache::new());
+    static ILLEGAL_TAGS: RefCell<LocalCache<()>> = RefCell::new(LocalCache::new());
 }
 
-fn validate_dangerous_tag(tag: &str) {
-    let mut cache = VALIDATED_TAG_CACHE.lock().unwrap();
-    if !cache.contains(tag) {
-        debug_assert!(
-            VALID_TAG_REGEX.is_match(tag),
-            format!("Invalid tag: {}", tag)
-        );
-        cache.insert(tag.to_string());
-    }
-}
 
-fn should_construct(
-    scope: &mut RootScope,
-    component: &Handle<JsValue>
-) -> bool {
-    let prototype = JsObject
-        ::from_raw(component.to_raw())
-        .get(scope, "prototype")
-        .unwrap();
-    if prototype.is_a::<JsObject>() {
-        let is_react_component = JsObject
-            ::from_raw(prototype.to_raw())
-            .get(scope, "isReactComponent")
-            .unwrap();
-        !not(is_react_component)
-    } else {
-        false
-    }
-}
-
-fn get_component_name(
-    scope: &mut RootScope,
-    type_val: Handle<JsValue>
-) -> Option<String> {
-    match type_val.variant() {
-        Variant::String(s) => Some(s.value()),
-        Variant::Function(f) => {
-            let f_obj = JsObject::from_raw(f.to_raw());
-            let display_name = f_obj
-                .get(scope, "displayName")
-                .unwrap();
-            if display_name.is_a::<JsString>() {
-                Some(to_string(scope, display_name.deref()))
-            } else {
-                let name = f_obj
-                    .get(scope, "name")
-                    .unwrap();
-                if name.is_a::<JsString>() {
-                    Some(to_string(scope, name.deref()))
-                } else {
-                    None
-                }
-            }
-        },
-        _ => None
+// Whether `tag` can be written as is. With `warn`, the first time an illegal
+// tag is seen on this thread prints a warning.
+fn validate_dangerous_tag(tag: &str, warn: bool) -> bool {
+    let validated = VALIDATED_TAGS.with(|cache| {
+        cache.borrow_mut().contains(&VALIDATED_TAG_CACHE, tag)
+    });
+    if validated {
+        return true;
     }
+    ILLEGAL_TAGS.with(|illegal_cache| {
+        let mut illegal_cache = illegal_cache.borrow_mut();
+        if illegal_cache.contains(&ILLEGAL_TAG_CACHE, tag) {
+            return false;
+        }
+        if VALID_TAG_REGEX.is_match(tag) {
+            VALIDATED_TAGS.with(|cache| {
+                cache.borrow_mut().insert(&VALIDATED_TAG_CACHE, tag, ());
+            });
+            return true;
+        }
+        if warn {
+            eprintln!("Warning: Invalid tag: {}", tag);
+        }
+        illegal_cache.insert(&ILLEGAL_TAG_CACHE, tag, ());
+        false
+    })
 }
 
-fn process_style_name(name: &str) -> String {
-    let mut cache = STYLE_NAME_CACHE.lock().unwrap();
-    if !cache.contains_key(name) {
-        cache.insert(name.to_string(), hyphenate_style_name(name));
-    }
-    cache.get(name).map(|s| s.clone()).unwrap()
+fn process_style_name(out: &mut dyn Sink, name: &str) {
+    STYLE_NAMES.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let name = cache.get_or_insert_with(
+            &STYLE_NAME_CACHE, name, hyphenate_style_name
+        );
+        escape_html_into(out, name.as_str());
+    });
 }
 
+/**
+ * Writes the ` style="..."` attribute for a style object, nothing if it
+ * has no non-null values. The serialized styles are escaped as they are
+ * written, like any other attribute value.
+ *
+ * Declarations `keep` refuses are left out, see `style_safety`.
+ */
 fn create_markup_for_styles(
-    scope: &mut RootScope,
-    styles: Handle<JsValue>,
-) -> Option<String> {
-    let mut serialized = String::new();
+    out: &mut dyn Sink,
+    styles: &PropValue,
+    keep: &mut dyn FnMut(&str, &str) -> bool,
+) {
+    let styles = match *styles {
+        PropValue::Object(ref styles) => styles,
+        _ => return,
+    };
     let mut is_first = true;
-    let own_property_names = styles.downcast::<JsObject>()
-        .unwrap()
-        .get_own_property_names(scope)
-        .unwrap()
-        .to_vec(scope)
-        .unwrap();
-    for style_name in own_property_names {
-        let style_name = style_name
-            .downcast::<JsString>()
-            .unwrap()
-            .value();
+    for (style_name, style_value) in styles {
         let is_custom_property = style_name.starts_with("--");
-        let style_value = get_raw(
-            scope, styles.to_raw(), style_name.as_str()
-        );
-        let style_value_handle = JsValue::from_raw(style_value).as_value(scope);
-        if !style_value_handle.is_a::<JsNull>() {
-            let delimiter = if is_first {""} else {";"};
-            let name = process_style_name(style_name.as_str());
+        if !style_value.is_null() {
             let value = dangerous_style_value(
                 style_name.as_str(),
-                style_value_handle,
+                style_value,
                 is_custom_property,
             );
-            serialized.push_str(format!("{}{}:{}", delimiter, name, value).as_str());
+            if !keep(style_name.as_str(), value.as_str()) {
+                continue;
+            }
+            out.write_str(if is_first { " style=\"" } else { ";" });
+            process_style_name(out, style_name.as_str());
+            out.write_char(':');
+            escape_html_into(out, value.as_str());
             is_first = false;
         }
     }
-    if serialized.len() > 0 { Some(serialized) } else { None }
+    if !is_first {
+        out.write_char('"');
+    }
 }
 
-fn get_non_children_inner_markup(
-    scope: &mut RootScope,
-    props: Local,
-    children: &Vec<JsValue>,
-) -> Option<String> {
-    let inner_html = get_raw(scope, props, "dangerouslySetInnerHTML");
-    if JsValue::from_raw(inner_html).as_value(scope).is_a::<JsObject>() {
-        let the_html = get_raw(scope, inner_html, "__html");
-        if !JsValue::from_raw(the_html).as_value(scope).is_a::<JsNull>() {
-            return Some(to_string(scope, &JsValue::from_raw(the_html)));
-        }
-    } else {
-        if children.len() == 1 {
-            let content: Handle<JsValue> = children[0].as_value(scope);
-            if content.is_a::<JsString>() || content.is_a::<JsNumber>() {
-                return Some(escape_text_content_for_browser(scope, content));
-            }
+enum InnerMarkup<'a> {
+    Html { html: Cow<'a, str>, trusted: bool },
+    Text(&'a str),
+    // Raw HTML that isn't trusted, where only trusted HTML may go.
+    Refused,
+}
+
+// Component markers only exist to observe a render, they never change it.
+fn unmarked(node: &Node) -> &Node {
+    match *node {
+        Node::Component(ref component) => unmarked(&component.node),
+        _ => node,
+    }
+}
+
+// Raw HTML is cleaned by `sanitizer`, unless it is trusted.
+fn sanitized<'a>(sanitizer: Option<&Sanitizer>, html: &'a str) -> Cow<'a, str> {
+    match sanitizer {
+        Some(sanitizer) => Cow::Owned(sanitizer.clean(html)),
+        None => Cow::Borrowed(html),
+    }
+}
+
+fn get_non_children_inner_markup<'a>(
+    children: &'a [Node],
+    sanitizer: Option<&Sanitizer>,
+    trusted_html_only: bool,
+) -> Option<InnerMarkup<'a>> {
+    if children.len() == 1 {
+        match *unmarked(&children[0]) {
+            Node::RawHtml(_) if trusted_html_only => return Some(InnerMarkup::Refused),
+            Node::RawHtml(ref html) => {
+                let html = sanitized(sanitizer, html);
+                return Some(InnerMarkup::Html { html, trusted: false });
+            },
+            Node::TrustedHtml(ref html) => {
+                let html = Cow::Borrowed(html.as_str());
+                return Some(InnerMarkup::Html { html, trusted: true });
+            },
+            Node::Text(ref text) => return Some(InnerMarkup::Text(text)),
+            _ => {}
         }
     }
     None
 }
 
-fn create_open_tag_markup(
-    scope: &mut RootScope,
-    tag_verbatim: &str,
-    tag_lowercase: &str,
-    props: JsObject,
-    namespace: &str,
-    make_static_markup: bool,
-    is_root_element: bool,
-) -> String {
-    let mut ret = format!("<{}", tag_verbatim);
-    let own_property_names = props
-        .get_own_property_names(scope)
-        .unwrap()
-        .to_vec(scope)
-        .unwrap();
-    for prop_key in own_property_names {
-        let mut prop_key: String = prop_key
-            .downcast::<JsString>()
-            .unwrap()
-            .value();
-        let mut prop_value: Handle<JsValue> = props
-            .get(scope, prop_key.as_str())
-            .unwrap();
-        if prop_value.is_a::<JsNull>() {
-            continue;
-        }
-        if prop_key == STYLE {
-            prop_value = create_markup_for_styles(scope, prop_value)
-                .map(|value| {
-                    JsString::new(scope, value.as_str())
-                        .unwrap()
-                        .as_value(scope)
-                })
-                .unwrap_or_else(|| {
-                    JsNull::new().as_value(scope)
-                });
-        }
-        let mut markup: Option<String> = None;
-        if is_custom_component(tag_lowercase, props.as_value(scope)) {
-            if !RESERVED_PROPS.contains(prop_key.as_str()) {
-                markup = Some(create_markup_for_custom_attribute(
-                    scope, prop_key.as_str(), prop_value
-                ));
-            }
-        } else {
-            markup = create_markup_for_property(
-                scope, prop_key.as_str(), prop_value
-            );
-        }
-        if let Some(markup) = markup {
-            ret.push_str(format!(" {}", markup).as_str());
+// `props.value != null ? props.value : props.defaultValue`
+fn get_value_prop<'a>(props: &'a Props, names: &[&str; 2]) -> Option<&'a PropValue> {
+    names.iter()
+        .filter_map(|name| props.get(name))
+        .find(|value| !value.is_null())
+}
+
+// `'' + children`
+fn text_of(node: &Node) -> String {
+    match *node {
+        Node::Text(ref text) => text.clone(),
+        Node::Fragment(ref children) => {
+            children.iter().map(text_of).collect::<Vec<_>>().join(",")
+        },
+        Node::Cached(ref cached) => text_of(&cached.node),
+        Node::Component(ref component) => text_of(&component.node),
+        Node::Element(_) | Node::RawHtml(_) | Node::TrustedHtml(_) => {
+            "[object Object]".to_string()
+        },
+    }
+}
+
+fn flatten_option_children(children: &[Node]) -> String {
+    let mut content = String::new();
+    for child in children {
+        match *child {
+            Node::Text(ref text) => content.push_str(text.as_str()),
+            Node::Fragment(ref children) => {
+                content.push_str(flatten_option_children(children).as_str());
+            },
+            Node::Cached(ref cached) => {
+                let children = ::std::slice::from_ref(&cached.node);
+                content.push_str(flatten_option_children(children).as_str());
+            },
+            Node::Component(ref component) => {
+                let children = ::std::slice::from_ref(&component.node);
+                content.push_str(flatten_option_children(children).as_str());
+            },
+            _ => {}
         }
     }
+    content
+}
 
-    // For static pages, no need to put React ID and checksum. Saves lots of
-    // bytes.
-    if make_static_markup {
-        return ret;
+fn input_props(props: &Props) -> Props {
+    let mut result = Props::new();
+    // The `type` attribute always comes first.
+    if let Some(input_type) = props.get("type") {
+        result.insert("type", input_type.clone());
     }
-    if is_root_element {
-        ret.push_str(" ");
-        ret.push_str(create_markup_for_root().as_str());
+    for (name, value) in props {
+        if name != "defaultValue" && name != "defaultChecked" {
+            result.insert(name.as_str(), value.clone());
+        }
     }
-    ret
+    let value = get_value_prop(props, &VALUE_PROP_NAMES)
+        .cloned()
+        .unwrap_or(PropValue::Null);
+    let checked = get_value_prop(props, &CHECKED_PROP_NAMES)
+        .cloned()
+        .unwrap_or(PropValue::Null);
+    result.insert("value", value);
+    result.insert("checked", checked);
+    result
 }
 
-fn resolve(
-    scope: &mut RootScope,
-    child: Local,
-    context: JsObject
-) -> (Local, JsObject) {
-    let type_raw = get_raw(scope, child, "type");
-    let type_obj = JsObject::from_raw(type_raw);
-    let props = get_obj(scope, child, "props");
-    let type_val = type_obj.as_value(scope);
-    let mut rendered_component: Option<Local> = None;
-    if type_val.is_a::<JsFunction>() {
-        let props = props.as_value(scope);
-        let instance: Handle<JsObject> = JsFunction::from_raw(type_raw)
-            .construct(scope, vec![props])
-            .unwrap();
-        let render_fn = get_fn(scope, instance.to_raw(), "render");
-        let this = instance.as_value(scope);
-        let obj = JsObject::from_raw(instance.to_raw()).as_value(scope);
-        rendered_component = Some(
-            render_fn
-                .call(scope, this, vec![obj])
-                .unwrap()
-                .to_raw()
-        );
+fn textarea_value(props: &Props, children: &[Node]) -> String {
+    if let Some(value) = props.get("value").filter(|value| !value.is_null()) {
+        return value.to_string();
+    }
+    if let Some(child) = children.first() {
+        return text_of(child);
     }
-    (rendered_component.unwrap(), context)
+    props.get("defaultValue")
+        .filter(|value| !value.is_null())
+        .map(|value| value.to_string())
+        .unwrap_or_default()
 }
 
-fn render_type(
-    html: &mut String,
-    scope: &mut RootScope,
-    component: Local,
-    static_markup: bool,
-    previous_was_text_node: bool,
-    level: u32,
-) -> Duration {
-    let mut render_cost: Duration = Duration::from_secs(0);
-    let mut current_is_text_node = false;
-    let type_raw = get_raw(scope, component, "type");
-    let type_obj = JsObject::from_raw(type_raw);
-    let props = get_obj(scope, component, "props");
-    let type_val = type_obj.as_value(scope);
-    if type_val.is_a::<JsFunction>() {
-        let props = props.as_value(scope);
-        let now = Instant::now();
-        let instance: Handle<JsObject> = JsFunction::from_raw(type_raw)
-            .construct(scope, vec![props])
-            .unwrap();
-        let render_fn = get_fn(scope, instance.to_raw(), "render");
-        let this = instance.as_value(scope);
-        let obj = JsObject::from_raw(instance.to_raw()).as_value(scope);
-        let rendered_component = render_fn
-            .call(scope, this, vec![obj])
-            .unwrap();
-        render_cost += now.elapsed();
-        render_cost += render_type(
-            html,
-            scope,
-            rendered_component.deref().to_raw(),
-            static_markup,
-            current_is_text_node,
-            level+1
-        );
-    } else if type_val.is_a::<JsString>() {
-        let type_str = to_string(scope, &type_obj);
-        let tag = type_str.to_lowercase();
-
-        let mut header = create_open_tag_markup(
-            scope,
-            type_str.as_str(),
-            tag.as_str(),
-            props,
-            "",
-            static_markup,
-            level == 0,
-        );
-        let mut footer = String::new();
-        if OMITTED_CLOSE_TAGS.contains(tag.as_str()) {
-            header.push_str("/>");
-        } else {
-            header.push_str(">");
-            footer = format!("</{}>", type_str);
-        }
-        html.push_str(header.as_str());
-        let children = get_children(scope, props.to_raw());
-        // FIXME:
-        if let Some(content) = get_non_children_inner_markup(
-            scope, props.to_raw(), &children
-        ) {
-            if NEWLINE_EATING_TAGS.contains(tag.as_str())
-                && content.chars().nth(0) == Some('\n')
-            {
-                html.push_str("\n");
-            }
-            html.push_str(content.as_str());
-        } else {
-            for child in children {
-                let child_val = child.as_value(scope);
-                if child_val.is_a::<JsString>()
-                    || child_val.is_a::<JsNumber>()
-                {
-                    let content = escape_text_content_for_browser(scope, child_val);
-                    if previous_was_text_node {
-                        html.push_str("<!-- -->");
-                    }
-                    html.push_str(content.as_str());
-                    current_is_text_node = true;
-                } else if child_val.is_a::<JsObject>() {
-                    render_cost += render_type(
-                        html,
-                        scope,
-                        child.to_raw(),
-                        static_markup,
-                        current_is_text_node,
-                        level+1
-                    );
-                } else {
-                    println!(">>> child={}", to_string(scope, &child));
-                    panic!("Invalid child type");
-                }
-            }
-        };
-        html.push_str(footer.as_str());
-    } else {
-        if JsValue::from_raw(component).as_value(scope).is_a::<JsArray>() {
-            println!("component is a JsArray");
-        }
-        println!(">>> component={}", to_string(scope, &JsObject::from_raw(component)));
-        println!(">>> type={}", to_string(scope, &type_obj));
-        println!(">>> props={}", to_string(scope, &props));
-        let children = get_children(scope, props.to_raw());
-        for child in children {
-            println!(">>> child={}", to_string(scope, &child));
-        }
-        panic!("Invalid component type");
+fn is_option_selected(select_value: &PropValue, value: &str) -> bool {
+    match *select_value {
+        PropValue::Array(ref values) => {
+            values.iter().any(|v| v.to_string() == value)
+        },
+        _ => select_value.to_string() == value,
     }
-    render_cost
 }
 
 #[derive(Eq, PartialEq)]
@@ -482,103 +307,1344 @@ impl PartialOrd for ReadSize {
     }
 }
 
-pub struct Frame {
-    dom_namespace: &'static str,
-    children: Vec<JsValue>,
-    child_index: u32,
-    context: JsObject,
-    footer: String,
-}
-
 pub struct DomServerRenderer<'a> {
-    pub call: Call<'a>,
-    stack: Vec<Frame>,
+    root: &'a Node,
     exhausted: bool,
-    current_select_value: JsValue,
+    // Markup rendered but not read yet, see `read`.
+    unread: Option<String>,
+    current_select_value: Option<PropValue>,
     previous_was_text_node: bool,
+    // Whether the output so far starts with text, `None` until something is
+    // written. Cached markup needs it to know whether to emit a separator.
+    leading_text: Option<bool>,
     static_markup: bool,
+    profiler: Option<Profiler>,
+    tracer: Option<Tracer>,
+    stats: Option<RenderStats>,
+    // How many components the current node is nested in.
+    component_depth: usize,
+    limits: Limits,
+    // Names of the components being rendered, innermost last.
+    component_stack: Vec<String>,
+    // Bytes written before the current sink, which may be a cache entry's.
+    bytes_offset: usize,
+    // The depth of the deepest element or component so far, as `limits`
+    // counts it, so cached markup knows how deep it goes.
+    deepest: usize,
+    error: Option<RenderError>,
+    url_policy: Arc<UrlPolicy>,
+    unsafe_urls: Vec<UnsafeUrl>,
+    // Whether the caller checks `take_error`.
+    fallible: bool,
+    nonce: Option<String>,
+    inline_hashes: Option<InlineHashes>,
+    sanitizer: Option<Arc<Sanitizer>>,
+    trusted_html_only: bool,
+    html_injections: Option<Vec<HtmlInjection>>,
+    // `None` with the style safety mode off.
+    style_safety: Option<UnsafeStyleAction>,
+    unsafe_styles: Vec<UnsafeStyle>,
+    invalid_name_action: InvalidNameAction,
+    invalid_names: Vec<InvalidName>,
+    head: Option<HeadCollector>,
+    document: Option<&'a Document>,
+    assets: Option<&'a Assets>,
+    // Whether the asset scripts are still to be written, see `take_scripts`.
+    scripts_pending: bool,
+    scripts: Option<String>,
+    // How often the output depended on more than the tree: unsafe URLs to
+    // report, nonces, inline hashes, raw HTML, unsafe styles and invalid
+    // names to report, head elements, asset scripts. A cache hit wouldn't
+    // reproduce it.
+    uncacheable: usize,
 }
 
 impl<'a> DomServerRenderer<'a> {
-    pub fn new(call: Call<'a>, static_markup: bool) -> Self {
-        let top_frame = Frame {
-            dom_namespace: HTML_NAMESPACE,
-            children: vec![],
-            child_index: 0,
-            context: JsObject::from_raw(
-                JsObject::new(call.scope)
-                    .deref()
-                    .to_raw()
-            ),
-            footer: String::new(),
-        };
-        let stack = vec![top_frame];
-        let exhausted = false;
-        let current_select_value = *JsNull::new().as_value(call.scope).deref();
-        let previous_was_text_node = false;
-        DomServerRenderer{
-            call,
-            stack,
-            exhausted,
-            current_select_value,
-            previous_was_text_node,
+    pub fn new(root: &'a Node, static_markup: bool) -> Self {
+        DomServerRenderer {
+            root,
+            exhausted: false,
+            unread: None,
+            current_select_value: None,
+            previous_was_text_node: false,
+            leading_text: None,
             static_markup,
+            profiler: None,
+            tracer: None,
+            stats: None,
+            component_depth: 0,
+            limits: Limits::default(),
+            component_stack: Vec::new(),
+            bytes_offset: 0,
+            deepest: 0,
+            error: None,
+            url_policy: url_policy::policy(),
+            unsafe_urls: Vec::new(),
+            fallible: false,
+            nonce: None,
+            inline_hashes: None,
+            sanitizer: sanitize::sanitizer(),
+            trusted_html_only: false,
+            html_injections: None,
+            style_safety: style_safety::action(),
+            unsafe_styles: Vec::new(),
+            invalid_name_action: name_safety::action(),
+            invalid_names: Vec::new(),
+            head: None,
+            document: None,
+            assets: None,
+            scripts_pending: false,
+            scripts: None,
+            uncacheable: 0,
         }
     }
 
+    /// Record a profile of the components rendered, see `take_profile`.
+    pub fn with_profile(mut self) -> Self {
+        self.profiler = Some(Profiler::new());
+        self
+    }
+
+    /// The profile of a finished render, if one was recorded.
+    pub fn take_profile(&mut self) -> Option<Vec<Profile>> {
+        self.profiler.take().map(Profiler::finish)
+    }
+
+    /// Record a trace of the components and elements rendered, see
+    /// `take_trace`.
+    pub fn with_trace(mut self) -> Self {
+        self.tracer = Some(Tracer::new());
+        self
+    }
+
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.tracer.take().map(Tracer::finish)
+    }
+
+    /// Count what is rendered, see `take_stats`.
+    pub fn with_stats(mut self) -> Self {
+        self.stats = Some(RenderStats::new());
+        self
+    }
+
+    pub fn take_stats(&mut self) -> Option<RenderStats> {
+        self.stats.take()
+    }
+
+    /// Abort the render once it exceeds `limits`, see `take_error`.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self.fallible = true;
+        self
+    }
+
+    /// Abort the render on unsafe URLs if the URL policy says so, see
+    /// `take_error`. Otherwise they are dropped.
+    pub fn fallible(mut self) -> Self {
+        self.fallible = true;
+        self
+    }
+
+    /// Why the render was aborted, if it was. Its output is incomplete.
+    pub fn take_error(&mut self) -> Option<RenderError> {
+        self.error.take()
+    }
+
+    /// Check URL attributes against `policy` instead of the global one.
+    pub fn with_url_policy(mut self, policy: Arc<UrlPolicy>) -> Self {
+        self.url_policy = policy;
+        self
+    }
+
+    /// Clean raw HTML with `sanitizer` instead of the global one.
+    pub fn with_sanitizer(mut self, sanitizer: Arc<Sanitizer>) -> Self {
+        self.sanitizer = Some(sanitizer);
+        self
+    }
+
+    /// Refuse raw HTML that isn't trusted (`Node::RawHtml`): abort the render
+    /// if it is fallible, see `take_error`, or leave it out.
+    pub fn with_trusted_html_only(mut self) -> Self {
+        self.trusted_html_only = true;
+        self
+    }
+
+    /// Record the raw HTML written, see `take_html_injections`.
+    pub fn with_html_injections(mut self) -> Self {
+        self.html_injections = Some(Vec::new());
+        self
+    }
+
+    /// The raw HTML written and where, in render order.
+    pub fn take_html_injections(&mut self) -> Option<Vec<HtmlInjection>> {
+        self.html_injections.take()
+    }
+
+    /// Check inline styles, doing `action` with unsafe ones instead of what
+    /// the global setting says, see `style_safety` and `take_error`.
+    pub fn with_style_safety(mut self, action: UnsafeStyleAction) -> Self {
+        self.style_safety = Some(action);
+        self
+    }
+
+    /// The unsafe styles found, in render order.
+    pub fn take_unsafe_styles(&mut self) -> Vec<UnsafeStyle> {
+        ::std::mem::take(&mut self.unsafe_styles)
+    }
+
+    /// Do `action` with invalid tag and attribute names instead of what the
+    /// global setting says, see `name_safety`.
+    pub fn with_invalid_name_action(mut self, action: InvalidNameAction) -> Self {
+        self.invalid_name_action = action;
+        self
+    }
+
+    /// The invalid names found, in render order.
+    pub fn take_invalid_names(&mut self) -> Vec<InvalidName> {
+        ::std::mem::take(&mut self.invalid_names)
+    }
+
+    /// Collect the children of `<Head>` elements and the `<head>` into the
+    /// head, see `head` and `take_head`.
+    pub fn with_head(mut self) -> Self {
+        self.head = Some(HeadCollector::new());
+        self
+    }
+
+    /// The head markup collected, and where it goes in the output if a
+    /// `<head>` was rendered. It has to be inserted there.
+    pub fn take_head(&mut self) -> Option<HeadCollector> {
+        self.head.take()
+    }
+
+    /// Render the tree into `document`, see `document`. Its head is collected
+    /// like with `with_head`.
+    pub fn with_document(mut self, document: &'a Document) -> Self {
+        self.document = Some(document);
+        self.with_head()
+    }
+
+    /// Write the tags for `assets`, see `assets`: links into the head, which
+    /// is collected, and scripts at the end of the first `<body>`.
+    pub fn with_assets(mut self, assets: &'a Assets) -> Self {
+        self.assets = Some(assets);
+        self.scripts_pending = true;
+        if self.head.is_none() {
+            self.head = Some(HeadCollector::new());
+        }
+        self
+    }
+
+    /// The asset scripts of a finished render if there was no `<body>` to
+    /// write them into.
+    pub fn take_scripts(&mut self) -> Option<String> {
+        self.scripts.take()
+    }
+
+    /// Stamp `nonce` on every `<script>` and `<style>` element that doesn't
+    /// have one, for a Content Security Policy.
+    pub fn with_nonce(mut self, nonce: String) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Hash the bodies of inline scripts and styles, see `take_inline_hashes`.
+    pub fn with_inline_hashes(mut self) -> Self {
+        self.inline_hashes = Some(InlineHashes::new());
+        self
+    }
+
+    pub fn take_inline_hashes(&mut self) -> Option<InlineHashes> {
+        self.inline_hashes.take()
+    }
+
+    /// The unsafe URLs found, in render order.
+    pub fn take_unsafe_urls(&mut self) -> Vec<UnsafeUrl> {
+        ::std::mem::take(&mut self.unsafe_urls)
+    }
+
+    /**
+     * The next `size` bytes of markup (or a little more, to end on a
+     * character boundary), `None` once all of it was read. Rendering isn't
+     * incremental: the first read renders the whole tree, later ones hand
+     * out the rest of it. To stream, render into a `ChunkQueue` instead.
+     */
     pub fn read(&mut self, size: ReadSize) -> Option<String> {
-        let now = Instant::now();
-        let mut html = String::new();
-        let component = self.call
-            .arguments
-            .get(self.call.scope, 0)
-            .unwrap()
-            .to_raw();
-        let render_cost = render_type(
-            &mut html, self.call.scope, component, self.static_markup, false, 0
-        );
-        // println!("[Render cost]: {}", duration_str(render_cost));
-        // println!("[Render total cost]: {}", duration_str(now.elapsed()));
+        if !self.exhausted {
+            let mut html = String::new();
+            self.read_into(&mut html);
+            self.unread = Some(html);
+        }
+        let mut html = self.unread.take()?;
+        if let ReadSize::Size(size) = size {
+            if size < html.len() {
+                let mut end = size.max(1);
+                while !html.is_char_boundary(end) {
+                    end += 1;
+                }
+                self.unread = Some(html.split_off(end));
+            }
+        }
         Some(html)
     }
 
-    pub fn render(
+    /**
+     * Renders the whole tree into `out`. Returns false if it was already
+     * rendered.
+     */
+    pub fn read_into(&mut self, out: &mut dyn Sink) -> bool {
+        if self.exhausted {
+            return false;
+        }
+        let root = self.root;
+        let mut out = CountingSink::new(out);
+        match self.document {
+            Some(document) => self.render_document(&mut out, document, root),
+            None => self.render(&mut out, root, HTML_NAMESPACE, 0),
+        }
+        // The tree had no `<head>` or `<body>` for the assets.
+        if self.head.as_ref().is_some_and(|head| head.position.is_none()) {
+            self.collect_asset_links();
+        }
+        if self.scripts_pending {
+            let mut scripts = String::new();
+            self.render_asset_scripts(&mut CountingSink::new(&mut scripts), 1);
+            self.scripts = Some(scripts);
+        }
+        // Text written after the last element wasn't checked yet.
+        if let Some(max_bytes) = self.limits.max_bytes {
+            if self.error.is_none() && out.written() > max_bytes {
+                self.exceeded(Limit::Bytes(max_bytes));
+            }
+        }
+        self.exhausted = true;
+        true
+    }
+
+    fn exceeded(&mut self, limit: Limit) {
+        self.error = Some(RenderError::LimitExceeded(LimitExceeded {
+            limit,
+            component_stack: self.stack(),
+        }));
+    }
+
+    fn stack(&self) -> Vec<String> {
+        self.component_stack.iter().rev().cloned().collect()
+    }
+
+    // Whether rendering an element or component `depth` deep may go on.
+    fn within_limits(&mut self, out: &CountingSink, depth: usize) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+        match self.limits.check(depth, self.bytes_offset + out.written()) {
+            Ok(()) => true,
+            Err(limit) => {
+                self.exceeded(limit);
+                false
+            },
+        }
+    }
+
+    fn render(
+        &mut self,
+        out: &mut CountingSink,
+        node: &Node,
+        parent_namespace: &'static str,
+        depth: u32,
+    ) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Node::Element(_) | Node::Component(_) = *node {
+            // Components count, like when converting from JS.
+            let depth = depth as usize + self.component_depth + 1;
+            self.deepest = self.deepest.max(depth);
+            if !self.limits.is_empty() && !self.within_limits(out, depth) {
+                return;
+            }
+        }
+        match *node {
+            Node::Text(ref text) => self.render_text(out, text),
+            Node::RawHtml(_) if self.trusted_html_only => self.refuse_html(None),
+            Node::RawHtml(ref raw) => {
+                let raw = sanitized(self.sanitizer.as_deref(), raw);
+                self.inject_html(None, &raw, false);
+                self.render_raw(out, &raw);
+            },
+            Node::TrustedHtml(ref raw) => {
+                self.inject_html(None, raw, true);
+                self.render_raw(out, raw);
+            },
+            Node::Fragment(ref children) => {
+                for child in children {
+                    self.render(out, child, parent_namespace, depth);
+                }
+            },
+            Node::Cached(ref cached) => {
+                self.render_cached(
+                    out, cached.key.as_str(), cached.ttl, parent_namespace, depth,
+                    |renderer, out| {
+                        renderer.render(out, &cached.node, parent_namespace, depth)
+                    },
+                );
+            },
+            Node::Component(ref component) => {
+                if let Some(ref mut tracer) = self.tracer {
+                    tracer.complete(
+                        component.name.as_str(), trace::CONVERT,
+                        component.started, component.elapsed,
+                    );
+                }
+                self.within_component(
+                    out, component.name.as_str(), component.elapsed,
+                    |renderer, out| {
+                        renderer.render(out, &component.node, parent_namespace, depth)
+                    },
+                );
+            },
+            Node::Element(ref element) if element.tag == head::HEAD => {
+                // Head elements are never roots.
+                if self.head.is_some() {
+                    self.collect_head(&element.children, depth + 1);
+                } else {
+                    for child in &element.children {
+                        self.render(out, child, parent_namespace, depth);
+                    }
+                }
+            },
+            Node::Element(ref element) => match component::get(element.tag.as_str()) {
+                Some(component) => {
+                    self.within_component(
+                        out, element.tag.as_str(), Duration::new(0, 0),
+                        |renderer, out| renderer.render_component(
+                            out, &*component, element, parent_namespace, depth
+                        ),
+                    );
+                },
+                None => self.traced(
+                    out, element.tag.as_str(), trace::DOM,
+                    |renderer, out| {
+                        renderer.render_dom(out, element, parent_namespace, depth)
+                    },
+                ),
+            },
+        }
+    }
+
+    fn render_component(
         &mut self,
-        child: &JsValue,
-        context: &JsObject,
-        parent_namespace: &str
-    ) -> String {
-        "".to_owned()
+        out: &mut CountingSink,
+        component: &dyn Component,
+        element: &Element,
+        parent_namespace: &'static str,
+        depth: u32,
+    ) {
+        let render = |renderer: &mut Self, out: &mut CountingSink| {
+            let rendered = component.render(&element.props, &element.children);
+            renderer.render(out, &rendered, parent_namespace, depth);
+        };
+        match component.cache_key(&element.props, &element.children) {
+            Some(key) => {
+                let key = element.tag.clone() + "\u{0}" + key.as_str();
+                let ttl = component.cache_ttl();
+                self.render_cached(out, key.as_str(), ttl, parent_namespace, depth, render);
+            },
+            None => render(self, out),
+        }
     }
 
-    pub fn render_DOM(
+    // Renders a component with `render`, recording it in the profile, the
+    // trace and the stats as `name`.
+    fn within_component<F>(
         &mut self,
-        element: &JsValue,
-        context: &JsObject,
-        parent_namespace: &'static str
-    ) -> String {
-        let tag = JsObject
-            ::from_raw(element.to_raw()).get(self.call.scope, "type")
-            .unwrap()
-            .deref()
-            .to_string(self.call.scope)
-            .unwrap()
-            .deref()
-            .value()
-            .to_lowercase();
+        out: &mut CountingSink,
+        name: &str,
+        elapsed: Duration,
+        render: F,
+    )
+        where F: FnOnce(&mut Self, &mut CountingSink)
+    {
+        let written = out.written();
+        if let Some(ref mut profiler) = self.profiler {
+            profiler.enter(name, written);
+        }
+        self.component_stack.push(name.to_string());
+        self.component_depth += 1;
+        self.traced(out, name, trace::COMPONENT, render);
+        self.component_depth -= 1;
+        self.component_stack.pop();
+        if let Some(ref mut profiler) = self.profiler {
+            profiler.exit(out.written(), elapsed);
+        }
+        if let Some(ref mut stats) = self.stats {
+            if self.component_depth == 0 {
+                stats.components.push((name.to_string(), out.written() - written));
+            }
+        }
+    }
+
+    fn traced<F>(&mut self, out: &mut CountingSink, name: &str, category: &'static str, render: F)
+        where F: FnOnce(&mut Self, &mut CountingSink)
+    {
+        if let Some(ref mut tracer) = self.tracer {
+            tracer.begin(name, category);
+        }
+        render(self, out);
+        if let Some(ref mut tracer) = self.tracer {
+            tracer.end(name, category);
+        }
+    }
+
+    fn mark_output(&mut self, is_text: bool) {
+        if self.leading_text.is_none() {
+            self.leading_text = Some(is_text);
+        }
+    }
+
+    /**
+     * Renders a subtree with `render`, or writes its cached markup. The
+     * markup is rendered as if it started the document, so it can be reused
+     * whatever precedes it.
+     */
+    fn render_cached<F>(
+        &mut self,
+        out: &mut CountingSink,
+        key: &str,
+        ttl: Option<Duration>,
+        parent_namespace: &'static str,
+        depth: u32,
+        render: F,
+    )
+        where F: FnOnce(&mut Self, &mut CountingSink)
+    {
+        // Options render differently inside a select.
+        if self.current_select_value.is_some() {
+            return render(self, out);
+        }
+        let csp = self.nonce.is_some() || self.inline_hashes.is_some();
+        let sanitizer = self.sanitizer.as_ref().map(|sanitizer| sanitizer.fingerprint());
+        let key = format!(
+            "{}\u{0}{}\u{0}{}\u{0}{:?}\u{0}{}\u{0}{}\u{0}{:?}\u{0}{}\u{0}{}\u{0}{}\u{0}{}",
+            self.static_markup, depth == 0, csp, sanitizer, self.url_policy.fingerprint(),
+            self.trusted_html_only, self.style_safety, self.head.is_some(), self.assets.is_some(),
+            parent_namespace, key
+        );
+        // The depth the subtree starts at, as `limits` counts it.
+        let base = depth as usize + self.component_depth;
+        let depth = depth as usize;
+        if let Some(cached) = component_cache::get_html(key.as_str()) {
+            // The limits apply as if the markup were rendered again.
+            let deepest = base + cached.depth;
+            self.deepest = self.deepest.max(deepest);
+            if !self.limits.is_empty() && !self.within_limits(out, deepest) {
+                return;
+            }
+            // Entries cached without stats have to be rendered again.
+            match (self.stats.as_mut(), cached.stats.as_ref()) {
+                (Some(stats), Some(cached)) => stats.add(cached, depth),
+                (None, _) => {},
+                (Some(_), None) => {
+                    return self.render_uncached(out, key, ttl, depth, base, render);
+                },
+            }
+            return self.write_cached(out, &cached);
+        }
+        self.render_uncached(out, key, ttl, depth, base, render);
+    }
+
+    fn render_uncached<F>(
+        &mut self,
+        out: &mut CountingSink,
+        key: String,
+        ttl: Option<Duration>,
+        depth: usize,
+        base: usize,
+        render: F,
+    )
+        where F: FnOnce(&mut Self, &mut CountingSink)
+    {
+        let deepest = ::std::mem::replace(&mut self.deepest, base);
+        // Injections are kept with the markup, whether reported or not.
+        let outer_injections = self.html_injections.replace(Vec::new());
+        let stack_base = self.component_stack.len();
+        let previous_was_text_node = self.previous_was_text_node;
+        let leading_text = self.leading_text.take();
+        let outer_stats = self.stats.take();
+        if outer_stats.is_some() {
+            self.stats = Some(RenderStats::new());
+        }
+        self.previous_was_text_node = false;
+        let uncacheable = self.uncacheable;
+        let mut html = String::new();
+        let bytes_offset = self.bytes_offset;
+        self.bytes_offset += out.written();
+        render(self, &mut CountingSink::new(&mut html));
+        self.bytes_offset = bytes_offset;
+        let cached_depth = self.deepest - base;
+        self.deepest = self.deepest.max(deepest);
+        let mut injections = ::std::mem::replace(&mut self.html_injections, outer_injections)
+            .unwrap_or_default();
+        // With the components outside the subtree left out, see `write_cached`.
+        for injection in &mut injections {
+            let len = injection.component_stack.len() - stack_base;
+            injection.component_stack.truncate(len);
+        }
+        // Cached with a depth relative to the subtree.
+        let stats = self.stats.take().map(|mut stats| {
+            stats.max_depth = stats.max_depth.saturating_sub(depth);
+            stats
+        });
+        self.stats = outer_stats;
+        if let (Some(outer), Some(stats)) = (self.stats.as_mut(), stats.as_ref()) {
+            outer.add(stats, depth);
+        }
+        let cached = Arc::new(CachedHtml {
+            html,
+            leading_text: self.leading_text,
+            trailing_text: self.previous_was_text_node,
+            depth: cached_depth,
+            injections,
+            stats,
+        });
+        // A select leaks its value to the options after it, which a cache
+        // hit wouldn't reproduce. Aborted renders are incomplete.
+        if self.current_select_value.is_none()
+            && self.error.is_none()
+            && self.uncacheable == uncacheable
+        {
+            component_cache::insert_html(key, cached.clone(), ttl);
+        }
+        self.previous_was_text_node = previous_was_text_node;
+        self.leading_text = leading_text;
+        self.write_cached(out, &cached);
+    }
+
+    fn write_cached(&mut self, out: &mut CountingSink, cached: &CachedHtml) {
+        if let Some(ref mut injections) = self.html_injections {
+            let stack = self.component_stack.iter().rev();
+            injections.extend(cached.injections.iter().map(|injection| {
+                let component_stack = injection.component_stack.iter().chain(stack.clone());
+                HtmlInjection {
+                    component_stack: component_stack.cloned().collect(),
+                    ..injection.clone()
+                }
+            }));
+        }
+        let starts_with_text = match cached.leading_text {
+            Some(starts_with_text) => starts_with_text,
+            None => return,
+        };
+        if starts_with_text && self.previous_was_text_node && !self.static_markup {
+            out.write_str("<!-- -->");
+        }
+        self.mark_output(starts_with_text);
+        out.write_str(cached.html.as_str());
+        self.previous_was_text_node = cached.trailing_text;
+    }
+
+    fn create_open_tag_markup(
+        &mut self,
+        out: &mut CountingSink,
+        tag_verbatim: &str,
+        tag_lowercase: &str,
+        props: &Props,
+        is_root_element: bool,
+    ) {
+        out.write_char('<');
+        out.write_str(tag_verbatim);
+        let is_custom = is_custom_component(tag_lowercase, props);
+        for (prop_key, prop_value) in props {
+            if prop_value.is_null() {
+                continue;
+            }
+            if prop_key != STYLE && !self.check_attribute_name(tag_lowercase, prop_key) {
+                continue;
+            }
+            let prop_value = match self.check_url(tag_lowercase, prop_key, prop_value) {
+                Some(prop_value) => prop_value,
+                None => continue,
+            };
+            let written = out.written();
+            if prop_key == STYLE {
+                if self.style_safety.is_some() {
+                    create_markup_for_styles(out, &prop_value, &mut |name, value| {
+                        self.check_style(tag_lowercase, name, value)
+                    });
+                } else {
+                    create_markup_for_styles(out, &prop_value, &mut |_, _| true);
+                }
+                if let Some(ref mut stats) = self.stats {
+                    stats.style_bytes += out.written() - written;
+                }
+            } else if is_custom {
+                if !RESERVED_PROPS.contains(prop_key.as_str()) {
+                    create_markup_for_custom_attribute(
+                        out, prop_key.as_str(), &prop_value
+                    );
+                }
+            } else {
+                create_markup_for_property(out, prop_key.as_str(), &prop_value);
+            }
+            match self.stats {
+                Some(ref mut stats) if out.written() > written => {
+                    stats.attribute(prop_key.as_str());
+                },
+                _ => {},
+            }
+        }
+
+        // For static pages, no need to put React ID and checksum. Saves lots of
+        // bytes.
+        if self.static_markup {
+            return;
+        }
+        if is_root_element {
+            create_markup_for_root(out);
+            if let Some(ref mut stats) = self.stats {
+                stats.attribute(ROOT_ATTRIBUTE);
+            }
+        }
+    }
+
+    /**
+     * The value to write for a prop, `None` to leave it out. The values of
+     * URL attributes go through the URL policy, the rest are kept as is.
+     */
+    fn check_url<'v>(
+        &mut self,
+        tag: &str,
+        name: &str,
+        value: &'v PropValue,
+    ) -> Option<Cow<'v, PropValue>> {
+        if value.is_null() || !is_url_property(name) {
+            return Some(Cow::Borrowed(value));
+        }
+        // What will be written: arrays are joined, numbers formatted...
+        let written = value.to_string();
+        let policy = self.url_policy.clone();
+        let url = match url_policy::urls(name, written.as_str()).into_iter()
+            .find(|url| !policy.is_safe(url))
+        {
+            Some(url) => url.to_string(),
+            None => return Some(Cow::Borrowed(value)),
+        };
+        let unsafe_url = UnsafeUrl {
+            tag: tag.to_string(),
+            attribute: name.to_string(),
+            url,
+            component_stack: self.stack(),
+        };
+        if let Some(ref report) = policy.report {
+            report(&unsafe_url);
+        }
+        self.uncacheable += 1;
+        let value = match policy.action {
+            UnsafeUrlAction::Replace(ref url) => Some(Cow::Owned(PropValue::String(url.clone()))),
+            UnsafeUrlAction::Abort if self.fallible => {
+                if self.error.is_none() {
+                    self.error = Some(RenderError::UnsafeUrl(unsafe_url.clone()));
+                }
+                None
+            },
+            UnsafeUrlAction::Drop | UnsafeUrlAction::Abort => None,
+        };
+        self.unsafe_urls.push(unsafe_url);
+        value
+    }
+
+    // Renders head elements for the `HeadCollector`. Nothing is written to
+    // the output, which a cache hit wouldn't reproduce.
+    fn collect_head(&mut self, children: &[Node], depth: u32) {
+        self.uncacheable += 1;
+        let previous_was_text_node = self.previous_was_text_node;
+        let leading_text = self.leading_text;
+        // Only counts the markup, for the profile and the stats.
+        let mut collected = String::new();
+        let mut out = CountingSink::new(&mut collected);
+        for child in children {
+            self.collect_head_node(&mut out, child, depth);
+        }
+        self.previous_was_text_node = previous_was_text_node;
+        self.leading_text = leading_text;
+    }
+
+    // Collects a head element, or each one in a fragment, a nested `<Head>`
+    // or a component's output, so they are de-duplicated one by one.
+    fn collect_head_node(&mut self, out: &mut CountingSink, node: &Node, depth: u32) {
+        match *node {
+            Node::Fragment(ref children) => {
+                for child in children {
+                    self.collect_head_node(out, child, depth);
+                }
+            },
+            Node::Cached(ref cached) => self.collect_head_node(out, &cached.node, depth),
+            Node::Component(ref component) => {
+                if let Some(ref mut tracer) = self.tracer {
+                    tracer.complete(
+                        component.name.as_str(), trace::CONVERT,
+                        component.started, component.elapsed,
+                    );
+                }
+                self.within_component(
+                    out, component.name.as_str(), component.elapsed,
+                    |renderer, out| renderer.collect_head_node(out, &component.node, depth),
+                );
+            },
+            Node::Element(ref element) if element.tag == head::HEAD => {
+                for child in &element.children {
+                    self.collect_head_node(out, child, depth);
+                }
+            },
+            Node::Element(ref element) => match component::get(element.tag.as_str()) {
+                Some(component) => {
+                    self.within_component(
+                        out, element.tag.as_str(), Duration::new(0, 0),
+                        |renderer, out| {
+                            let rendered = component.render(&element.props, &element.children);
+                            renderer.collect_head_node(out, &rendered, depth);
+                        },
+                    );
+                },
+                None => self.collect_head_markup(out, node, head_key(element), depth),
+            },
+            _ => self.collect_head_markup(out, node, None, depth),
+        }
+    }
+
+    fn collect_head_markup(
+        &mut self,
+        out: &mut CountingSink,
+        node: &Node,
+        key: Option<String>,
+        depth: u32,
+    ) {
+        let mut markup = String::new();
+        self.previous_was_text_node = false;
+        self.render(&mut CountingSink::new(&mut markup), node, HTML_NAMESPACE, depth);
+        out.write_str(markup.as_str());
+        if let Some(ref mut head) = self.head {
+            head.add(key, markup);
+        }
+    }
+
+    // The document around the tree, see `document`.
+    fn render_document(&mut self, out: &mut CountingSink, document: &Document, root: &Node) {
+        if let Some(ref doctype) = document.doctype {
+            out.write_str("<!DOCTYPE ");
+            out.write_str(doctype.as_str());
+            out.write_char('>');
+        }
+        self.create_shell_tag_markup(out, "html", &document.html_attributes);
+        out.write_str("<head>");
+        self.collect_head(&document.head, 1);
+        self.end_head(out);
+        out.write_str("</head>");
+        self.create_shell_tag_markup(out, "body", &document.body_attributes);
+        self.render(out, root, HTML_NAMESPACE, 0);
+        self.previous_was_text_node = false;
+        for script in &document.scripts {
+            self.render(out, script, HTML_NAMESPACE, 1);
+        }
+        self.render_asset_scripts(out, 1);
+        out.write_str("</body></html>");
+    }
+
+    // Puts the head markup collected at the end of the `<head>` being
+    // written, after the asset links.
+    fn end_head(&mut self, out: &CountingSink) {
+        self.collect_asset_links();
+        let position = self.bytes_offset + out.written();
+        if let Some(ref mut head) = self.head {
+            head.position = Some(position);
+        }
+    }
+
+    fn collect_asset_links(&mut self) {
+        if let Some(assets) = self.assets {
+            self.collect_head(&assets.head(), 1);
+        }
+    }
+
+    // The asset scripts, if they weren't written yet.
+    fn render_asset_scripts(&mut self, out: &mut CountingSink, depth: u32) {
+        let assets = match self.assets {
+            Some(assets) if self.scripts_pending => assets,
+            _ => return,
+        };
+        self.scripts_pending = false;
+        self.uncacheable += 1;
+        for script in &assets.scripts() {
+            self.render(out, script, HTML_NAMESPACE, depth);
+        }
+    }
+
+    // The open tag of the document's `<html>` or `<body>`, which is never a
+    // root and takes plain attributes.
+    fn create_shell_tag_markup(&mut self, out: &mut CountingSink, tag: &str, attributes: &Props) {
+        out.write_char('<');
+        out.write_str(tag);
+        for (name, value) in attributes {
+            if !value.is_null() && self.check_attribute_name(tag, name) {
+                create_markup_for_property(out, name.as_str(), value);
+            }
+        }
+        out.write_char('>');
+    }
+
+    // Whether an element can be written, see `name_safety`.
+    fn check_tag(&mut self, tag: &str) -> bool {
+        if validate_dangerous_tag(tag, self.warns_of_invalid_names()) {
+            return true;
+        }
+        self.invalid_name(NameKind::Tag, tag, tag);
+        false
+    }
+
+    fn check_attribute_name(&mut self, tag: &str, name: &str) -> bool {
+        if is_attribute_name_safe(name, self.warns_of_invalid_names()) {
+            return true;
+        }
+        self.invalid_name(NameKind::Attribute, tag, name);
+        false
+    }
+
+    fn warns_of_invalid_names(&self) -> bool {
+        self.invalid_name_action == InvalidNameAction::Warn || !self.fallible
+    }
+
+    fn invalid_name(&mut self, kind: NameKind, tag: &str, name: &str) {
+        let invalid_name = InvalidName {
+            kind,
+            name: name.to_string(),
+            tag: tag.to_string(),
+            component_stack: self.stack(),
+        };
+        self.uncacheable += 1;
+        if !self.warns_of_invalid_names() && self.error.is_none() {
+            self.error = Some(RenderError::InvalidName(invalid_name.clone()));
+        }
+        self.invalid_names.push(invalid_name);
+    }
+
+    // Whether to write the `name: value` declaration, with the style safety
+    // mode on.
+    fn check_style(&mut self, tag: &str, name: &str, value: &str) -> bool {
+        let threat = match style_safety::check(name, value) {
+            Some(threat) => threat,
+            None => return true,
+        };
+        let unsafe_style = UnsafeStyle {
+            tag: tag.to_string(),
+            property: name.to_string(),
+            value: value.to_string(),
+            threat,
+            component_stack: self.stack(),
+        };
+        self.uncacheable += 1;
+        if self.style_safety == Some(UnsafeStyleAction::Abort) && self.fallible && self.error.is_none() {
+            self.error = Some(RenderError::UnsafeStyle(unsafe_style.clone()));
+        }
+        self.unsafe_styles.push(unsafe_style);
+        false
+    }
+
+    // Raw HTML that isn't trusted, with only trusted HTML allowed.
+    fn refuse_html(&mut self, tag: Option<&str>) {
+        if self.fallible && self.error.is_none() {
+            self.error = Some(RenderError::UntrustedHtml(UntrustedHtml {
+                tag: tag.map(str::to_string),
+                component_stack: self.stack(),
+            }));
+        }
+    }
+
+    fn inject_html(&mut self, tag: Option<&str>, html: &str, trusted: bool) {
+        if self.html_injections.is_none() {
+            return;
+        }
+        let injection = HtmlInjection {
+            tag: tag.map(str::to_string),
+            trusted,
+            bytes: html.len(),
+            component_stack: self.stack(),
+        };
+        if let Some(ref mut injections) = self.html_injections {
+            injections.push(injection);
+        }
+    }
+
+    fn render_raw(&mut self, out: &mut CountingSink, raw: &str) {
+        if !raw.is_empty() {
+            self.mark_output(false);
+        }
+        if let Some(ref mut stats) = self.stats {
+            stats.inner_html_bytes += raw.len();
+        }
+        out.write_str(raw);
+        self.previous_was_text_node = false;
+    }
+
+    fn render_text(&mut self, out: &mut CountingSink, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.mark_output(true);
+        if let Some(ref mut stats) = self.stats {
+            stats.text_nodes += 1;
+        }
+        if !self.static_markup {
+            if self.previous_was_text_node {
+                out.write_str("<!-- -->");
+            }
+            self.previous_was_text_node = true;
+        }
+        escape_html_into(out, text);
+    }
+
+    fn render_dom(
+        &mut self,
+        out: &mut CountingSink,
+        element: &Element,
+        parent_namespace: &'static str,
+        depth: u32,
+    ) {
+        let tag = element.tag.to_lowercase();
+        if !self.check_tag(tag.as_str()) {
+            return;
+        }
+        self.mark_output(false);
+        if let Some(ref mut stats) = self.stats {
+            stats.elements += 1;
+            stats.max_depth = stats.max_depth.max(depth as usize + 1);
+        }
         let namespace = match parent_namespace {
             HTML_NAMESPACE => get_intrinsic_namespace(tag.as_str()),
             _ => parent_namespace
         };
-        validate_dangerous_tag(tag.as_str());
-        let props = get_obj(self.call.scope, element.to_raw(), "props");
+        let mut props = Cow::Borrowed(&element.props);
+        let mut children = Cow::Borrowed(element.children.as_slice());
         match tag.as_str() {
-            "input" => {},
-            "textarea" => {},
-            "select" => {},
-            "option" => {},
-            _ => {},
+            "input" => {
+                props = Cow::Owned(input_props(&element.props));
+            },
+            "textarea" => {
+                let value = textarea_value(&element.props, &element.children);
+                props.to_mut().remove("value");
+                children = Cow::Owned(vec![Node::Text(value)]);
+            },
+            "select" => {
+                self.current_select_value = get_value_prop(
+                    &element.props, &VALUE_PROP_NAMES
+                ).cloned();
+                props.to_mut().remove("value");
+            },
+            "option" => {
+                if let Some(ref select_value) = self.current_select_value {
+                    let content = flatten_option_children(&element.children);
+                    let selected = match element.props.get("value") {
+                        Some(value) if !value.is_null() => {
+                            is_option_selected(select_value, value.to_string().as_str())
+                        },
+                        _ => is_option_selected(select_value, content.as_str()),
+                    };
+                    let mut option_props = Props::new();
+                    option_props.insert("selected", PropValue::Bool(selected));
+                    for (name, value) in &element.props {
+                        option_props.insert(name.as_str(), value.clone());
+                    }
+                    option_props.insert("selected", PropValue::Bool(selected));
+                    props = Cow::Owned(option_props);
+                    children = Cow::Owned(vec![Node::Text(content)]);
+                }
+            },
+            _ => {}
+        }
+        let is_script_or_style = tag == "script" || tag == "style";
+        if let Some(ref nonce) = self.nonce {
+            if is_script_or_style && props.get("nonce").is_none() {
+                props.to_mut().insert("nonce", PropValue::String(nonce.clone()));
+                self.uncacheable += 1;
+            }
+        }
+        self.create_open_tag_markup(
+            out,
+            element.tag.as_str(),
+            tag.as_str(),
+            &props,
+            depth == 0,
+        );
+        self.previous_was_text_node = false;
+        if OMITTED_CLOSE_TAGS.contains(tag.as_str()) {
+            out.write_str("/>");
+            return;
+        }
+        out.write_char('>');
+        let is_inline = match props.get("src") {
+            Some(src) => src.is_null(),
+            None => true,
         };
-        "".to_owned()
+        if is_script_or_style && is_inline && self.inline_hashes.is_some() {
+            let mut body = String::new();
+            let bytes_offset = self.bytes_offset;
+            self.bytes_offset += out.written();
+            self.render_children(
+                &mut CountingSink::new(&mut body), element, &tag, namespace, &children, depth,
+            );
+            self.bytes_offset = bytes_offset;
+            if !body.is_empty() {
+                if let Some(ref mut hashes) = self.inline_hashes {
+                    hashes.add(tag.as_str(), body.as_str());
+                }
+                self.uncacheable += 1;
+            }
+            out.write_str(body.as_str());
+        } else {
+            let collects_head = match self.head {
+                Some(ref head) => tag == "head" && head.position.is_none(),
+                None => false,
+            };
+            if collects_head {
+                self.collect_head(&children, depth + 1);
+                self.end_head(out);
+            } else {
+                self.render_children(out, element, &tag, namespace, &children, depth);
+            }
+            if tag == "body" {
+                self.render_asset_scripts(out, depth + 1);
+            }
+        }
+        out.write_str("</");
+        out.write_str(element.tag.as_str());
+        out.write_char('>');
+        self.previous_was_text_node = false;
+    }
+
+    // What goes between the tags of `element`, its inner HTML or children.
+    fn render_children(
+        &mut self,
+        out: &mut CountingSink,
+        element: &Element,
+        tag: &str,
+        namespace: &'static str,
+        children: &[Node],
+        depth: u32,
+    ) {
+        let inner_markup = get_non_children_inner_markup(
+            children, self.sanitizer.as_deref(), self.trusted_html_only,
+        );
+        if let Some(content) = inner_markup {
+            let starts_with_newline = match content {
+                InnerMarkup::Html { ref html, .. } => html.starts_with('\n'),
+                InnerMarkup::Text(content) => content.starts_with('\n'),
+                InnerMarkup::Refused => false,
+            };
+            if NEWLINE_EATING_TAGS.contains(tag) && starts_with_newline {
+                out.write_char('\n');
+            }
+            match content {
+                InnerMarkup::Html { ref html, trusted } => {
+                    self.inject_html(Some(tag), html, trusted);
+                    out.write_str(html);
+                },
+                InnerMarkup::Text(content) => escape_html_into(out, content),
+                InnerMarkup::Refused => self.refuse_html(Some(tag)),
+            }
+            if let Some(ref mut stats) = self.stats {
+                match content {
+                    InnerMarkup::Html { ref html, .. } => stats.inner_html_bytes += html.len(),
+                    InnerMarkup::Text("") | InnerMarkup::Refused => {},
+                    InnerMarkup::Text(_) => stats.text_nodes += 1,
+                }
+            }
+        } else {
+            let child_namespace = get_child_namespace(
+                Some(namespace), element.tag.as_str()
+            );
+            for child in children.iter() {
+                self.render(out, child, child_namespace, depth + 1);
+            }
+        }
+    }
+}
+
+/**
+ * Render a tree to HTML that React can hydrate on the client.
+ */
+pub fn render_to_string(node: &Node) -> String {
+    DomServerRenderer::new(node, false)
+        .read(ReadSize::Infinity)
+        .unwrap_or_default()
+}
+
+/**
+ * Render a tree to static HTML, without the extra DOM attributes and text
+ * separators React uses internally.
+ */
+pub fn render_to_static_markup(node: &Node) -> String {
+    DomServerRenderer::new(node, true)
+        .read(ReadSize::Infinity)
+        .unwrap_or_default()
+}
+
+/**
+ * Render a tree into `out`, like `render_to_string`, or like
+ * `render_to_static_markup` with `static_markup`.
+ */
+pub fn render_into(node: &Node, static_markup: bool, out: &mut dyn Sink) {
+    DomServerRenderer::new(node, static_markup).read_into(out);
+}
+
+/**
+ * Render a tree to a string sized after the last render of `route`, so pages
+ * of the same kind don't regrow their buffer on every request.
+ */
+pub fn render_route(route: &str, node: &Node, static_markup: bool) -> String {
+    let hint = capacity_hint(route);
+    let mut html = String::with_capacity(hint + hint / 8);
+    render_into(node, static_markup, &mut html);
+    record_capacity_hint(route, html.len());
+    html
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+    pub static_markup: bool,
+    // Size the output after previous renders of this route, see `render_route`.
+    pub route: Option<String>,
+    // Profile the components, see `profile`.
+    pub profile: bool,
+    // Trace the components and elements, see `trace`.
+    pub trace: bool,
+    // Count elements, attributes, bytes..., see `stats`.
+    pub stats: bool,
+    // Abort the render past these, see `limits`.
+    pub limits: Limits,
+    // Instead of the global policy, see `url_policy`.
+    pub url_policy: Option<Arc<UrlPolicy>>,
+    // For a Content Security Policy, see `csp`.
+    pub nonce: Option<String>,
+    pub inline_hashes: bool,
+    // Instead of the global sanitizer, see `sanitize`.
+    pub sanitizer: Option<Arc<Sanitizer>>,
+    // Fail on raw HTML that isn't trusted, see `trusted_html`.
+    pub trusted_html_only: bool,
+    // Report the raw HTML written.
+    pub html_injections: bool,
+    // Instead of the global setting, see `style_safety`.
+    pub style_safety: Option<UnsafeStyleAction>,
+    // Instead of the global setting, see `name_safety`.
+    pub invalid_names: Option<InvalidNameAction>,
+    // Collect head elements into the `<head>`, see `head`.
+    pub collect_head: bool,
+    // Render a complete document, see `document`. Collects the head too.
+    pub document: Option<Document>,
+    // Write the tags for these, see `assets`. Collects the head too.
+    pub assets: Option<Assets>,
+}
+
+/**
+ * The HTML, and whatever else `RenderOptions` asked for.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderResult {
+    pub html: String,
+    pub profile: Option<Vec<Profile>>,
+    pub trace: Option<Trace>,
+    pub stats: Option<RenderStats>,
+    // Whatever the policy did with them.
+    pub unsafe_urls: Vec<UnsafeUrl>,
+    pub inline_hashes: Option<InlineHashes>,
+    pub html_injections: Option<Vec<HtmlInjection>>,
+    // Left out, or the one that aborted the render.
+    pub unsafe_styles: Vec<UnsafeStyle>,
+    // Left out, or the one that aborted the render.
+    pub invalid_names: Vec<InvalidName>,
+    // The head markup collected, if there was no `<head>` to put it in.
+    pub head: Option<String>,
+    // The asset scripts, if there was no `<body>` to put them in.
+    pub scripts: Option<String>,
+}
+
+/**
+ * Render a tree as `options` say. Fails if the render was aborted, for
+ * exceeding one of the limits, an unsafe URL or style, untrusted HTML or an
+ * invalid name, without touching the route's capacity hint.
+ */
+pub fn render_with_options(
+    node: &Node,
+    options: &RenderOptions,
+) -> Result<RenderResult, RenderError> {
+    let hint = options.route.as_ref().map_or(0, |route| capacity_hint(route));
+    let mut html = String::with_capacity(hint + hint / 8);
+    let mut renderer = DomServerRenderer::new(node, options.static_markup).fallible();
+    if options.profile {
+        renderer = renderer.with_profile();
+    }
+    if options.trace {
+        renderer = renderer.with_trace();
+    }
+    if options.stats {
+        renderer = renderer.with_stats();
+    }
+    renderer = renderer.with_limits(options.limits.clone());
+    if let Some(ref policy) = options.url_policy {
+        renderer = renderer.with_url_policy(policy.clone());
+    }
+    if let Some(ref nonce) = options.nonce {
+        renderer = renderer.with_nonce(nonce.clone());
+    }
+    if let Some(ref sanitizer) = options.sanitizer {
+        renderer = renderer.with_sanitizer(sanitizer.clone());
+    }
+    if options.inline_hashes {
+        renderer = renderer.with_inline_hashes();
+    }
+    if options.trusted_html_only {
+        renderer = renderer.with_trusted_html_only();
+    }
+    if options.html_injections {
+        renderer = renderer.with_html_injections();
+    }
+    if let Some(action) = options.style_safety {
+        renderer = renderer.with_style_safety(action);
+    }
+    if let Some(action) = options.invalid_names {
+        renderer = renderer.with_invalid_name_action(action);
+    }
+    if let Some(ref document) = options.document {
+        renderer = renderer.with_document(document);
+    } else if options.collect_head {
+        renderer = renderer.with_head();
+    }
+    if let Some(ref assets) = options.assets {
+        renderer = renderer.with_assets(assets);
+    }
+    renderer.read_into(&mut html);
+    if let Some(error) = renderer.take_error() {
+        return Err(error);
+    }
+    let head = renderer.take_head().and_then(|head| match head.position {
+        Some(position) => {
+            html.insert_str(position, head.markup().as_str());
+            None
+        },
+        None => Some(head.markup()),
+    });
+    if let Some(ref route) = options.route {
+        record_capacity_hint(route, html.len());
     }
+    Ok(RenderResult {
+        html,
+        profile: renderer.take_profile(),
+        trace: renderer.take_trace(),
+        stats: renderer.take_stats(),
+        unsafe_urls: renderer.take_unsafe_urls(),
+        inline_hashes: renderer.take_inline_hashes(),
+        html_injections: renderer.take_html_injections(),
+        unsafe_styles: renderer.take_unsafe_styles(),
+        invalid_names: renderer.take_invalid_names(),
+        head,
+        scripts: renderer.take_scripts(),
+    })
 }
@@ -0,0 +1,132 @@
+/**
+ * Per-component render profiles: where a render spent its time and which
+ * components produced its output.
+ *
+ * Calls of the same component under the same parent are merged, like a
+ * profiler's call tree. A JS component's time covers calling it, converting
+ * its output and rendering that to HTML; a native component's only the last
+ * two, since it renders during the HTML walk.
+ */
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use util::duration_str;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    // Total time minus the total time of the children.
+    pub self_time: Duration,
+    pub total_time: Duration,
+    pub calls: usize,
+    // Bytes of HTML written, children included.
+    pub bytes: usize,
+    pub children: Vec<Profile>,
+}
+
+struct Frame {
+    profile: Profile,
+    started: Instant,
+    bytes: usize,
+}
+
+#[derive(Default)]
+pub struct Profiler {
+    stack: Vec<Frame>,
+    roots: Vec<Profile>,
+}
+
+impl Profile {
+    fn new(name: &str) -> Profile {
+        Profile {
+            name: name.to_string(),
+            self_time: Duration::new(0, 0),
+            total_time: Duration::new(0, 0),
+            calls: 1,
+            bytes: 0,
+            children: Vec::new(),
+        }
+    }
+
+    fn merge_into(self, profiles: &mut Vec<Profile>) {
+        match profiles.iter_mut().position(|profile| profile.name == self.name) {
+            Some(i) => {
+                let profile = &mut profiles[i];
+                profile.total_time += self.total_time;
+                profile.calls += self.calls;
+                profile.bytes += self.bytes;
+                for child in self.children {
+                    child.merge_into(&mut profile.children);
+                }
+            },
+            None => profiles.push(self),
+        }
+    }
+
+    fn compute_self_time(&mut self) {
+        let mut children = Duration::new(0, 0);
+        for child in &mut self.children {
+            child.compute_self_time();
+            children += child.total_time;
+        }
+        self.self_time = self.total_time.checked_sub(children)
+            .unwrap_or_else(|| Duration::new(0, 0));
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        writeln!(
+            f, "{:indent$}{} x{}: {} total, {} self, {} bytes",
+            "", self.name, self.calls,
+            duration_str(self.total_time), duration_str(self.self_time), self.bytes,
+            indent = depth * 2
+        )?;
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line per component, children indented under their parent.
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    /// A component starts rendering, `bytes` have been written so far.
+    pub fn enter(&mut self, name: &str, bytes: usize) {
+        self.stack.push(Frame {
+            profile: Profile::new(name),
+            started: Instant::now(),
+            bytes,
+        });
+    }
+
+    /// The last entered component is done. `elapsed` is time it took before
+    /// the HTML walk, i.e. converting a JS component.
+    pub fn exit(&mut self, bytes: usize, elapsed: Duration) {
+        let Frame { mut profile, started, bytes: start } = self.stack
+            .pop()
+            .expect("Profiler::exit without enter");
+        profile.total_time = started.elapsed() + elapsed;
+        profile.bytes = bytes - start;
+        match self.stack.last_mut() {
+            Some(parent) => profile.merge_into(&mut parent.profile.children),
+            None => profile.merge_into(&mut self.roots),
+        }
+    }
+
+    /// The top level components, in render order.
+    pub fn finish(mut self) -> Vec<Profile> {
+        for profile in &mut self.roots {
+            profile.compute_self_time();
+        }
+        self.roots
+    }
+}
@@ -0,0 +1,115 @@
+/**
+ * An opt-in sanitizer for raw HTML, e.g. CMS content passed to
+ * `dangerouslySetInnerHTML`. The markup is parsed the way a browser would and
+ * written back with only the allowed tags, attributes and URL schemes, see
+ * `ammonia`.
+ *
+ * Trusted HTML (`Node::TrustedHtml`, e.g. marked with `trustHTML` in JS or
+ * rendered by a JS component with a static `trustedHTML = true`) is never
+ * sanitized.
+ */
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+
+use ammonia::Builder;
+
+lazy_static! {
+    static ref SANITIZER: RwLock<Option<Arc<Sanitizer>>> = RwLock::new(None);
+}
+
+/**
+ * What raw HTML may contain. `None` keeps ammonia's defaults: common text and
+ * structure tags, `lang` and `title` on any tag, `href` on links, `src` on
+ * images..., and the usual web URL schemes. Scripts, styles and event
+ * handlers are always removed, whatever the lists say: `script` and `style`
+ * tags, `style` and `on*` attributes and `javascript:`/`vbscript:` URLs are
+ * left out of them.
+ */
+#[derive(Clone, Debug, Default, Hash, PartialEq)]
+pub struct Sanitizer {
+    pub tags: Option<Vec<String>>,
+    // Attributes allowed on any tag.
+    pub attributes: Option<Vec<String>>,
+    // Attributes allowed on one tag, as `(tag, attribute)`.
+    pub tag_attributes: Option<Vec<(String, String)>>,
+    pub url_schemes: Option<Vec<String>>,
+}
+
+fn str_set(strings: &[String]) -> HashSet<&str> {
+    strings.iter().map(String::as_str).collect()
+}
+
+fn is_script_tag(tag: &str) -> bool {
+    tag.eq_ignore_ascii_case("script") || tag.eq_ignore_ascii_case("style")
+}
+
+fn is_script_attribute(name: &str) -> bool {
+    name.eq_ignore_ascii_case("style") || name.get(..2).is_some_and(|prefix| prefix.eq_ignore_ascii_case("on"))
+}
+
+fn is_script_scheme(scheme: &str) -> bool {
+    scheme.eq_ignore_ascii_case("javascript") || scheme.eq_ignore_ascii_case("vbscript")
+}
+
+impl Sanitizer {
+    pub fn clean(&self, html: &str) -> String {
+        let mut builder = Builder::default();
+        if let Some(ref tags) = self.tags {
+            let tags: HashSet<&str> = tags.iter()
+                .map(String::as_str)
+                .filter(|tag| !is_script_tag(tag))
+                .collect();
+            // ammonia refuses to both keep and drop a tag's content.
+            builder.rm_clean_content_tags(tags.iter().cloned());
+            builder.tags(tags);
+        }
+        if let Some(ref attributes) = self.attributes {
+            builder.generic_attributes(str_set(attributes).into_iter()
+                .filter(|name| !is_script_attribute(name))
+                .collect());
+        }
+        if let Some(ref tag_attributes) = self.tag_attributes {
+            let mut allowed: HashMap<&str, HashSet<&str>> = HashMap::new();
+            for (tag, attribute) in tag_attributes {
+                if !is_script_attribute(attribute) {
+                    allowed.entry(tag.as_str()).or_default().insert(attribute.as_str());
+                }
+            }
+            builder.tag_attributes(allowed);
+        }
+        if let Some(ref url_schemes) = self.url_schemes {
+            builder.url_schemes(str_set(url_schemes).into_iter()
+                .filter(|scheme| !is_script_scheme(scheme))
+                .collect());
+        }
+        // ammonia sets `rel="noopener noreferrer"` on links, unless the
+        // attribute is allowed.
+        let allows_rel = self.attributes.iter().flatten().any(|name| name == "rel")
+            || self.tag_attributes.iter().flatten().any(|(_, name)| name == "rel");
+        if allows_rel {
+            builder.link_rel(None);
+        }
+        builder.clean(html).to_string()
+    }
+
+    /// Equal for sanitizers that clean the same, to key cached markup by.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/**
+ * The sanitizer renders use unless `RenderOptions::sanitizer` says
+ * otherwise, none by default.
+ */
+pub fn sanitizer() -> Option<Arc<Sanitizer>> {
+    SANITIZER.read().unwrap().clone()
+}
+
+pub fn set_sanitizer(sanitizer: Option<Sanitizer>) {
+    *SANITIZER.write().unwrap() = sanitizer.map(Arc::new);
+}
@@ -0,0 +1,133 @@
+/**
+ * JSON that is safe inside a `<script>` element. `JSON.stringify` output can
+ * close the element early (`</script>`), open a comment (`<!--`), and before
+ * ES2019 U+2028 and U+2029 end a JavaScript string. The serializer writes
+ * `<`, `>`, `&` and both separators as `\uXXXX` escapes instead, which parse
+ * back to the same value.
+ *
+ * The `JsonScript` component renders such a script:
+ *
+ *   <JsonScript id="state" data={state} />
+ *     <script type="application/json" id="state">{"bio":"\u003c/script\u003e"}</script>
+ *   <JsonScript global="assetManifest" data={assets} />
+ *     <script>window["assetManifest"] = {"main.js":"/static/main.js"};</script>
+ */
+use std::fmt::Write;
+
+use element::{Element, Node, PropValue, Props};
+use util::number_to_string;
+
+const DATA: &str = "data";
+const GLOBAL: &str = "global";
+const TYPE: &str = "type";
+const APPLICATION_JSON: &str = "application/json";
+
+/// `value` as JSON that can go in a script, see above.
+pub fn to_script_json(value: &PropValue) -> String {
+    let mut json = String::new();
+    write_value(&mut json, value);
+    json
+}
+
+/// Makes JSON serialized elsewhere, e.g. by `JSON.stringify`, safe to put in
+/// a script. Outside of strings JSON has none of the escaped characters.
+pub fn escape_script_json(json: &str) -> String {
+    let mut escaped = String::with_capacity(json.len());
+    for c in json.chars() {
+        write_char(&mut escaped, c);
+    }
+    escaped
+}
+
+fn write_value(out: &mut String, value: &PropValue) {
+    match *value {
+        PropValue::Null => out.push_str("null"),
+        PropValue::Bool(b) => out.push_str(if b { "true" } else { "false" }),
+        // Like `JSON.stringify`.
+        PropValue::Number(n) if n.is_finite() => out.push_str(number_to_string(n).as_str()),
+        PropValue::Number(_) => out.push_str("null"),
+        PropValue::String(ref s) => write_string(out, s),
+        PropValue::Array(ref items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        },
+        PropValue::Object(ref props) => {
+            out.push('{');
+            for (i, (name, value)) in props.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, name);
+                out.push(':');
+                write_value(out, value);
+            }
+            out.push('}');
+        },
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => write_char(out, c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_char(out: &mut String, c: char) {
+    match c {
+        '<' | '>' | '&' | '\u{2028}' | '\u{2029}' => {
+            write!(out, "\\u{:04x}", c as u32).unwrap();
+        },
+        c => out.push(c),
+    }
+}
+
+/**
+ * `<JsonScript data={...} />`, the native component. Renders `data` as
+ * `type="application/json"` script, or with a `global` name, as a script
+ * that assigns it to `window[global]`. Other props go on the script.
+ */
+pub fn json_script(props: &Props, _children: &[Node]) -> Node {
+    let data = props.get(DATA).unwrap_or(&PropValue::Null);
+    let global = props.get(GLOBAL).and_then(PropValue::as_str);
+    let mut script = Element::new("script");
+    if global.is_none() && props.get(TYPE).is_none() {
+        script = script.prop(TYPE, APPLICATION_JSON);
+    }
+    for (name, value) in props {
+        if name != DATA && name != GLOBAL {
+            script = script.prop(name.as_str(), value.clone());
+        }
+    }
+    let json = to_script_json(data);
+    let body = match global {
+        Some(global) => {
+            let mut body = String::from("window[");
+            write_string(&mut body, global);
+            body.push_str("] = ");
+            body.push_str(json.as_str());
+            body.push(';');
+            body
+        },
+        None => json,
+    };
+    // Nothing to sanitize, and a sanitizer would take the script apart.
+    script.child(Node::TrustedHtml(body)).into()
+}
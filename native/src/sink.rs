@@ -0,0 +1,194 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::mem;
+use std::sync::{Arc, RwLock};
+
+/**
+ * Where rendered markup goes. The renderer writes many small pieces (tag
+ * names, escaped runs of text, attribute values) straight into a sink
+ * instead of building intermediate strings.
+ */
+pub trait Sink {
+    fn write_str(&mut self, s: &str);
+
+    fn write_char(&mut self, c: char) {
+        let mut buf = [0; 4];
+        self.write_str(c.encode_utf8(&mut buf));
+    }
+
+    /// The renderer is about to write about `additional` more bytes.
+    fn reserve(&mut self, _additional: usize) {}
+}
+
+impl Sink for String {
+    fn write_str(&mut self, s: &str) {
+        self.push_str(s);
+    }
+
+    fn write_char(&mut self, c: char) {
+        self.push(c);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        String::reserve(self, additional);
+    }
+}
+
+/**
+ * Passes markup through to another sink, counting the bytes written.
+ */
+pub struct CountingSink<'a> {
+    sink: &'a mut dyn Sink,
+    written: usize,
+}
+
+impl<'a> CountingSink<'a> {
+    pub fn new(sink: &'a mut dyn Sink) -> CountingSink<'a> {
+        CountingSink {
+            sink,
+            written: 0,
+        }
+    }
+
+    pub fn written(&self) -> usize {
+        self.written
+    }
+}
+
+impl<'a> Sink for CountingSink<'a> {
+    fn write_str(&mut self, s: &str) {
+        self.written += s.len();
+        self.sink.write_str(s);
+    }
+
+    fn write_char(&mut self, c: char) {
+        self.written += c.len_utf8();
+        self.sink.write_char(c);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.sink.reserve(additional);
+    }
+}
+
+/**
+ * Cuts markup into chunks of at least `chunk_size` bytes and passes each one
+ * to `send` as soon as the render has written it, for streaming: `send` can
+ * hand the chunk to the thread writing the response through a channel while
+ * the rest of the page renders. Only the current chunk is kept, and `flush`
+ * sends it once the render is done.
+ */
+pub struct ChunkQueue<F: FnMut(String)> {
+    send: F,
+    current: String,
+    chunk_size: usize,
+}
+
+impl<F: FnMut(String)> ChunkQueue<F> {
+    pub fn new(chunk_size: usize, send: F) -> ChunkQueue<F> {
+        ChunkQueue {
+            send,
+            current: String::with_capacity(chunk_size),
+            chunk_size,
+        }
+    }
+
+    /// Sends the current chunk even if it is short, e.g. once the render is
+    /// done.
+    pub fn flush(&mut self) {
+        if !self.current.is_empty() {
+            let chunk = mem::replace(
+                &mut self.current,
+                String::with_capacity(self.chunk_size),
+            );
+            (self.send)(chunk);
+        }
+    }
+}
+
+impl<F: FnMut(String)> Sink for ChunkQueue<F> {
+    fn write_str(&mut self, s: &str) {
+        self.current.push_str(s);
+        if self.current.len() >= self.chunk_size {
+            self.flush();
+        }
+    }
+}
+
+/**
+ * Writes markup to an `io::Write`, which should be buffered. Rendering can't
+ * stop half way through, so the first error is kept, later writes are
+ * dropped and `finish` returns it.
+ */
+pub struct WriteSink<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> WriteSink<W> {
+    pub fn new(writer: W) -> WriteSink<W> {
+        WriteSink {
+            writer,
+            error: None,
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.writer.flush().map(|_| self.writer),
+        }
+    }
+}
+
+impl<W: Write> Sink for WriteSink<W> {
+    fn write_str(&mut self, s: &str) {
+        if self.error.is_none() {
+            if let Err(err) = self.writer.write_all(s.as_bytes()) {
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+// How many routes have a capacity hint. Routes should be few, but they may
+// come from URLs by mistake, so the oldest hint makes room for a new one.
+const MAX_CAPACITY_HINTS: usize = 1024;
+
+#[derive(Default)]
+struct CapacityHints {
+    // Output size of the last render of each route.
+    sizes: HashMap<String, usize>,
+    // The routes, oldest first.
+    routes: VecDeque<String>,
+}
+
+lazy_static! {
+    static ref CAPACITY_HINTS: Arc<RwLock<CapacityHints>> = {
+        Arc::new(RwLock::new(CapacityHints::default()))
+    };
+}
+
+/**
+ * How many bytes the last render of `route` produced, 0 for a route that
+ * hasn't been rendered yet. Routes are whatever the app uses to tell pages
+ * of similar size apart, e.g. `/products/:id`, not full URLs.
+ */
+pub fn capacity_hint(route: &str) -> usize {
+    CAPACITY_HINTS.read().unwrap().sizes.get(route).cloned().unwrap_or(0)
+}
+
+pub fn record_capacity_hint(route: &str, size: usize) {
+    let mut hints = CAPACITY_HINTS.write().unwrap();
+    if let Some(hint) = hints.sizes.get_mut(route) {
+        *hint = size;
+        return;
+    }
+    if hints.routes.len() >= MAX_CAPACITY_HINTS {
+        if let Some(oldest) = hints.routes.pop_front() {
+            hints.sizes.remove(&oldest);
+        }
+    }
+    hints.routes.push_back(route.to_string());
+    hints.sizes.insert(route.to_string(), size);
+}
@@ -0,0 +1,54 @@
+/**
+ * Page weight counters collected while rendering, to track regressions.
+ */
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    pub elements: usize,
+    pub text_nodes: usize,
+    // Deepest element nesting, 1 for a single element.
+    pub max_depth: usize,
+    // Bytes of `style` attributes, name and quotes included.
+    pub style_bytes: usize,
+    // Bytes of raw HTML, from `dangerouslySetInnerHTML`.
+    pub inner_html_bytes: usize,
+    // Attributes written, by prop name.
+    pub attributes: BTreeMap<String, usize>,
+    // Bytes written by each top level component, in render order.
+    pub components: Vec<(String, usize)>,
+}
+
+impl RenderStats {
+    pub fn new() -> RenderStats {
+        RenderStats::default()
+    }
+
+    pub fn attribute(&mut self, name: &str) {
+        if let Some(count) = self.attributes.get_mut(name) {
+            *count += 1;
+            return;
+        }
+        self.attributes.insert(name.to_string(), 1);
+    }
+
+    pub fn attribute_count(&self) -> usize {
+        self.attributes.values().sum()
+    }
+
+    /// Adds the counters of a subtree rendered `depth` elements deep, whose
+    /// own `max_depth` was relative to it.
+    pub fn add(&mut self, other: &RenderStats, depth: usize) {
+        self.elements += other.elements;
+        self.text_nodes += other.text_nodes;
+        if other.max_depth > 0 {
+            self.max_depth = self.max_depth.max(depth + other.max_depth);
+        }
+        self.style_bytes += other.style_bytes;
+        self.inner_html_bytes += other.inner_html_bytes;
+        for (name, count) in &other.attributes {
+            *self.attributes.entry(name.clone()).or_insert(0) += *count;
+        }
+        self.components.extend(other.components.iter().cloned());
+    }
+}
@@ -0,0 +1,186 @@
+/**
+ * An optional safety mode for inline styles. Style values are only escaped
+ * as attribute values, so user data in one can still inject CSS: a `;` starts
+ * another declaration, `expression(...)`, `-moz-binding` and `behavior` run
+ * script in old browsers, `url(javascript:...)` in some. With the mode on,
+ * declarations like that are left out, or fail the render, and reported.
+ */
+use std::error::Error;
+use std::fmt;
+use std::sync::RwLock;
+
+use error::component_stack_string;
+use util::hyphenate_style_name;
+
+lazy_static! {
+    static ref ACTION: RwLock<Option<UnsafeStyleAction>> = RwLock::new(None);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnsafeStyleAction {
+    // Leave the declaration out, keep the rest of the style.
+    Drop,
+    // Abort the render with a `RenderError::UnsafeStyle`. Renders that can't
+    // fail drop the declaration instead.
+    Abort,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StyleThreat {
+    // `expression(...)`, script in old IE.
+    Expression,
+    // `url(javascript:...)` or `url(vbscript:...)`.
+    ScriptUrl,
+    // A `-moz-binding` or `behavior` property.
+    Binding,
+    // `;`, `{`, `}` or an unbalanced quote, which end the declaration, or a
+    // property that isn't a CSS identifier.
+    Syntax,
+}
+
+impl StyleThreat {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            StyleThreat::Expression => "expression",
+            StyleThreat::ScriptUrl => "scriptUrl",
+            StyleThreat::Binding => "binding",
+            StyleThreat::Syntax => "syntax",
+        }
+    }
+}
+
+/**
+ * An unsafe style declaration found while rendering. `property` is the
+ * style's key as given, `component_stack` innermost first.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsafeStyle {
+    pub tag: String,
+    pub property: String,
+    pub value: String,
+    pub threat: StyleThreat,
+    pub component_stack: Vec<String>,
+}
+
+impl fmt::Display for UnsafeStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "Blocked an unsafe `{}` style on <{}> ({}): {}{}",
+            self.property, self.tag, self.threat.name(), self.value,
+            component_stack_string(&self.component_stack),
+        )
+    }
+}
+
+impl Error for UnsafeStyle {}
+
+/// Why the `name: value` declaration is unsafe, if it is.
+pub fn check(name: &str, value: &str) -> Option<StyleThreat> {
+    if !is_identifier(name) || has_syntax(value) {
+        return Some(StyleThreat::Syntax);
+    }
+    let property = hyphenate_style_name(name).to_ascii_lowercase();
+    if property == "behavior" || property == "-moz-binding" {
+        return Some(StyleThreat::Binding);
+    }
+    let value = normalize(value);
+    if value.contains("expression(") {
+        return Some(StyleThreat::Expression);
+    }
+    let mut urls = value.split("url(").skip(1);
+    if urls.any(|url| {
+        let url = url.trim_start_matches(['"', '\'']);
+        url.starts_with("javascript:") || url.starts_with("vbscript:")
+    }) {
+        return Some(StyleThreat::ScriptUrl);
+    }
+    None
+}
+
+// Letters, digits, `-` and `_`, which covers custom properties and vendor
+// prefixes. Anything else in a property (`;`, `:`, quotes, whitespace...) can
+// start another declaration.
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| {
+        c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+    })
+}
+
+// `;`, `{` and `}` outside strings, or a string that isn't closed. Anything
+// escaped with a backslash is part of the value.
+fn has_syntax(value: &str) -> bool {
+    let mut quote = None;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', _) => {
+                chars.next();
+            },
+            ('"', None) | ('\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (';', None) | ('{', None) | ('}', None) => return true,
+            _ => {},
+        }
+    }
+    quote.is_some()
+}
+
+// What the CSS parser sees: lowercase, without comments and whitespace, and
+// with escapes like `\65` decoded, so `ex/**/pr\65 ssion(` is caught too.
+fn normalize(value: &str) -> String {
+    let mut normalized = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            },
+            '\\' => {
+                let mut hex = String::new();
+                while hex.len() < 6 {
+                    match chars.peek() {
+                        Some(&c) if c.is_ascii_hexdigit() => hex.push(c),
+                        _ => break,
+                    }
+                    chars.next();
+                }
+                if hex.is_empty() {
+                    if let Some(c) = chars.next() {
+                        normalized.extend(c.to_lowercase());
+                    }
+                    continue;
+                }
+                // One whitespace ends the escape.
+                if matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+                    chars.next();
+                }
+                let decoded = u32::from_str_radix(hex.as_str(), 16).ok()
+                    .and_then(::std::char::from_u32)
+                    .unwrap_or('\u{fffd}');
+                normalized.extend(decoded.to_lowercase());
+            },
+            c if c.is_whitespace() => {},
+            c => normalized.extend(c.to_lowercase()),
+        }
+    }
+    normalized
+}
+
+/**
+ * What renders do with unsafe styles unless `RenderOptions::style_safety`
+ * says otherwise, `None` (the mode is off) by default.
+ */
+pub fn action() -> Option<UnsafeStyleAction> {
+    *ACTION.read().unwrap()
+}
+
+pub fn set_action(action: Option<UnsafeStyleAction>) {
+    *ACTION.write().unwrap() = action;
+}
@@ -0,0 +1,132 @@
+/**
+ * Traces of a single render in the Chrome Trace Event format, for
+ * chrome://tracing or Perfetto.
+ *
+ * Every component and DOM element gets a begin and an end event as the HTML
+ * is written. A JS component also gets a complete event for when it was
+ * called and its output converted, which happens before the HTML walk.
+ */
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json;
+
+pub const COMPONENT: &str = "component";
+pub const CONVERT: &str = "convert";
+pub const DOM: &str = "dom";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    Begin,
+    End,
+    Complete(Duration),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEvent {
+    pub name: String,
+    pub category: &'static str,
+    pub phase: Phase,
+    // Since the first event.
+    pub timestamp: Duration,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Default)]
+pub struct Tracer {
+    events: Vec<(String, &'static str, Phase, Instant)>,
+}
+
+impl Tracer {
+    pub fn new() -> Tracer {
+        Tracer::default()
+    }
+
+    pub fn begin(&mut self, name: &str, category: &'static str) {
+        self.events.push((name.to_string(), category, Phase::Begin, Instant::now()));
+    }
+
+    pub fn end(&mut self, name: &str, category: &'static str) {
+        self.events.push((name.to_string(), category, Phase::End, Instant::now()));
+    }
+
+    pub fn complete(
+        &mut self,
+        name: &str,
+        category: &'static str,
+        started: Instant,
+        elapsed: Duration,
+    ) {
+        self.events.push((name.to_string(), category, Phase::Complete(elapsed), started));
+    }
+
+    pub fn finish(self) -> Trace {
+        let origin = match self.events.iter().map(|event| event.3).min() {
+            Some(origin) => origin,
+            None => return Trace::default(),
+        };
+        let events = self.events
+            .into_iter()
+            .map(|(name, category, phase, at)| TraceEvent {
+                name,
+                category,
+                phase,
+                timestamp: at - origin,
+            })
+            .collect();
+        Trace { events }
+    }
+}
+
+impl Trace {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("traces always serialize")
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()
+    }
+}
+
+// Trace timestamps are in microseconds.
+fn microseconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1e6 + duration.subsec_nanos() as f64 / 1e3
+}
+
+impl Serialize for Trace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("traceEvents", &self.events)?;
+        map.serialize_entry("displayTimeUnit", "ms")?;
+        map.end()
+    }
+}
+
+impl Serialize for TraceEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("name", &self.name)?;
+        map.serialize_entry("cat", self.category)?;
+        match self.phase {
+            Phase::Begin => map.serialize_entry("ph", "B")?,
+            Phase::End => map.serialize_entry("ph", "E")?,
+            Phase::Complete(elapsed) => {
+                map.serialize_entry("ph", "X")?;
+                map.serialize_entry("dur", &microseconds(elapsed))?;
+            },
+        }
+        map.serialize_entry("ts", &microseconds(self.timestamp))?;
+        map.serialize_entry("pid", &1)?;
+        map.serialize_entry("tid", &1)?;
+        map.end()
+    }
+}
@@ -0,0 +1,48 @@
+/**
+ * Auditing raw HTML. Markup marked as trusted (`Node::TrustedHtml`, from
+ * `trustHTML` in JS) can be told apart from plain strings in
+ * `dangerouslySetInnerHTML`, so a render may refuse the latter, and report
+ * which components injected raw HTML at all.
+ */
+use std::error::Error;
+use std::fmt;
+
+use error::component_stack_string;
+
+/**
+ * Raw HTML written by a render, see `RenderOptions::html_injections`.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtmlInjection {
+    // The element it is the inner HTML of, if any.
+    pub tag: Option<String>,
+    pub trusted: bool,
+    pub bytes: usize,
+    // Innermost first, the first one injected it.
+    pub component_stack: Vec<String>,
+}
+
+/**
+ * Raw HTML that wasn't marked as trusted, in a render that only allows
+ * trusted HTML.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct UntrustedHtml {
+    pub tag: Option<String>,
+    pub component_stack: Vec<String>,
+}
+
+impl fmt::Display for UntrustedHtml {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.tag {
+            Some(ref tag) => write!(f, "Refused untrusted HTML in <{}>", tag)?,
+            None => write!(f, "Refused untrusted HTML")?,
+        }
+        write!(
+            f, ": only HTML marked with trustHTML may be set with dangerouslySetInnerHTML{}",
+            component_stack_string(&self.component_stack),
+        )
+    }
+}
+
+impl Error for UntrustedHtml {}
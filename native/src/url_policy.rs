@@ -0,0 +1,211 @@
+/**
+ * What URLs may be written to URL attributes (`href`, `src`, `action`...,
+ * see `util::url_attribute_config`), so user data like a profile link can't
+ * turn into `href="javascript:..."`.
+ *
+ * `javascript:` and `vbscript:` URLs are always unsafe, and `data:` URLs
+ * unless they are raster images. Relative URLs are always safe.
+ */
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+
+use error::component_stack_string;
+
+// Images that can't run scripts, unlike `image/svg+xml`.
+const DATA_IMAGE_TYPES: [&str; 8] = [
+    "image/avif",
+    "image/bmp",
+    "image/gif",
+    "image/jpeg",
+    "image/jpg",
+    "image/png",
+    "image/webp",
+    "image/x-icon",
+];
+
+lazy_static! {
+    static ref POLICY: RwLock<Arc<UrlPolicy>> = RwLock::new(Arc::new(UrlPolicy::default()));
+}
+
+// Called with every unsafe URL, e.g. to log a warning.
+pub type Report = Arc<dyn Fn(&UnsafeUrl) + Send + Sync>;
+
+#[derive(Clone, Debug, Hash, PartialEq)]
+pub enum UnsafeUrlAction {
+    // Leave the attribute out.
+    Drop,
+    // Write this URL instead, e.g. `about:blank`.
+    Replace(String),
+    // Abort the render with a `RenderError::UnsafeUrl`. Renders that can't
+    // fail, like `render_to_string`, drop the attribute instead.
+    Abort,
+}
+
+#[derive(Clone)]
+pub struct UrlPolicy {
+    // The schemes URLs may have, lowercase. `None` allows any but the unsafe
+    // ones.
+    pub allowed_schemes: Option<Vec<String>>,
+    pub action: UnsafeUrlAction,
+    pub report: Option<Report>,
+}
+
+/**
+ * An unsafe URL found while rendering. `component_stack` lists the
+ * components being rendered at the time, innermost first.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsafeUrl {
+    pub tag: String,
+    pub attribute: String,
+    pub url: String,
+    pub component_stack: Vec<String>,
+}
+
+impl Default for UrlPolicy {
+    fn default() -> UrlPolicy {
+        UrlPolicy {
+            allowed_schemes: None,
+            action: UnsafeUrlAction::Drop,
+            report: None,
+        }
+    }
+}
+
+impl fmt::Debug for UrlPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UrlPolicy")
+            .field("allowed_schemes", &self.allowed_schemes)
+            .field("action", &self.action)
+            .field("report", &self.report.is_some())
+            .finish()
+    }
+}
+
+impl UrlPolicy {
+    pub fn is_safe(&self, url: &str) -> bool {
+        let url = normalize(url);
+        let (scheme, rest) = match split_scheme(url.as_str()) {
+            Some(parts) => parts,
+            None => return true,
+        };
+        match scheme.as_str() {
+            "javascript" | "vbscript" => false,
+            "data" if !is_image_data(rest) => false,
+            scheme => match self.allowed_schemes {
+                Some(ref allowed) => allowed.iter().any(|allowed| allowed.eq_ignore_ascii_case(scheme)),
+                None => true,
+            },
+        }
+    }
+
+    /// Equal for policies that write the same, to key cached markup by.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.allowed_schemes.hash(&mut hasher);
+        self.action.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/**
+ * The URLs in the value of the attribute `name`: each candidate of a
+ * `srcset`, each URL of a `ping`, or the whole value.
+ */
+pub fn urls<'v>(name: &str, value: &'v str) -> Vec<&'v str> {
+    match name.to_ascii_lowercase().as_str() {
+        "srcset" | "imagesrcset" => srcset_urls(value),
+        "ping" => value.split_ascii_whitespace().collect(),
+        _ => vec![value],
+    }
+}
+
+// Like the HTML parser: a candidate is a URL up to whitespace, trailing
+// commas ending it, then descriptors up to the next comma outside
+// parentheses.
+fn srcset_urls(srcset: &str) -> Vec<&str> {
+    let mut urls = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            return urls;
+        }
+        let end = rest.find(|c: char| c.is_ascii_whitespace()).unwrap_or(rest.len());
+        let url = &rest[..end];
+        rest = &rest[end..];
+        let trimmed = url.trim_end_matches(',');
+        urls.push(trimmed);
+        if trimmed.len() < url.len() {
+            continue;
+        }
+        let mut parens = 0;
+        let end = rest.char_indices().find(|&(_, c)| {
+            match c {
+                '(' => parens += 1,
+                ')' if parens > 0 => parens -= 1,
+                ',' if parens == 0 => return true,
+                _ => {},
+            }
+            false
+        });
+        rest = end.map_or("", |(i, _)| &rest[i..]);
+    }
+}
+
+// Browsers skip leading spaces and control characters, and tabs and newlines
+// anywhere, so `" java\tscript:"` is still a `javascript:` URL.
+fn normalize(url: &str) -> String {
+    url.trim_start_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|&c| c != '\t' && c != '\n' && c != '\r')
+        .collect()
+}
+
+// The lowercase scheme and what follows the colon, `None` if relative.
+fn split_scheme(url: &str) -> Option<(String, &str)> {
+    let colon = url.find(':')?;
+    let scheme = &url[..colon];
+    let mut chars = scheme.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => {},
+        _ => return None,
+    }
+    if !chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.') {
+        return None;
+    }
+    Some((scheme.to_ascii_lowercase(), &url[colon + 1..]))
+}
+
+// `image/png;base64,...`
+fn is_image_data(data: &str) -> bool {
+    let end = data.find([';', ',']).unwrap_or(data.len());
+    let media_type = data[..end].trim();
+    DATA_IMAGE_TYPES.iter().any(|image| image.eq_ignore_ascii_case(media_type))
+}
+
+/**
+ * The policy renders use unless `RenderOptions::url_policy` says otherwise.
+ */
+pub fn policy() -> Arc<UrlPolicy> {
+    POLICY.read().unwrap().clone()
+}
+
+pub fn set_policy(policy: UrlPolicy) {
+    *POLICY.write().unwrap() = Arc::new(policy);
+}
+
+impl fmt::Display for UnsafeUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "Blocked an unsafe URL in the `{}` attribute of <{}>: {}",
+            self.attribute, self.tag, self.url,
+        )?;
+        f.write_str(component_stack_string(&self.component_stack).as_str())
+    }
+}
+
+impl Error for UnsafeUrl {}
@@ -7,7 +7,7 @@ use std::collections::HashSet;
  * Support style names that may come passed in prefixed by adding permutations
  * of vendor prefixes.
  */
-pub const CSS_PREFIXES: [&'static str; 4] = ["Webkit", "ms", "Moz", "O"];
+pub const CSS_PREFIXES: [&str; 4] = ["Webkit", "ms", "Moz", "O"];
 
 lazy_static! {
     /**
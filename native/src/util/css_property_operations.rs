@@ -8,9 +8,12 @@ use neon::js::{
 };
 use neon::mem::{Handle, Managed};
 
-use util::{
+use element::PropValue;
+use util::js_value::{
+    get_raw,
     get_obj,
     get_fn,
+    to_prop_value,
 };
 use util::dangerous_style_value::dangerous_style_value;
 
@@ -65,9 +68,12 @@ pub fn set_value_for_styles(
         //     }
         // }
 
+        let raw_value = get_raw(scope, styles.to_raw(), style_name.as_str());
         let style_value = dangerous_style_value(
             style_name.as_str(),
-            styles.get(scope, style_name.as_str()).unwrap(),
+            &to_prop_value(scope, raw_value)
+                .unwrap()
+                .unwrap_or(PropValue::Null),
             is_custom_property,
         );
         if style_name == "float" {
@@ -1,13 +1,6 @@
 // DONE
-use neon::mem::Handle;
-use neon::js::{
-    JsBoolean,
-    JsNull,
-    JsNumber,
-    JsString,
-    JsValue,
-};
-
+use element::PropValue;
+use util::number_to_string;
 use util::css_property::IS_UNITLESS_NUMBER;
 
 /**
@@ -19,10 +12,9 @@ use util::css_property::IS_UNITLESS_NUMBER;
  * @param {*} value CSS property value such as `10px`.
  * @return {string} Normalized style value with dimensions applied.
  */
-
 pub fn dangerous_style_value(
     name: &str,
-    value: Handle<JsValue>,
+    value: &PropValue,
     is_custom_property: bool,
 ) -> String {
     // Note that we've removed escapeTextForBrowser() calls here since the
@@ -34,25 +26,17 @@ pub fn dangerous_style_value(
     // This is not an XSS hole but instead a potential CSS injection issue
     // which has lead to a greater discussion about how we're going to
     // trust URLs moving forward. See #2115901
-    if value.is_a::<JsNull>()
-        || value.is_a::<JsBoolean>()
-    {
-        return "".to_string();
-    }
-    let text = value.downcast::<JsString>().map(|v| v.value());
-    if text == Some("".to_string()) {
-        return "".to_string();
-    }
-
-    let number = value.downcast::<JsNumber>().map(|v| v.value() as u32);
-    if !is_custom_property
-        && number.is_some()
-        && number != Some(0)
-        && !IS_UNITLESS_NUMBER.contains(name)
-    {
-        // Presumes implicit 'px' suffix for unitless numbesr
-        return format!("{}px", number.unwrap());
+    match *value {
+        PropValue::Null | PropValue::Bool(_) => return "".to_string(),
+        PropValue::String(ref s) if s.is_empty() => return "".to_string(),
+        PropValue::Number(n) if !is_custom_property
+            && n != 0.0
+            && !IS_UNITLESS_NUMBER.contains(name) =>
+        {
+            // Presumes implicit 'px' suffix for unitless numbers
+            return format!("{}px", number_to_string(n));
+        },
+        _ => {}
     }
-    text.map(|s| s.trim().to_owned())
-        .unwrap_or_else(|| "".to_string())
+    value.to_string().trim().to_owned()
 }
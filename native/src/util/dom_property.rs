@@ -1,17 +1,6 @@
 use std::collections::{HashSet, HashMap};
 
-use neon::js::{
-    JsArray,
-    JsBoolean,
-    JsFunction,
-    JsNull,
-    JsNumber,
-    JsObject,
-    JsString,
-    JsUndefined,
-    JsValue,
-};
-use neon::mem::Handle;
+use element::PropValue;
 
 use util::html_dom_property_config as html_config;
 use util::svg_dom_property_config as svg_config;
@@ -28,7 +17,6 @@ pub const HAS_STRING_BOOLEAN_VALUE: u32 = 0x40;
 pub const ATTRIBUTE_NAME_START_CHAR: &str =
     ":A-Z_a-z\\u00C0-\\u00D6\\u00D8-\\u00F6\\u00F8-\\u02FF\\u0370-\\u037D\\u037F-\\u1FFF\\u200C-\\u200D\\u2070-\\u218F\\u2C00-\\u2FEF\\u3001-\\uD7FF\\uF900-\\uFDCF\\uFDF0-\\uFFFD";
 /* eslint-enable max-len */
-pub const ID_ATTRIBUTE_NAME: &str = "data-reactid";
 pub const ROOT_ATTRIBUTE_NAME: &str = "data-reactroot";
 
 lazy_static! {
@@ -67,6 +55,8 @@ lazy_static! {
     };
 }
 
+// Like React's, some fields are only used on the client.
+#[allow(dead_code)]
 pub struct PropertyInfo {
     pub attribute_name: String,
     pub attribute_namespace: Option<&'static str>,
@@ -112,10 +102,10 @@ fn inject_dom_property_config(
             .unwrap_or_else(|| prop_name.to_lowercase());
         let attribute_namespace = config.dom_attribute_namespaces
             .get(prop_name.as_str())
-            .map(|s| *s);
+            .copied();
         let info = PropertyInfo {
-            attribute_name: attribute_name,
-            attribute_namespace: attribute_namespace,
+            attribute_name,
+            attribute_namespace,
             property_name: prop_name.to_string(),
 
             must_use_property: check_mask(*prop_config, MUST_USE_PROPERTY),
@@ -145,7 +135,7 @@ pub fn check_mask(value: u32, bitmask: u32) -> bool {
     (value & bitmask) == bitmask
 }
 
-pub fn should_set_attribute(name: &str, value: Handle<JsValue>) -> bool {
+pub fn should_set_attribute(name: &str, value: &PropValue) -> bool {
     if is_reserved_prop(name) {
         return false;
     }
@@ -159,20 +149,11 @@ pub fn should_set_attribute(name: &str, value: Handle<JsValue>) -> bool {
             return false;
         }
     }
-    if value.is_a::<JsNull>() {
-        return true;
-    }
-    if value.is_a::<JsBoolean>() {
-        return should_attribute_accept_boolean_value(name);
-    }
-    if value.is_a::<JsUndefined>() ||
-        value.is_a::<JsNumber>() ||
-        value.is_a::<JsString>() ||
-        value.is_a::<JsObject>()
-    {
-        return true;
+    match *value {
+        PropValue::Bool(_) => should_attribute_accept_boolean_value(name),
+        // Functions and symbols never make it into a `PropValue`.
+        _ => true,
     }
-    false
 }
 
 pub fn get_property_info(name: &str) -> Option<&PropertyInfo> {
@@ -1,44 +1,127 @@
+use element::PropValue;
+use sink::Sink;
 
-use std::ops::Index;
-
-use regex::{Regex, Captures};
-use neon::scope::{RootScope};
-use neon::js::{Value};
-use neon::js::{
-    JsValue,
-    JsBoolean,
-    JsNumber,
-    JsString,
-};
-use neon::mem::Handle;
-
-lazy_static! {
-    static ref REGEX: Regex = Regex::new("[\"<>'&]").unwrap();
+/**
+ * Appends `text` to `out` with `"`, `&`, `'`, `<` and `>` escaped.
+ *
+ * All five are ASCII, so scanning bytes never splits a UTF-8 sequence and the
+ * runs between them are copied as they are. Text with nothing to escape is a
+ * single copy.
+ */
+pub fn escape_html_into<S: Sink + ?Sized>(out: &mut S, text: &str) {
+    let mut last = 0;
+    for (i, byte) in text.bytes().enumerate() {
+        let escaped = match byte {
+            b'"' => "&quot;",
+            b'&' => "&amp;",
+            b'\'' => "&#x27;",
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            _ => continue,
+        };
+        out.write_str(&text[last..i]);
+        out.write_str(escaped);
+        last = i + 1;
+    }
+    out.write_str(&text[last..]);
 }
 
-pub fn escape_html(text: &str) -> String {
-    REGEX.replace_all(text, |caps: &Captures| {
-        match caps.index(0) {
-            "\"" => "&quot;",
-            "&" => "&amp;",
-            "'" => "&#x27;",
-            "<" => "&lt;",
-            ">" => "&gt;",
-            _ => unreachable!()
-        }.to_owned()
-    }).to_string()
+pub fn escape_text_content_for_browser_into<S: Sink + ?Sized>(
+    out: &mut S,
+    value: &PropValue,
+) {
+    match *value {
+        PropValue::Bool(_) | PropValue::Number(_) => {
+            out.write_str(value.to_string().as_str());
+        },
+        PropValue::String(ref text) => escape_html_into(out, text),
+        _ => escape_html_into(out, value.to_string().as_str()),
+    }
 }
 
-pub fn escape_text_content_for_browser(
-    scope: &mut RootScope, obj: Handle<JsValue>,
-) -> String {
-    let text = obj
-        .to_string(scope)
-        .unwrap()
-        .value();
-    if obj.is_a::<JsBoolean>() || obj.is_a::<JsNumber>() {
-        text
-    } else {
-        escape_html(text.as_str())
+#[cfg(test)]
+pub mod tests {
+    use proptest::prelude::*;
+
+    use element::PropValue;
+    use super::{escape_html_into, escape_text_content_for_browser_into};
+
+    // Markup-significant characters, controls and non-BMP text.
+    pub const NASTY: &str = "[<>&\"'`= a\\x00-\\x1f\\x7f\\u{2028}\\u{10000}-\\u{10ffff}]*";
+
+    fn escape_html(text: &str) -> String {
+        let mut out = String::new();
+        escape_html_into(&mut out, text);
+        out
+    }
+
+    fn escape_text_content_for_browser(value: &PropValue) -> String {
+        let mut out = String::new();
+        escape_text_content_for_browser_into(&mut out, value);
+        out
+    }
+
+    pub fn unescape(escaped: &str) -> String {
+        escaped
+            .replace("&quot;", "\"")
+            .replace("&#x27;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&")
+    }
+
+    pub fn assert_inert(escaped: &str) {
+        for (i, c) in escaped.char_indices() {
+            match c {
+                '<' | '>' | '"' | '\'' => panic!("raw {:?} in {:?}", c, escaped),
+                '&' => {
+                    let rest = &escaped[i..];
+                    assert!(
+                        ["&quot;", "&#x27;", "&lt;", "&gt;", "&amp;"]
+                            .iter()
+                            .any(|entity| rest.starts_with(entity)),
+                        "bare & in {:?}", escaped
+                    );
+                },
+                _ => {},
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn escaped_text_is_inert(text in NASTY) {
+            assert_inert(escape_html(text.as_str()).as_str());
+        }
+
+        #[test]
+        fn escaped_text_is_inert_for_any_string(text in any::<String>()) {
+            assert_inert(escape_html(text.as_str()).as_str());
+        }
+
+        #[test]
+        fn unescaping_round_trips(text in NASTY) {
+            prop_assert_eq!(unescape(escape_html(text.as_str()).as_str()), text);
+        }
+
+        #[test]
+        fn escaping_appends(prefix in NASTY, text in NASTY) {
+            let mut out = prefix.clone();
+            escape_html_into(&mut out, text.as_str());
+            prop_assert_eq!(out, prefix + escape_html(text.as_str()).as_str());
+        }
+
+        #[test]
+        fn text_content_escapes_strings(text in any::<String>()) {
+            prop_assert_eq!(
+                escape_text_content_for_browser(&PropValue::String(text.clone())),
+                escape_html(text.as_str())
+            );
+        }
+
+        #[test]
+        fn text_content_of_numbers_is_inert(n in any::<f64>()) {
+            assert_inert(escape_text_content_for_browser(&PropValue::Number(n)).as_str());
+        }
     }
 }
@@ -1,7 +0,0 @@
-
-// react/packages/react-dom/src/shared/HTMLNodeType.js
-pub const ELEMENT_NODE: u32 = 1;
-pub const TEXT_NODE: u32 = 3;
-pub const COMMENT_NODE: u32 = 8;
-pub const DOCUMENT_NODE: u32 = 9;
-pub const DOCUMENT_FRAGMENT_NODE: u32 = 11;
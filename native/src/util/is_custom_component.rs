@@ -1,8 +1,7 @@
 
 use std::collections::HashSet;
 
-use neon::mem::Handle;
-use neon::js::{JsValue, JsString};
+use element::Props;
 
 lazy_static! {
     static ref TAG_NAMES: HashSet<&'static str> = hashset! {
@@ -21,9 +20,9 @@ lazy_static! {
     };
 }
 
-pub fn is_custom_component(tag_name: &str, props: Handle<JsValue>) -> bool {
+pub fn is_custom_component(tag_name: &str, props: &Props) -> bool {
     if !tag_name.contains("-") {
-        return props.is_a::<JsString>()
+        return props.get("is").and_then(|is| is.as_str()).is_some()
     }
-    return !TAG_NAMES.contains(tag_name)
+    !TAG_NAMES.contains(tag_name)
 }
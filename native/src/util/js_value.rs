@@ -0,0 +1,228 @@
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use neon_runtime::mem::same_handle;
+use neon_runtime::raw::Local;
+use neon::scope::{RootScope, Scope};
+use neon::vm::VmResult;
+use neon::mem::{Handle, Managed};
+use neon::js::{Value, Object, Variant};
+use neon::js::error::{JsError, Kind};
+use neon::js::{
+    JsFunction,
+    JsObject,
+    JsValue,
+    JsNull,
+    JsString,
+};
+
+use element::{PropValue, Props};
+
+
+const REACT_ELEMENT_TYPE_NUMBER: u32 = 0xeac7;
+const TO_JSON: &str = "toJSON";
+// How deeply `to_prop_value` follows nested arrays and objects.
+const MAX_PROP_VALUE_DEPTH: usize = 512;
+
+lazy_static! {
+    static ref REACT_ELEMENT_TYPE: Arc<Mutex<Option<String>>> = {
+        Arc::new(Mutex::new(None))
+    };
+}
+
+pub fn get_raw(scope: &mut RootScope, obj: Local, key: &str) -> Local {
+    JsObject::from_raw(obj)
+        .get(scope, key)
+        .unwrap()
+        .deref()
+        .to_raw()
+}
+
+pub fn get_obj(scope: &mut RootScope, obj: Local, key: &str) -> JsObject {
+    JsObject::from_raw(get_raw(scope, obj, key))
+}
+
+pub fn get_fn(scope: &mut RootScope, obj: Local, key: &str) -> JsFunction {
+    JsFunction::<JsObject>::from_raw(get_raw(scope, obj, key))
+}
+
+pub fn to_string<T: Value>(scope: &mut RootScope, obj: &T) -> String {
+    obj.to_string(scope)
+        .unwrap()
+        .deref()
+        .value()
+}
+
+/**
+ * Converts a JavaScript value into a `PropValue`, recursing into arrays and
+ * plain objects. Returns `None` for values React would never render
+ * (`undefined`, functions and symbols). Objects with a `toJSON` method are
+ * converted like `JSON.stringify` would, so dates become ISO strings. Throws
+ * a `TypeError` for circular or too deeply nested values.
+ */
+pub fn to_prop_value(scope: &mut RootScope, value: Local) -> VmResult<Option<PropValue>> {
+    to_nested_prop_value(scope, value, &mut ValueConversion::default())
+}
+
+/**
+ * Like `to_prop_value`, but `None` for values holding a function anywhere,
+ * which `to_prop_value` would leave out.
+ */
+pub fn to_data_value(scope: &mut RootScope, value: Local) -> VmResult<Option<PropValue>> {
+    let mut cx = ValueConversion::default();
+    let value = to_nested_prop_value(scope, value, &mut cx)?;
+    Ok(if cx.left_out_function { None } else { value })
+}
+
+// The state of converting one value.
+#[derive(Default)]
+struct ValueConversion {
+    // The arrays and objects the current value is nested in.
+    ancestors: Vec<Local>,
+    left_out_function: bool,
+}
+
+fn to_nested_prop_value(
+    scope: &mut RootScope,
+    value: Local,
+    cx: &mut ValueConversion,
+) -> VmResult<Option<PropValue>> {
+    let value = JsValue::from_raw(value).as_value(scope);
+    Ok(match value.variant() {
+        Variant::Null(_) => Some(PropValue::Null),
+        Variant::Boolean(b) => Some(PropValue::Bool(b.value())),
+        Variant::Integer(i) => Some(PropValue::Number(i.value() as f64)),
+        Variant::Number(n) => Some(PropValue::Number(n.value())),
+        Variant::String(s) => Some(PropValue::String(s.value())),
+        Variant::Array(array) => {
+            enter(&mut cx.ancestors, array.to_raw())?;
+            let mut items = Vec::new();
+            for item in array.to_vec(scope)? {
+                items.push(
+                    to_nested_prop_value(scope, item.to_raw(), cx)?
+                        .unwrap_or(PropValue::Null)
+                );
+            }
+            cx.ancestors.pop();
+            Some(PropValue::Array(items))
+        },
+        Variant::Object(obj) => {
+            enter(&mut cx.ancestors, obj.to_raw())?;
+            let to_json = get_raw(scope, obj.to_raw(), TO_JSON);
+            let value = if JsValue::from_raw(to_json).as_value(scope).is_a::<JsFunction>() {
+                let to_json = JsFunction::<JsObject>::from_raw(to_json);
+                let json = to_json.call(scope, obj, Vec::<Handle<JsValue>>::new())?;
+                to_nested_prop_value(scope, json.to_raw(), cx)?
+            } else {
+                let mut props = Props::new();
+                let names = obj.get_own_property_names(scope)?.to_vec(scope)?;
+                for name in names {
+                    let name = to_string(scope, name.deref());
+                    let raw = get_raw(scope, obj.to_raw(), name.as_str());
+                    if let Some(value) = to_nested_prop_value(scope, raw, cx)? {
+                        props.insert(name, value);
+                    }
+                }
+                Some(PropValue::Object(props))
+            };
+            cx.ancestors.pop();
+            value
+        },
+        Variant::Function(_) => {
+            cx.left_out_function = true;
+            None
+        },
+        _ => None
+    })
+}
+
+// Descends into `value`, unless it is one of its own ancestors.
+fn enter(ancestors: &mut Vec<Local>, value: Local) -> VmResult<()> {
+    if ancestors.iter().any(|&ancestor| unsafe { same_handle(ancestor, value) }) {
+        return JsError::throw(Kind::TypeError, "Converting circular structure to a prop value");
+    }
+    if ancestors.len() >= MAX_PROP_VALUE_DEPTH {
+        return JsError::throw(Kind::TypeError, "Prop value is nested too deeply");
+    }
+    ancestors.push(value);
+    Ok(())
+}
+
+// The Symbol used to tag the ReactElement type. If there is no native Symbol
+// nor polyfill, then a plain number is used for performance.
+// var REACT_ELEMENT_TYPE =
+//     (typeof Symbol === 'function' && Symbol.for && Symbol.for('react.element')) ||
+//     0xeac7;
+
+/**
+ * Verifies the object is a ReactElement.
+ * See https://reactjs.org/docs/react-api.html#isvalidelement
+ * @param {?object} object
+ * @return {boolean} True if `object` is a valid component.
+ * @final
+ */
+pub fn is_valid_element(scope: &mut RootScope, obj: Handle<JsObject>) -> bool {
+    // function isValidElement(object) {
+    //     return (
+    //         typeof object === 'object' &&
+    //             object !== null &&
+    //             object.$$typeof === REACT_ELEMENT_TYPE
+    //     );
+    // }
+    let mut react_element_type = REACT_ELEMENT_TYPE.lock().unwrap();
+    if react_element_type.is_none() {
+        let mut value = String::new();
+        let global = scope.global();
+        let symbol = global
+            .get(scope, "Symbol")
+            .unwrap();
+        if symbol.is_a::<JsFunction>() {
+            let symbol_for = get_fn(scope, symbol.to_raw(), "for");
+            let this = symbol_for.as_value(scope);
+            let argument = JsString::new(scope, "react.element").unwrap();
+            value = symbol_for
+                .call(scope, this, vec![argument])
+                .unwrap()
+                .to_string(scope)
+                .unwrap()
+                .value();
+        }
+        react_element_type.get_or_insert(value);
+    }
+    if obj.is_a::<JsObject>() && !obj.is_a::<JsNull>() {
+        match JsObject
+            ::from_raw(obj.to_raw())
+            .get(scope, "$$typeof")
+            .unwrap()
+            .variant()
+        {
+            Variant::Number(number) => {
+                number.value() as u32 == REACT_ELEMENT_TYPE_NUMBER
+            },
+            Variant::String(s) => {
+                Some(s.value()) == *react_element_type
+            }
+            _ => false
+        }
+    } else {
+        false
+    }
+}
+
+
+pub fn not(value: Handle<JsValue>) -> bool {
+    // [MDN]: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Operators/Logical_Operators
+    //   * undefined
+    //   * null
+    //   * NaN
+    //   * 0
+    //   * Empty String ("")
+
+    // ::FIXME: May have bug
+    match value.variant() {
+        Variant::Null(_) | Variant::Undefined(_) => true,
+        Variant::Number(v) => v.value() == 0.0,
+        Variant::String(v) => v.value().len() == 0,
+        _ => false
+    }
+}
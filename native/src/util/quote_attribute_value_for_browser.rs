@@ -1,6 +1,6 @@
-use neon::scope::RootScope;
-use neon::mem::Handle;
-use neon::js::JsValue;
+use element::PropValue;
+use sink::Sink;
+use util::escape_text_content_for_browser::escape_text_content_for_browser_into;
 
 /**
  * Escapes attribute value to prevent scripting attacks.
@@ -8,11 +8,65 @@ use neon::js::JsValue;
  * @param {*} value Value to escape.
  * @return {string} An escaped string.
  */
+pub fn quote_attribute_value_for_browser_into<S: Sink + ?Sized>(
+    out: &mut S,
+    value: &PropValue,
+) {
+    out.write_char('"');
+    escape_text_content_for_browser_into(out, value);
+    out.write_char('"');
+}
+
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use element::{PropValue, Props};
+    use util::escape_text_content_for_browser::tests::{NASTY, unescape};
+    use super::quote_attribute_value_for_browser_into;
+
+    fn quote_attribute_value_for_browser(value: &PropValue) -> String {
+        let mut out = String::new();
+        quote_attribute_value_for_browser_into(&mut out, value);
+        out
+    }
+
+    fn prop_value() -> BoxedStrategy<PropValue> {
+        let scalar = prop_oneof![
+            Just(PropValue::Null),
+            any::<bool>().prop_map(PropValue::Bool),
+            any::<f64>().prop_map(PropValue::Number),
+            NASTY.prop_map(PropValue::String),
+            any::<String>().prop_map(PropValue::String),
+        ];
+        scalar.prop_recursive(2, 8, 4, |inner| prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(PropValue::Array),
+            prop::collection::vec((NASTY, inner), 0..4)
+                .prop_map(|entries| PropValue::Object(entries.into_iter().collect::<Props>())),
+        ]).boxed()
+    }
+
+    // In a double quoted attribute value only `"` ends the value, and `>`
+    // only ends the tag after it.
+    fn assert_contained(quoted: &str) {
+        let tag = format!("<a title={}>", quoted);
+        assert!(quoted.starts_with('"') && quoted.ends_with('"'), "{:?}", quoted);
+        assert_eq!(tag.matches('"').count(), 2, "{:?}", tag);
+        assert_eq!(tag.find('>'), Some(tag.len() - 1), "{:?}", tag);
+    }
 
-use util::escape_text_content_for_browser::escape_text_content_for_browser;
+    proptest! {
+        #[test]
+        fn values_can_not_break_out(value in prop_value()) {
+            assert_contained(quote_attribute_value_for_browser(&value).as_str());
+        }
 
-pub fn quote_attribute_value_for_browser(
-    scope: &mut RootScope, obj: Handle<JsValue>
-) -> String {
-    format!("\"{}\"", escape_text_content_for_browser(scope, obj))
+        #[test]
+        fn quoted_strings_round_trip(text in NASTY) {
+            let quoted = quote_attribute_value_for_browser(&PropValue::String(text.clone()));
+            assert_contained(quoted.as_str());
+            prop_assert_eq!(unescape(&quoted[1..quoted.len() - 1]), text);
+        }
+    }
 }
@@ -0,0 +1,29 @@
+use std::collections::HashSet;
+
+// Attributes whose values are URLs, checked against the `UrlPolicy` before
+// they are written. HTML attribute names are case-insensitive, so names are
+// looked up ASCII-lowercased, see `is_url_property`. Custom elements write
+// their props verbatim, so attribute names are listed along with prop names.
+// `srcset` and `ping` hold several URLs, see `url_policy::urls`.
+lazy_static! {
+    pub static ref URL_PROPERTIES: HashSet<&'static str> = hashset! {
+        "action",
+        "background",
+        "cite",
+        "data",
+        "formaction",
+        "href",
+        "imagesrcset",
+        "manifest",
+        "ping",
+        "poster",
+        "src",
+        "srcset",
+        "xlinkhref",
+        "xlink:href",
+    };
+}
+
+pub fn is_url_property(name: &str) -> bool {
+    URL_PROPERTIES.contains(name.to_ascii_lowercase().as_str())
+}
@@ -0,0 +1,112 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::assets::{AssetManifest, Assets, Entrypoint, UnknownEntrypoint};
+use react_neon_ssr::{Document, Element, Node, RenderOptions, render_with_options};
+
+fn entrypoint(scripts: &[&str], styles: &[&str]) -> Entrypoint {
+    Entrypoint {
+        scripts: scripts.iter().map(|file| file.to_string()).collect(),
+        styles: styles.iter().map(|file| file.to_string()).collect(),
+    }
+}
+
+#[test]
+fn reads_manifests() {
+    let flat = AssetManifest::parse(
+        r#"{"main.js": "static/js/main.1.js", "main.js.map": "static/js/main.1.js.map",
+            "main.css": "static/css/main.1.css", "logo.svg": "static/media/logo.svg"}"#
+    ).unwrap();
+    assert_eq!(
+        flat,
+        AssetManifest::new().entrypoint(
+            "main", entrypoint(&["static/js/main.1.js"], &["static/css/main.1.css"]),
+        )
+    );
+
+    let listed = AssetManifest::parse(
+        r#"{"files": {"main.js": "/static/js/main.js"},
+            "entrypoints": ["static/js/runtime.js", "static/css/main.css", "static/js/main.js"]}"#
+    ).unwrap();
+    assert_eq!(
+        listed,
+        AssetManifest::new().entrypoint(
+            "main",
+            entrypoint(&["static/js/runtime.js", "static/js/main.js"], &["static/css/main.css"]),
+        )
+    );
+
+    let named = AssetManifest::parse(
+        r#"{"entrypoints": {
+            "app": {"assets": {"js": ["vendor.js", "app.js"], "css": ["app.css"]}},
+            "admin": {"js": ["vendor.js", "admin.js"], "css": "admin.css"}
+        }}"#
+    ).unwrap();
+    assert_eq!(
+        named,
+        AssetManifest::new()
+            .entrypoint("app", entrypoint(&["vendor.js", "app.js"], &["app.css"]))
+            .entrypoint("admin", entrypoint(&["vendor.js", "admin.js"], &["admin.css"]))
+    );
+
+    assert_eq!(
+        named.assets(&["app", "admin"], "https://cdn.example.com/static/"),
+        Ok(Assets {
+            scripts: vec![
+                "https://cdn.example.com/static/vendor.js".to_string(),
+                "https://cdn.example.com/static/app.js".to_string(),
+                "https://cdn.example.com/static/admin.js".to_string(),
+            ],
+            styles: vec![
+                "https://cdn.example.com/static/app.css".to_string(),
+                "https://cdn.example.com/static/admin.css".to_string(),
+            ],
+        })
+    );
+    assert_eq!(named.assets(&["shop"], ""), Err(UnknownEntrypoint("shop".to_string())));
+}
+
+#[test]
+fn writes_the_asset_tags() {
+    let manifest = AssetManifest::new()
+        .entrypoint("main", entrypoint(&["/js/main.js"], &["/css/main.css"]));
+    let assets = manifest.assets(&["main"], "").unwrap();
+    let render = |node: &Node, document: Option<Document>| {
+        let options = RenderOptions {
+            static_markup: true,
+            document,
+            assets: Some(assets.clone()),
+            ..RenderOptions::default()
+        };
+        render_with_options(node, &options).unwrap()
+    };
+    let links = "<link rel=\"stylesheet\" href=\"/css/main.css\"/>\
+                 <link rel=\"preload\" href=\"/js/main.js\" as=\"script\"/>";
+    let script = "<script src=\"/js/main.js\" defer=\"\"></script>";
+
+    let app: Node = Element::new("p").into();
+    let document = Document::new().head(Element::new("meta").prop("charSet", "utf-8").into());
+    assert_eq!(
+        render(&app, Some(document)).html,
+        format!(
+            "<!DOCTYPE html><html><head><meta charSet=\"utf-8\"/>{}</head>\
+             <body><p></p>{}</body></html>",
+            links, script,
+        )
+    );
+
+    let page: Node = Element::new("html")
+        .child(Element::new("head").into())
+        .child(Element::new("body").child(app.clone()).into())
+        .into();
+    let result = render(&page, None);
+    assert_eq!(
+        result.html,
+        format!("<html><head>{}</head><body><p></p>{}</body></html>", links, script)
+    );
+    assert_eq!((result.head, result.scripts), (None, None));
+
+    let result = render(&app, None);
+    assert_eq!(result.html, "<p></p>");
+    assert_eq!(result.head.unwrap(), links);
+    assert_eq!(result.scripts.unwrap(), script);
+}
@@ -0,0 +1,50 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::cache;
+use react_neon_ssr::{Element, Node, PropValue, Props, render_to_string};
+
+fn stats(name: &str) -> cache::CacheStats {
+    cache::stats().into_iter().find(|stats| stats.name == name).unwrap()
+}
+
+fn styled(names: &[&str]) -> Node {
+    let style: Props = names.iter()
+        .map(|name| (name.to_string(), PropValue::from("1")))
+        .collect();
+    Element::new("div").prop("style", PropValue::Object(style)).into()
+}
+
+// One test, the counters are shared by every thread of the process.
+#[test]
+fn caches_are_bounded_and_manageable() {
+    let names: Vec<String> = (0..50).map(|i| format!("customProp{}", i)).collect();
+    let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+
+    let before = stats("styleName");
+    render_to_string(&styled(&names));
+    render_to_string(&styled(&names));
+    let after = stats("styleName");
+    assert_eq!(after.misses - before.misses, 50);
+    assert_eq!(after.hits - before.hits, 50);
+
+    assert!(cache::resize("styleName", 10));
+    assert!(cache::clear(Some("styleName")));
+    let before = stats("styleName");
+    render_to_string(&styled(&names));
+    let after = stats("styleName");
+    assert_eq!(after.misses - before.misses, 50);
+    assert_eq!(after.evictions - before.evictions, 40);
+
+    // The oldest names were evicted, the newest are still cached.
+    let before = stats("styleName");
+    render_to_string(&styled(&names[..5]));
+    render_to_string(&styled(&names[45..]));
+    let after = stats("styleName");
+    assert_eq!(after.misses - before.misses, 5);
+    assert_eq!(after.hits - before.hits, 5);
+
+    assert!(!cache::resize("nope", 10));
+    assert!(!cache::clear(Some("nope")));
+    assert!(cache::clear(None));
+    cache::resize("styleName", 1024);
+}
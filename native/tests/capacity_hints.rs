@@ -0,0 +1,14 @@
+//! On its own, as filling the capacity hints evicts those of other tests.
+extern crate react_neon_ssr;
+
+use react_neon_ssr::sink::{capacity_hint, record_capacity_hint};
+
+#[test]
+fn keeps_a_bounded_number_of_capacity_hints() {
+    record_capacity_hint("/first", 10);
+    for i in 0..2000 {
+        record_capacity_hint(format!("/pages/{}", i).as_str(), 1);
+    }
+    assert_eq!(capacity_hint("/first"), 0);
+    assert_eq!(capacity_hint("/pages/1999"), 1);
+}
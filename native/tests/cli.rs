@@ -0,0 +1,128 @@
+//! The `react-neon-ssr` binary. Needs the `cli` feature:
+//!
+//!   cargo test --no-default-features --features cli --test cli
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+const HEADING: &str = r#"{"type": "h1", "props": {"children": "Hi"}}"#;
+
+struct Output {
+    code: i32,
+    stdout: String,
+    stderr: String,
+}
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_react-neon-ssr"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Options are checked first, so the binary may exit without reading.
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    let output = child.wait_with_output().unwrap();
+    Output {
+        code: output.status.code().unwrap(),
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+    }
+}
+
+// An empty directory for one test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("react-neon-ssr-cli-{}-{}", process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn path_str(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn renders_stdin_to_stdout() {
+    let output = run(&[], HEADING);
+    assert_eq!((output.code, output.stderr.as_str()), (0, ""));
+    assert_eq!(output.stdout, "<h1 data-reactroot=\"\">Hi</h1>\n");
+
+    let output = run(&["--static", "-"], HEADING);
+    assert_eq!((output.code, output.stdout.as_str()), (0, "<h1>Hi</h1>\n"));
+}
+
+#[test]
+fn exits_with_1_for_trees_that_cant_be_rendered() {
+    let output = run(&[], r#"{"type": "#);
+    assert_eq!(output.code, 1);
+    assert_eq!(output.stdout, "");
+    assert!(
+        output.stderr.starts_with("react-neon-ssr: <stdin>: invalid element tree: EOF while parsing"),
+        "{}", output.stderr
+    );
+
+    let output = run(&[], r#"{"type": "a b"}"#);
+    assert_eq!((output.code, output.stderr.as_str()), (1, "react-neon-ssr: <stdin>: Invalid tag: a b\n"));
+
+    let output = run(&["-s"], r#"{"type": "p", "props": {"on click": "x()"}}"#);
+    assert_eq!(output.code, 1);
+    assert_eq!(output.stderr, "react-neon-ssr: <stdin>: Invalid attribute name on <p>: `on click`\n");
+}
+
+#[test]
+fn exits_with_2_for_bad_arguments() {
+    for (args, error) in &[
+        (vec!["--nope"], "unknown option `--nope`"),
+        (vec!["-o"], "-o requires a directory"),
+        (vec!["-", "-"], "stdin (`-`) can only be read once"),
+        (vec!["-o", "out", "-"], "--out-dir can not be used with stdin"),
+    ] {
+        let output = run(args, HEADING);
+        assert_eq!(output.code, 2, "{:?}", args);
+        assert_eq!(output.stdout, "");
+        assert!(output.stderr.starts_with(&format!("react-neon-ssr: {}\n\nUsage:", error)), "{}", output.stderr);
+    }
+}
+
+#[test]
+fn writes_each_file_to_the_out_dir() {
+    let dir = temp_dir("out-dir");
+    let (a, b) = (dir.join("a.json"), dir.join("b.json"));
+    fs::write(&a, HEADING).unwrap();
+    fs::write(&b, r#"{"type": "p", "props": {"children": "b"}}"#).unwrap();
+    let out = dir.join("pages");
+
+    let output = run(&["-s", "-o", path_str(&out), path_str(&a), path_str(&b)], "");
+    assert_eq!((output.code, output.stdout.as_str(), output.stderr.as_str()), (0, "", ""));
+    assert_eq!(fs::read_to_string(out.join("a.html")).unwrap(), "<h1>Hi</h1>");
+    assert_eq!(fs::read_to_string(out.join("b.html")).unwrap(), "<p>b</p>");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn refuses_files_that_would_overwrite_each_other() {
+    let dir = temp_dir("collisions");
+    let (a, b) = (dir.join("a"), dir.join("b"));
+    fs::create_dir_all(&a).unwrap();
+    fs::create_dir_all(&b).unwrap();
+    let (a, b) = (a.join("index.json"), b.join("index.json"));
+    fs::write(&a, HEADING).unwrap();
+    fs::write(&b, HEADING).unwrap();
+    let out = dir.join("pages");
+
+    let output = run(&["-o", path_str(&out), path_str(&a), path_str(&b)], "");
+    assert_eq!(output.code, 2);
+    let error = format!(
+        "react-neon-ssr: `{}` and `{}` would both be written to {}\n",
+        a.display(),
+        b.display(),
+        out.join("index.html").display(),
+    );
+    assert!(output.stderr.starts_with(&error), "{}", output.stderr);
+    assert!(!out.exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,74 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::component;
+use react_neon_ssr::{Element, Node, PropValue, Props, render_to_static_markup, render_to_string};
+
+// `<span class={tone}>{label}: {children}</span>`
+fn badge(props: &Props, children: &[Node]) -> Node {
+    let tone = props.get("tone").and_then(PropValue::as_str).unwrap_or("plain");
+    let label = props.get("label").and_then(PropValue::as_str).unwrap_or("");
+    let mut span = Element::new("span")
+        .prop("className", tone)
+        .child(Node::text(format!("{}: ", label)));
+    for child in children {
+        span = span.child(child.clone());
+    }
+    span.into()
+}
+
+fn badge_element(tag: &str) -> Node {
+    Element::new("section")
+        .child(Element::new(tag)
+            .prop("tone", "new")
+            .prop("label", "Sale")
+            .child(Node::text("up to "))
+            .child(Element::new("b").child(Node::text("50%")).into())
+            .into())
+        .into()
+}
+
+#[test]
+fn renders_registered_components_inline() {
+    component::register("RegistryBadge", badge);
+    assert!(component::names().contains(&"RegistryBadge".to_string()));
+    assert!(component::get("RegistryBadge").is_some());
+
+    let node = badge_element("RegistryBadge");
+    assert_eq!(
+        render_to_static_markup(&node),
+        "<section><span class=\"new\">Sale: up to <b>50%</b></span></section>"
+    );
+    assert_eq!(
+        render_to_string(&node),
+        "<section data-reactroot=\"\"><span class=\"new\">Sale: <!-- -->up to <b>50%</b></span></section>"
+    );
+
+    assert!(component::unregister("RegistryBadge"));
+    assert!(!component::unregister("RegistryBadge"));
+    assert_eq!(
+        render_to_static_markup(&node),
+        "<section><RegistryBadge tone=\"new\" label=\"Sale\">up to <b>50%</b></RegistryBadge></section>"
+    );
+}
+
+#[test]
+fn other_names_are_dom_elements() {
+    component::register("RegistryKnown", badge);
+    assert!(component::get("RegistryUnknown").is_none());
+    assert_eq!(
+        render_to_static_markup(&badge_element("RegistryUnknown")),
+        "<section><RegistryUnknown tone=\"new\" label=\"Sale\">up to <b>50%</b></RegistryUnknown></section>"
+    );
+    // Lowercase names are never components, so they can't shadow a tag.
+    assert!(component::get("registryknown").is_none());
+    assert_eq!(
+        render_to_static_markup(&badge_element("registryknown")),
+        "<section><registryknown tone=\"new\" label=\"Sale\">up to <b>50%</b></registryknown></section>"
+    );
+}
+
+#[test]
+#[should_panic(expected = "Native component names must start with an uppercase letter: span")]
+fn refuses_lowercase_names() {
+    component::register("span", badge);
+}
@@ -0,0 +1,124 @@
+extern crate react_neon_ssr;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::element::CachedNode;
+use react_neon_ssr::{Element, Node, Props};
+use react_neon_ssr::{render_to_static_markup, render_to_string};
+
+fn label(props: &Props, _: &[Node]) -> Node {
+    let label = props.get("label").map(|label| label.to_string());
+    Node::Fragment(vec![
+        Node::text(label.unwrap_or_default()),
+        Element::new("b").child(Node::text("!")).into(),
+        Node::text("tail"),
+    ])
+}
+
+// Every test registers its own components, the cache is shared by the
+// whole process.
+fn counting(name: &str, ttl: Option<Duration>) -> Arc<AtomicUsize> {
+    let renders = Arc::new(AtomicUsize::new(0));
+    let counter = renders.clone();
+    let render = move |props: &Props, children: &[Node]| -> Node {
+        counter.fetch_add(1, Ordering::SeqCst);
+        label(props, children)
+    };
+    match ttl {
+        Some(ttl) => component::register(name, Pure::new(render).ttl(ttl)),
+        None => component::register(name, Pure::new(render)),
+    }
+    renders
+}
+
+fn labelled(name: &str, label: &str) -> Node {
+    Element::new(name).prop("label", label).into()
+}
+
+fn trees(name: &str) -> Vec<Node> {
+    vec![
+        // The root, with data-reactroot inside.
+        labelled(name, "a"),
+        Element::new("div").child(labelled(name, "a")).into(),
+        // Text on both sides needs separators.
+        Node::Fragment(vec![
+            Node::text("before"),
+            labelled(name, "a"),
+            Node::text("after"),
+        ]),
+        Element::new("p")
+            .child(Node::text("x"))
+            .child(labelled(name, "a"))
+            .child(labelled(name, "a"))
+            .into(),
+    ]
+}
+
+#[test]
+fn cached_markup_matches_a_fresh_render() {
+    let renders = counting("CachedLabel", None);
+    component::register("UncachedLabel", label);
+    let cached = trees("CachedLabel");
+    let uncached = trees("UncachedLabel");
+    for _ in 0..2 {
+        for (cached, uncached) in cached.iter().zip(&uncached) {
+            assert_eq!(render_to_string(cached), render_to_string(uncached));
+            assert_eq!(
+                render_to_static_markup(cached),
+                render_to_static_markup(uncached)
+            );
+        }
+    }
+    // Once per context: static or not, at the root or not.
+    assert_eq!(renders.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn separators_are_written_around_cached_text() {
+    counting("CachedText", None);
+    let tree: Node = Element::new("p")
+        .child(Node::text("x"))
+        .child(labelled("CachedText", "y"))
+        .child(labelled("CachedText", "y"))
+        .into();
+    let expected = "<p data-reactroot=\"\">x<!-- -->y<b>!</b>tail<!-- -->y<b>!</b>tail</p>";
+    assert_eq!(render_to_string(&tree), expected);
+    assert_eq!(render_to_string(&tree), expected);
+    assert_eq!(render_to_static_markup(&tree), "<p>xy<b>!</b>taily<b>!</b>tail</p>");
+}
+
+#[test]
+fn props_are_part_of_the_key() {
+    let renders = counting("CachedKeyed", None);
+    assert_eq!(render_to_static_markup(&labelled("CachedKeyed", "a")), "a<b>!</b>tail");
+    assert_eq!(render_to_static_markup(&labelled("CachedKeyed", "b")), "b<b>!</b>tail");
+    assert_eq!(render_to_static_markup(&labelled("CachedKeyed", "a")), "a<b>!</b>tail");
+    assert_eq!(renders.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn expired_markup_is_rendered_again() {
+    let renders = counting("CachedExpiring", Some(Duration::from_millis(0)));
+    render_to_static_markup(&labelled("CachedExpiring", "a"));
+    render_to_static_markup(&labelled("CachedExpiring", "a"));
+    assert_eq!(renders.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn cached_nodes_render_like_their_content() {
+    let content: Node = Element::new("span").child(Node::text("cached")).into();
+    let cached = Node::Cached(Arc::new(CachedNode {
+        key: "cached-nodes-render-like-their-content".to_string(),
+        ttl: None,
+        node: content.clone(),
+    }));
+    for _ in 0..2 {
+        assert_eq!(render_to_string(&cached), render_to_string(&content));
+        let tree: Node = Element::new("div").child(cached.clone()).into();
+        let expected: Node = Element::new("div").child(content.clone()).into();
+        assert_eq!(render_to_string(&tree), render_to_string(&expected));
+    }
+}
@@ -0,0 +1,57 @@
+//! On its own, as shrinking the component cache evicts the entries of other
+//! tests.
+extern crate react_neon_ssr;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use react_neon_ssr::component_cache::{self, CachedHtml};
+
+// 40 bytes with a one letter key.
+fn insert(key: &str, ttl: Option<Duration>) {
+    let html = Arc::new(CachedHtml {
+        html: format!("<p>{:0>32}</p>", key),
+        leading_text: Some(false),
+        trailing_text: false,
+        depth: 1,
+        injections: Vec::new(),
+        stats: None,
+    });
+    component_cache::insert_html(key.to_string(), html, ttl);
+}
+
+fn cached(keys: &[&str]) -> Vec<bool> {
+    keys.iter().map(|key| component_cache::get_html(key).is_some()).collect()
+}
+
+#[test]
+fn evicts_expired_then_oldest_entries() {
+    component_cache::clear();
+    component_cache::set_max_bytes(100);
+
+    insert("a", None);
+    insert("b", Some(Duration::from_millis(1)));
+    thread::sleep(Duration::from_millis(5));
+    // Only the expired entry makes room.
+    insert("c", None);
+    assert_eq!(cached(&["a", "b", "c"]), vec![true, false, true]);
+    assert_eq!(component_cache::stats().bytes, 80);
+
+    // Inserted again, `a` is newer than `c`.
+    insert("a", None);
+    insert("d", None);
+    assert_eq!(cached(&["a", "c", "d"]), vec![true, false, true]);
+    assert_eq!(component_cache::stats().entries, 2);
+
+    // Many replaced entries leave the order bounded and eviction right.
+    for _ in 0..1000 {
+        insert("a", Some(Duration::from_secs(60)));
+    }
+    insert("e", None);
+    assert_eq!(cached(&["a", "d", "e"]), vec![true, false, true]);
+    assert_eq!(component_cache::stats().bytes, 80);
+
+    component_cache::set_max_bytes(16 * 1024 * 1024);
+    component_cache::clear();
+}
@@ -0,0 +1,88 @@
+//! Golden-file conformance tests against `react-dom/server`.
+//!
+//! Every `fixtures/conformance/<name>.json` element tree has the markup
+//! `ReactDOMServer.renderToString` produced for it in `<name>.html`, and the
+//! `renderToStaticMarkup` markup in `<name>.static.html`. Regenerate them with
+//! `node scripts/generate-conformance-fixtures.js` after adding a case.
+//!
+//! Run with `cargo test --no-default-features`, no Node required.
+extern crate react_neon_ssr;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use react_neon_ssr::json;
+use react_neon_ssr::{render_to_string, render_to_static_markup};
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("conformance");
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("{}: {}", dir.display(), err))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn check(failures: &mut Vec<String>, expected_path: &Path, actual: &str) {
+    let expected = match fs::read_to_string(expected_path) {
+        Ok(expected) => expected,
+        Err(err) => {
+            failures.push(format!("{}: {}", expected_path.display(), err));
+            return;
+        },
+    };
+    // Fixture files end with a newline, the markup itself never does.
+    let expected = if expected.ends_with('\n') {
+        &expected[..expected.len() - 1]
+    } else {
+        expected.as_str()
+    };
+    if expected != actual {
+        failures.push(format!(
+            "{}\n  expected: {}\n    actual: {}",
+            expected_path.display(),
+            expected,
+            actual,
+        ));
+    }
+}
+
+#[test]
+fn matches_react_dom_server() {
+    let paths = fixtures();
+    assert!(!paths.is_empty(), "no conformance fixtures found");
+
+    let mut failures = Vec::new();
+    for path in &paths {
+        let source = fs::read_to_string(path).unwrap();
+        let node = match json::from_str(source.as_str()) {
+            Ok(node) => node,
+            Err(err) => {
+                failures.push(format!("{}: {}", path.display(), err));
+                continue;
+            },
+        };
+        check(
+            &mut failures,
+            &path.with_extension("html"),
+            render_to_string(&node).as_str(),
+        );
+        check(
+            &mut failures,
+            &path.with_extension("static.html"),
+            render_to_static_markup(&node).as_str(),
+        );
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} conformance checks failed:\n\n{}",
+        failures.len(),
+        paths.len() * 2,
+        failures.join("\n\n"),
+    );
+}
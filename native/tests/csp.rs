@@ -0,0 +1,81 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::csp::{InlineHashes, sha256_source};
+use react_neon_ssr::{Element, Node, Props, RenderOptions, render_to_static_markup, render_with_options};
+
+fn inline(tag: &str, body: &str) -> Node {
+    Element::new(tag).child(Node::RawHtml(body.to_string())).into()
+}
+
+fn with_nonce(node: &Node, nonce: &str) -> String {
+    let options = RenderOptions {
+        static_markup: true,
+        nonce: Some(nonce.to_string()),
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options).unwrap().html
+}
+
+fn hashed(node: &Node) -> (String, InlineHashes) {
+    let options = RenderOptions {
+        static_markup: true,
+        inline_hashes: true,
+        ..RenderOptions::default()
+    };
+    let result = render_with_options(node, &options).unwrap();
+    (result.html, result.inline_hashes.unwrap())
+}
+
+#[test]
+fn stamps_the_nonce_on_scripts_and_styles() {
+    let node: Node = Element::new("head")
+        .child(inline("script", "window.x = 1"))
+        .child(Element::new("script").prop("src", "/app.js").into())
+        .child(inline("style", "p{}"))
+        .child(Element::new("script").prop("nonce", "static").into())
+        .child(Element::new("link").prop("rel", "stylesheet").into())
+        .into();
+    assert_eq!(
+        with_nonce(&node, "r4nd\"m"),
+        "<head><script nonce=\"r4nd&quot;m\">window.x = 1</script>\
+         <script src=\"/app.js\" nonce=\"r4nd&quot;m\"></script>\
+         <style nonce=\"r4nd&quot;m\">p{}</style>\
+         <script nonce=\"static\"></script><link rel=\"stylesheet\"/></head>"
+    );
+}
+
+#[test]
+fn hashes_inline_bodies() {
+    assert_eq!(
+        sha256_source("alert('Hello, world.');"),
+        "sha256-qznLcsROx4GACP2dm0UCKCzCG+HiZ1guq6ZZDob/Tng="
+    );
+    let node: Node = Element::new("div")
+        .child(inline("script", "alert('Hello, world.');"))
+        .child(inline("script", "alert('Hello, world.');"))
+        .child(Element::new("script").prop("src", "/app.js").child(Node::text("x")).into())
+        .child(Element::new("script").into())
+        .child(Element::new("style").child(Node::text("a > b {}")).into())
+        .into();
+    let (html, hashes) = hashed(&node);
+    assert!(html.contains("<style>a &gt; b {}</style>"));
+    assert_eq!(hashes.scripts, vec!["sha256-qznLcsROx4GACP2dm0UCKCzCG+HiZ1guq6ZZDob/Tng="]);
+    // The hash is of the markup the browser gets.
+    assert_eq!(hashes.styles, vec![sha256_source("a &gt; b {}")]);
+}
+
+#[test]
+fn cached_components_get_each_render_its_nonce() {
+    component::register("CspAnalytics", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        inline("script", "track()")
+    }));
+    let node: Node = Element::new("CspAnalytics").into();
+    assert_eq!(render_to_static_markup(&node), "<script>track()</script>");
+    assert_eq!(with_nonce(&node, "a"), "<script nonce=\"a\">track()</script>");
+    assert_eq!(with_nonce(&node, "b"), "<script nonce=\"b\">track()</script>");
+    let (_, hashes) = hashed(&node);
+    assert_eq!(hashes.scripts, vec![sha256_source("track()")]);
+    let (_, hashes) = hashed(&node);
+    assert_eq!(hashes.scripts.len(), 1);
+}
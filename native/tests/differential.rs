@@ -0,0 +1,436 @@
+//! Differential tests between the Rust renderer and `react-dom/server`.
+//!
+//! Random element trees (known and unknown props, style objects, unicode
+//! text, form elements, namespaces) are rendered by both. Every tree whose
+//! markup differs is minimised and saved to `target/differential/` in the
+//! conformance fixture format, with react-dom's markup as the expected
+//! output, ready to be copied into `tests/fixtures/conformance`.
+//!
+//! The reference is `scripts/react-dom-reference.js`, so this needs Node and
+//! the repository's `node_modules`. It is ignored by default:
+//!
+//!   cargo test --no-default-features --test differential -- --ignored
+//!
+//! `DIFFERENTIAL_CASES` (default 500) and `DIFFERENTIAL_SEED` (default
+//! random) control the run, `NODE` picks the node binary.
+extern crate rand;
+extern crate react_neon_ssr;
+extern crate serde_json;
+
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use react_neon_ssr::json;
+use react_neon_ssr::{render_to_string, render_to_static_markup};
+use react_neon_ssr::{Element, Node, PropValue, Props};
+
+const MAX_DEPTH: usize = 4;
+
+const TAGS: &[&str] = &[
+    "div", "span", "p", "a", "b", "label", "ul", "li", "table", "td", "pre",
+    "listing", "textarea", "input", "select", "option", "img", "br", "video",
+    "svg", "path", "text", "foreignObject", "math", "mi", "my-element",
+    "font-face",
+];
+
+const PROP_NAMES: &[&str] = &[
+    "className", "htmlFor", "id", "title", "hidden", "download", "draggable",
+    "spellCheck", "contentEditable", "acceptCharset", "httpEquiv", "rows",
+    "cols", "size", "span", "start", "rowSpan", "tabIndex", "autoPlay",
+    "value", "defaultValue", "checked", "defaultChecked", "multiple",
+    "selected", "type", "strokeWidth", "fillOpacity", "xlinkHref", "xmlLang",
+    "viewBox", "data-test", "aria-label", "someProp", "onClick", "is",
+];
+
+const STYLE_NAMES: &[&str] = &[
+    "color", "fontSize", "lineHeight", "opacity", "zIndex", "flexGrow",
+    "marginTop", "width", "float", "WebkitTransition", "msTransform",
+    "MozBoxSizing", "--custom-prop", "fontFamily",
+];
+
+const STRINGS: &[&str] = &[
+    "", " ", "a", "0", "-1", "1.5", "1e21", "true", "false", "null", "\n",
+    "\nx", "  pad  ", "<b>x</b>", "&amp;", "a&b<c>d\"e'f", "javascript:x",
+    "héllo", "日本語", "🎉", "\u{0}", "\u{7f}", "\u{2028}",
+];
+
+const CHARS: &[char] = &[
+    'a', 'Z', '0', ' ', '\n', '\t', '<', '>', '&', '"', '\'', '`', '=', '/',
+    ';', ':', 'é', '日', '🎉', '\u{0}', '\u{200b}',
+];
+
+const NUMBERS: &[f64] = &[
+    0.0, -0.0, 1.0, -1.0, 0.5, 1.5, 2.0, 12.0, 0.1, 1e21, 1e-7, 123456789.0,
+];
+
+#[derive(Debug, PartialEq)]
+struct Rendered {
+    html: String,
+    static_markup: String,
+}
+
+enum Outcome {
+    Same,
+    // react-dom refused the tree, e.g. children on a void element.
+    Rejected,
+    Differs(Rendered, Rendered),
+}
+
+struct Reference {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Reference {
+    fn spawn() -> Reference {
+        let script = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join("scripts")
+            .join("react-dom-reference.js");
+        let node = env::var("NODE").unwrap_or_else(|_| "node".to_string());
+        let mut child = Command::new(&node)
+            .arg(&script)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|err| panic!("failed to run `{}`: {}", node, err));
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Reference { child, stdin, stdout }
+    }
+
+    fn render(&mut self, json: &str) -> Option<Rendered> {
+        writeln!(self.stdin, "{}", json).unwrap();
+        let mut line = String::new();
+        self.stdout.read_line(&mut line).unwrap();
+        assert!(
+            !line.is_empty(),
+            "the react-dom reference exited, are react and react-dom installed?"
+        );
+        let result: serde_json::Value = serde_json::from_str(line.as_str()).unwrap();
+        if result.get("error").is_some() {
+            return None;
+        }
+        Some(Rendered {
+            html: result["html"].as_str().unwrap().to_string(),
+            static_markup: result["static"].as_str().unwrap().to_string(),
+        })
+    }
+}
+
+impl Drop for Reference {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+fn compare(reference: &mut Reference, node: &Node) -> Outcome {
+    let source = match json::to_string(node) {
+        Ok(source) => source,
+        Err(_) => return Outcome::Rejected,
+    };
+    let expected = match reference.render(source.as_str()) {
+        Some(expected) => expected,
+        None => return Outcome::Rejected,
+    };
+    // Render what react-dom saw, not the tree we built.
+    let node = json::from_str(source.as_str()).unwrap();
+    let actual = Rendered {
+        html: render_to_string(&node),
+        static_markup: render_to_static_markup(&node),
+    };
+    if expected == actual {
+        Outcome::Same
+    } else {
+        Outcome::Differs(expected, actual)
+    }
+}
+
+fn random_string<R: Rng>(rng: &mut R) -> String {
+    if rng.gen_bool(0.5) {
+        return STRINGS.choose(rng).unwrap().to_string();
+    }
+    let len = rng.gen_range(0..8);
+    (0..len).map(|_| *CHARS.choose(rng).unwrap()).collect()
+}
+
+fn random_scalar<R: Rng>(rng: &mut R) -> PropValue {
+    match rng.gen_range(0..10) {
+        0 => PropValue::Null,
+        1 | 2 => PropValue::Bool(rng.gen()),
+        3..=5 => PropValue::Number(*NUMBERS.choose(rng).unwrap()),
+        _ => PropValue::String(random_string(rng)),
+    }
+}
+
+fn random_style<R: Rng>(rng: &mut R) -> PropValue {
+    let len = rng.gen_range(0..4);
+    let mut style = Props::new();
+    for _ in 0..len {
+        style.insert(*STYLE_NAMES.choose(rng).unwrap(), random_scalar(rng));
+    }
+    PropValue::Object(style)
+}
+
+fn random_prop_name<R: Rng>(rng: &mut R) -> String {
+    if rng.gen_bool(0.9) {
+        return PROP_NAMES.choose(rng).unwrap().to_string();
+    }
+    let len = rng.gen_range(1..6);
+    (0..len).map(|_| rng.gen_range(b'a'..=b'z') as char).collect()
+}
+
+fn random_element<R: Rng>(rng: &mut R, depth: usize) -> Node {
+    let mut element = Element::new(*TAGS.choose(rng).unwrap());
+    for _ in 0..rng.gen_range(0..5) {
+        let name = random_prop_name(rng);
+        let value = if rng.gen_bool(0.1) {
+            PropValue::Array(vec![random_scalar(rng), random_scalar(rng)])
+        } else {
+            random_scalar(rng)
+        };
+        element.props.insert(name, value);
+    }
+    if rng.gen_bool(0.3) {
+        element.props.insert("style", random_style(rng));
+    }
+    if rng.gen_bool(0.1) {
+        element.children.push(Node::RawHtml(random_string(rng)));
+    } else if depth < MAX_DEPTH {
+        for _ in 0..rng.gen_range(0..4) {
+            element.children.push(random_node(rng, depth + 1));
+        }
+    }
+    element.into()
+}
+
+fn random_node<R: Rng>(rng: &mut R, depth: usize) -> Node {
+    match rng.gen_range(0..10) {
+        0..=3 => random_element(rng, depth),
+        4..=6 => Node::Text(random_string(rng)),
+        7 => Node::Text(random_scalar(rng).to_string()),
+        8 if depth < MAX_DEPTH => Node::Fragment(
+            (0..rng.gen_range(0..3)).map(|_| random_node(rng, depth + 1)).collect()
+        ),
+        _ => Node::empty(),
+    }
+}
+
+fn random_tree<R: Rng>(rng: &mut R) -> Node {
+    if rng.gen_bool(0.8) {
+        random_element(rng, 0)
+    } else {
+        random_node(rng, 0)
+    }
+}
+
+fn shrink_string(s: &str) -> Vec<String> {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.is_empty() {
+        return Vec::new();
+    }
+    let half = chars.len() / 2;
+    let mut candidates = vec![
+        String::new(),
+        chars[..half].iter().collect(),
+        chars[half..].iter().collect(),
+    ];
+    if chars.len() <= 16 {
+        for i in 0..chars.len() {
+            let mut shorter = chars.clone();
+            shorter.remove(i);
+            candidates.push(shorter.into_iter().collect());
+        }
+    }
+    candidates
+}
+
+fn shrink_value(value: &PropValue) -> Vec<PropValue> {
+    match *value {
+        PropValue::String(ref s) => {
+            shrink_string(s).into_iter().map(PropValue::String).collect()
+        },
+        PropValue::Array(ref items) => (0..items.len())
+            .map(|i| {
+                let mut items = items.clone();
+                items.remove(i);
+                PropValue::Array(items)
+            })
+            .collect(),
+        PropValue::Object(ref props) => shrink_props(props)
+            .into_iter()
+            .map(PropValue::Object)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn shrink_props(props: &Props) -> Vec<Props> {
+    let mut candidates = Vec::new();
+    for (name, value) in props {
+        let mut without = props.clone();
+        without.remove(name);
+        candidates.push(without);
+        for smaller in shrink_value(value) {
+            let mut props = props.clone();
+            props.insert(name.as_str(), smaller);
+            candidates.push(props);
+        }
+    }
+    candidates
+}
+
+fn shrink_children(children: &[Node]) -> Vec<Vec<Node>> {
+    let mut candidates = Vec::new();
+    for i in 0..children.len() {
+        let mut without = children.to_vec();
+        without.remove(i);
+        candidates.push(without);
+    }
+    for (i, child) in children.iter().enumerate() {
+        for smaller in shrink(child) {
+            let mut children = children.to_vec();
+            children[i] = smaller;
+            candidates.push(children);
+        }
+    }
+    candidates
+}
+
+/**
+ * Smaller variants of `node`, most aggressive first.
+ */
+fn shrink(node: &Node) -> Vec<Node> {
+    match *node {
+        Node::Element(ref element) => {
+            let mut candidates: Vec<Node> = element.children
+                .iter()
+                .filter(|child| !matches!(**child, Node::RawHtml(_) | Node::TrustedHtml(_)))
+                .cloned()
+                .collect();
+            for props in shrink_props(&element.props) {
+                let mut smaller = element.clone();
+                smaller.props = props;
+                candidates.push(smaller.into());
+            }
+            for children in shrink_children(&element.children) {
+                let mut smaller = element.clone();
+                smaller.children = children;
+                candidates.push(smaller.into());
+            }
+            candidates
+        },
+        Node::Fragment(ref nodes) => {
+            let mut candidates = nodes.clone();
+            candidates.extend(
+                shrink_children(nodes).into_iter().map(Node::Fragment)
+            );
+            candidates
+        },
+        Node::Text(ref text) => {
+            shrink_string(text).into_iter().map(Node::Text).collect()
+        },
+        Node::RawHtml(ref html) => {
+            shrink_string(html).into_iter().map(Node::RawHtml).collect()
+        },
+        Node::TrustedHtml(ref html) => {
+            shrink_string(html).into_iter().map(Node::TrustedHtml).collect()
+        },
+        // Never generated, the reference knows nothing about caching.
+        Node::Cached(ref cached) => vec![cached.node.clone()],
+        Node::Component(ref component) => vec![component.node.clone()],
+    }
+}
+
+fn minimize(reference: &mut Reference, mut node: Node) -> (Node, Rendered, Rendered) {
+    let (mut expected, mut actual) = match compare(reference, &node) {
+        Outcome::Differs(expected, actual) => (expected, actual),
+        _ => unreachable!(),
+    };
+    'shrinking: loop {
+        for candidate in shrink(&node) {
+            if let Outcome::Differs(e, a) = compare(reference, &candidate) {
+                node = candidate;
+                expected = e;
+                actual = a;
+                continue 'shrinking;
+            }
+        }
+        return (node, expected, actual);
+    }
+}
+
+fn save(node: &Node, expected: &Rendered) -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("target")
+        .join("differential");
+    fs::create_dir_all(&dir).unwrap();
+    let source = serde_json::to_string_pretty(node).unwrap();
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    let path = dir.join(format!("{:016x}.json", hasher.finish()));
+    fs::write(&path, source + "\n").unwrap();
+    fs::write(path.with_extension("html"), expected.html.clone() + "\n").unwrap();
+    fs::write(
+        path.with_extension("static.html"),
+        expected.static_markup.clone() + "\n",
+    ).unwrap();
+    path
+}
+
+fn env_number(name: &str) -> Option<u64> {
+    env::var(name).ok().map(|value| {
+        value.parse().unwrap_or_else(|_| panic!("{} must be a number", name))
+    })
+}
+
+#[test]
+#[ignore]
+fn matches_react_dom_server_on_random_trees() {
+    let cases = env_number("DIFFERENTIAL_CASES").unwrap_or(500);
+    let seed = env_number("DIFFERENTIAL_SEED").unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut reference = Reference::spawn();
+
+    let mut rejected = 0;
+    let mut failures = Vec::new();
+    for _ in 0..cases {
+        let node = random_tree(&mut rng);
+        match compare(&mut reference, &node) {
+            Outcome::Same => {},
+            Outcome::Rejected => rejected += 1,
+            Outcome::Differs(..) => {
+                let (node, expected, actual) = minimize(&mut reference, node);
+                let path = save(&node, &expected);
+                failures.push(format!(
+                    "{}\n  react-dom: {:?}\n       rust: {:?}",
+                    path.display(),
+                    expected,
+                    actual,
+                ));
+            },
+        }
+    }
+    println!(
+        "{} cases (seed {}), {} rejected by react-dom, {} mismatches",
+        cases, seed, rejected, failures.len(),
+    );
+    assert!(
+        failures.is_empty(),
+        "{} of {} random trees render differently (seed {}):\n\n{}",
+        failures.len(),
+        cases,
+        seed,
+        failures.join("\n\n"),
+    );
+}
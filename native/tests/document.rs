@@ -0,0 +1,53 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::{Document, Element, Node, PropValue, RenderOptions, render_with_options};
+
+fn render_document(node: &Node, document: Document, static_markup: bool) -> String {
+    let options = RenderOptions {
+        static_markup,
+        document: Some(document),
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options).unwrap().html
+}
+
+fn title(text: &str) -> Node {
+    Element::new("title").child(Node::text(text)).into()
+}
+
+#[test]
+fn renders_the_app_into_a_document() {
+    let app: Node = Element::new("div")
+        .child(Element::new("Head").child(title("Product")).into())
+        .child(Node::text("app"))
+        .into();
+    let document = Document::new()
+        .html_attribute("lang", "en")
+        .html_attribute("dir", PropValue::Null)
+        .body_attribute("className", "app")
+        .head(Element::new("meta").prop("charSet", "utf-8").into())
+        .head(title("Shop"))
+        .script(Element::new("script").prop("src", "/main.js").prop("defer", true).into());
+    assert_eq!(
+        render_document(&app, document, false),
+        "<!DOCTYPE html><html lang=\"en\"><head><meta charSet=\"utf-8\"/>\
+         <title>Product</title></head><body class=\"app\">\
+         <div data-reactroot=\"\">app</div><script src=\"/main.js\" defer=\"\"></script>\
+         </body></html>"
+    );
+
+    let document = Document { doctype: None, ..Document::new() };
+    assert_eq!(
+        render_document(&Node::text("text"), document, true),
+        "<html><head></head><body>text</body></html>"
+    );
+}
+
+#[test]
+fn leaves_out_invalid_shell_attributes() {
+    let document = Document::new().body_attribute("on click", "x").body_attribute("id", "b");
+    assert_eq!(
+        render_document(&Element::new("p").into(), document, true),
+        "<!DOCTYPE html><html><head></head><body id=\"b\"><p></p></body></html>"
+    );
+}
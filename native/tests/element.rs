@@ -0,0 +1,89 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::{Element, Node, PropValue, Props};
+use react_neon_ssr::{render_to_static_markup, render_to_string};
+
+#[test]
+fn renders_elements_with_their_props() {
+    let node: Node = Element::new("div")
+        .prop("className", "card")
+        .prop("hidden", true)
+        .prop("title", PropValue::Null)
+        .prop("tabIndex", 1.0)
+        .child(Element::new("br").into())
+        .child(Element::new("span").child(Node::text("x")).into())
+        .into();
+    assert_eq!(
+        render_to_string(&node),
+        "<div class=\"card\" hidden=\"\" tabindex=\"1\" data-reactroot=\"\"><br/><span>x</span></div>"
+    );
+    assert_eq!(
+        render_to_static_markup(&node),
+        "<div class=\"card\" hidden=\"\" tabindex=\"1\"><br/><span>x</span></div>"
+    );
+}
+
+#[test]
+fn escapes_text_and_attribute_values() {
+    let node: Node = Element::new("a")
+        .prop("href", "/?a=1&b=\"2\"")
+        .child(Node::text("<b>'&'</b>"))
+        .into();
+    assert_eq!(
+        render_to_static_markup(&node),
+        "<a href=\"/?a=1&amp;b=&quot;2&quot;\">&lt;b&gt;&#x27;&amp;&#x27;&lt;/b&gt;</a>"
+    );
+}
+
+#[test]
+fn separates_adjacent_text_in_fragments() {
+    let node = Node::Fragment(vec![
+        Node::text("a"),
+        Node::Fragment(vec![Node::text("b"), Node::empty()]),
+        Element::new("i").into(),
+        Node::text("c"),
+    ]);
+    assert_eq!(render_to_string(&node), "a<!-- -->b<i data-reactroot=\"\"></i>c");
+    assert_eq!(render_to_static_markup(&node), "ab<i></i>c");
+}
+
+#[test]
+fn writes_raw_html_as_is() {
+    let node: Node = Element::new("div")
+        .child(Node::RawHtml("<p>a & b</p>".to_string()))
+        .into();
+    assert_eq!(render_to_static_markup(&node), "<div><p>a & b</p></div>");
+}
+
+#[test]
+fn converts_prop_values_like_javascript() {
+    let numbers = vec![
+        ("", 0.0), (" 12 ", 12.0), ("-1.5", -1.5), ("+3", 3.0), ("0x1f", 31.0),
+        ("0b11", 3.0), ("0o7", 7.0), ("1e3", 1000.0), ("-Infinity", f64::NEG_INFINITY),
+    ];
+    for (string, number) in numbers {
+        assert_eq!(PropValue::from(string).to_number(), number, "{:?}", string);
+    }
+    for string in &["-0x1f", "12px", "1 2"] {
+        assert!(PropValue::from(*string).to_number().is_nan(), "{:?}", string);
+    }
+    assert_eq!(PropValue::Bool(true).to_number(), 1.0);
+    assert_eq!(PropValue::Null.to_number(), 0.0);
+    let array = PropValue::Array(vec![PropValue::from(1.0), PropValue::Null, "a".into()]);
+    assert_eq!(array.to_string(), "1,,a");
+    assert!(array.to_number().is_nan());
+    assert!(PropValue::Array(vec![PropValue::from(7.0)]).to_number() == 7.0);
+}
+
+#[test]
+fn props_keep_their_order() {
+    let mut props = Props::new();
+    props.insert("b", 1.0.into());
+    props.insert("a", 2.0.into());
+    props.insert("b", 3.0.into());
+    let names: Vec<&str> = props.iter().map(|entry| entry.0.as_str()).collect();
+    assert_eq!(names, vec!["b", "a"]);
+    assert_eq!(props.get("b"), Some(&PropValue::Number(3.0)));
+    assert_eq!(props.remove("b"), Some(PropValue::Number(3.0)));
+    assert_eq!(props.len(), 1);
+}
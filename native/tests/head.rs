@@ -0,0 +1,167 @@
+extern crate react_neon_ssr;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::element::{CachedNode, RenderedComponent};
+use react_neon_ssr::{Element, Node, PropValue, Props, RenderOptions, RenderResult};
+use react_neon_ssr::{render_to_static_markup, render_with_options};
+
+fn collected(node: &Node) -> RenderResult {
+    let options = RenderOptions {
+        static_markup: true,
+        collect_head: true,
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options).unwrap()
+}
+
+fn title(text: &str) -> Node {
+    Element::new("title").child(Node::text(text)).into()
+}
+
+fn meta(attribute: &str, name: &str, content: &str) -> Node {
+    Element::new("meta").prop(attribute, name).prop("content", content).into()
+}
+
+fn head(children: Vec<Node>) -> Node {
+    Element::new("Head").child(Node::Fragment(children)).into()
+}
+
+#[test]
+fn hoists_head_elements_into_the_head() {
+    component::register("HeadProductPage", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        Element::new("main")
+            .child(head(vec![
+                title("Product"),
+                meta("name", "description", "A product"),
+                meta("property", "og:title", "Product"),
+                Element::new("link").prop("rel", "canonical").prop("href", "/p/1").into(),
+            ]))
+            .child(Element::new("h1").child(Node::text("Product")).into())
+            .into()
+    }));
+    let node: Node = Element::new("html")
+        .child(Element::new("head")
+            .child(Element::new("meta").prop("charSet", "utf-8").into())
+            .child(title("Shop"))
+            .child(meta("name", "description", "A shop"))
+            .into())
+        .child(Element::new("body")
+            .child(Element::new("HeadProductPage").into())
+            .child(head(vec![title("Product | Shop")]))
+            .into())
+        .into();
+    let expected = "<html><head><meta charSet=\"utf-8\"/>\
+                    <meta name=\"description\" content=\"A product\"/>\
+                    <meta property=\"og:title\" content=\"Product\"/>\
+                    <link rel=\"canonical\" href=\"/p/1\"/>\
+                    <title>Product | Shop</title></head>\
+                    <body><main><h1>Product</h1></main></body></html>";
+    for _ in 0..2 {
+        let result = collected(&node);
+        assert_eq!(result.html, expected);
+        assert_eq!(result.head, None);
+    }
+}
+
+#[test]
+fn returns_the_head_without_a_head_element() {
+    let node: Node = Element::new("div")
+        .child(head(vec![
+            Element::new("link").prop("rel", "stylesheet").prop("href", "/a.css").into(),
+            Element::new("link").prop("rel", "stylesheet").prop("href", "/b.css").into(),
+            Element::new("link").prop("rel", "stylesheet").prop("href", "/a.css").into(),
+        ]))
+        .child(Node::text("body"))
+        .into();
+    let result = collected(&node);
+    assert_eq!(result.html, "<div>body</div>");
+    assert_eq!(
+        result.head.unwrap(),
+        "<link rel=\"stylesheet\" href=\"/b.css\"/><link rel=\"stylesheet\" href=\"/a.css\"/>"
+    );
+    // Rendered in place unless the head is collected.
+    assert_eq!(
+        render_to_static_markup(&node),
+        "<div><link rel=\"stylesheet\" href=\"/a.css\"/><link rel=\"stylesheet\" href=\"/b.css\"/>\
+         <link rel=\"stylesheet\" href=\"/a.css\"/>body</div>"
+    );
+}
+
+#[test]
+fn collects_from_cached_components_and_roots() {
+    component::register("HeadCachedTitle", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        Node::Fragment(vec![head(vec![title("Cached")]), Element::new("p").into()])
+    }));
+    let node: Node = Element::new("HeadCachedTitle").into();
+    assert_eq!(render_to_static_markup(&node), "<title>Cached</title><p></p>");
+    let result = collected(&node);
+    assert_eq!(result.html, "<p></p>");
+    assert_eq!(result.head.unwrap(), "<title>Cached</title>");
+
+    // Only the elements that stay in place are roots.
+    let node = Node::Fragment(vec![head(vec![title("Root")]), Element::new("div").into()]);
+    let options = RenderOptions { collect_head: true, ..RenderOptions::default() };
+    let result = render_with_options(&node, &options).unwrap();
+    assert_eq!(result.html, "<div data-reactroot=\"\"></div>");
+    assert_eq!(result.head.unwrap(), "<title>Root</title>");
+}
+
+fn marked(name: &str, node: Node) -> Node {
+    Node::Component(Box::new(RenderedComponent {
+        name: name.to_string(),
+        started: Instant::now(),
+        elapsed: Duration::new(0, 0),
+        node,
+    }))
+}
+
+#[test]
+fn de_duplicates_inside_components() {
+    component::register("HeadNativeTitle", |props: &Props, _: &[Node]| -> Node {
+        head(vec![title(props.get("text").and_then(PropValue::as_str).unwrap_or(""))])
+    });
+    // How JS components come out of a conversion with an options object.
+    let page = marked("Page", Node::Fragment(vec![
+        head(vec![marked("Meta", Node::Fragment(vec![
+            title("Site"),
+            meta("name", "description", "A site"),
+        ]))]),
+        Element::new("main")
+            .child(head(vec![marked("Title", title("Product"))]))
+            .child(head(vec![Node::Cached(Arc::new(CachedNode {
+                key: "HeadCachedDescription".to_string(),
+                ttl: None,
+                node: meta("name", "description", "A product"),
+            }))]))
+            .into(),
+    ]));
+    let result = collected(&page);
+    assert_eq!(result.html, "<main></main>");
+    assert_eq!(
+        result.head.unwrap(),
+        "<title>Product</title><meta name=\"description\" content=\"A product\"/>"
+    );
+
+    let node = Node::Fragment(vec![
+        head(vec![title("Site")]),
+        Element::new("HeadNativeTitle").prop("text", "Native").into(),
+    ]);
+    assert_eq!(collected(&node).head.unwrap(), "<title>Native</title>");
+
+    let options = RenderOptions {
+        static_markup: true,
+        collect_head: true,
+        profile: true,
+        stats: true,
+        trace: true,
+        ..RenderOptions::default()
+    };
+    let result = render_with_options(&page, &options).unwrap();
+    assert_eq!(
+        result.head.unwrap(),
+        "<title>Product</title><meta name=\"description\" content=\"A product\"/>"
+    );
+}
@@ -0,0 +1,79 @@
+//! Converting JS values, through the Node addon.
+//!
+//! This needs Node and the addon built (`npm install` in the repository root
+//! runs `neon build`). It is ignored by default:
+//!
+//!   cargo test --no-default-features --test js_value -- --ignored
+//!
+//! `NODE` picks the node binary.
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+// Runs `script` with the addon as `addon`, and returns what it printed.
+fn node(script: &str) -> String {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let node = env::var("NODE").unwrap_or_else(|_| "node".to_string());
+    let output = Command::new(&node)
+        .current_dir(&root)
+        .arg("-e")
+        .arg(format!("const addon = require('./native');\n{}", script))
+        .output()
+        .unwrap_or_else(|err| panic!("failed to run `{}`: {}", node, err));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap().trim_end().to_string()
+}
+
+#[test]
+#[ignore]
+fn throws_for_circular_values() {
+    let printed = node("
+        const state = {items: []};
+        state.items.push(state);
+        try {
+            addon.serializeForScript(state);
+        } catch (err) {
+            console.log(err.name + ': ' + err.message);
+        }
+        const shared = {};
+        console.log(addon.serializeForScript({a: shared, b: [shared]}));
+    ");
+    assert_eq!(
+        printed,
+        "TypeError: Converting circular structure to a prop value\n{\"a\":{},\"b\":[{}]}"
+    );
+}
+
+#[test]
+#[ignore]
+fn converts_values_with_to_json() {
+    let printed = node("
+        console.log(addon.serializeForScript({at: new Date(0)}));
+        console.log(addon.serializeForScript({id: {toJSON: () => 7}}));
+    ");
+    assert_eq!(printed, "{\"at\":\"1970-01-01T00:00:00.000Z\"}\n{\"id\":7}");
+}
+
+#[test]
+#[ignore]
+fn trusted_components_dont_trust_their_children() {
+    let printed = node("
+        function Shell(props) {
+            return {type: 'main', props: {children: [
+                {type: 'script', props: {dangerouslySetInnerHTML: {__html: 'boot()'}}},
+                props.children,
+            ]}};
+        }
+        Shell.trustedHTML = true;
+        const cms = {type: 'div', props: {dangerouslySetInnerHTML: {__html: '<b onclick=\"x()\">cms</b>'}}};
+        addon.setSanitizer({});
+        try {
+            console.log(addon.renderToStaticMarkup({type: Shell, props: {children: cms}}));
+            console.log(addon.renderToStaticMarkup({type: Shell, props: {children: [cms]}}));
+        } finally {
+            addon.setSanitizer(null);
+        }
+    ");
+    let html = "<main><script>boot()</script><div><b>cms</b></div></main>";
+    assert_eq!(printed, format!("{}\n{}", html, html));
+}
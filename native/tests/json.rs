@@ -0,0 +1,127 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::json;
+use react_neon_ssr::{Element, Node, PropValue, RenderError, RenderOptions};
+use react_neon_ssr::{render_json_to_static_markup, render_with_options};
+
+#[test]
+fn reads_element_trees() {
+    let node = json::from_str(r#"{
+        "type": "div", "key": "k", "ref": null, "$$typeof": 60103,
+        "props": {"className": "box", "tabIndex": 1, "children": [
+            "Hello ", {"type": "b", "props": {"children": 42}}, null, true, [1.5]
+        ]}
+    }"#).unwrap();
+    let expected: Node = Element::new("div")
+        .prop("className", "box")
+        .prop("tabIndex", 1.0)
+        .child(Node::text("Hello "))
+        .child(Element::new("b").child(Node::text("42")).into())
+        .child(Node::empty())
+        .child(Node::empty())
+        .child(Node::Fragment(vec![Node::text("1.5")]))
+        .into();
+    assert_eq!(node, expected);
+    assert_eq!(json::from_str(r#"{"type": "br"}"#).unwrap(), Element::new("br").into());
+}
+
+#[test]
+fn reads_inner_html_as_untrusted() {
+    let node = json::from_str(
+        r#"{"type": "div", "props": {"dangerouslySetInnerHTML": {"__html": "<b>x</b>"}}}"#
+    ).unwrap();
+    assert_eq!(node, Element::new("div").child(Node::RawHtml("<b>x</b>".to_string())).into());
+    let options = RenderOptions {
+        trusted_html_only: true,
+        ..RenderOptions::default()
+    };
+    match render_with_options(&node, &options) {
+        Err(RenderError::UntrustedHtml(_)) => {},
+        result => panic!("expected untrusted HTML, got {:?}", result.map(|result| result.html)),
+    }
+    let empty = json::from_str(
+        r#"{"type": "div", "props": {"dangerouslySetInnerHTML": {"__html": null}}}"#
+    ).unwrap();
+    assert_eq!(empty, Element::new("div").into());
+}
+
+#[test]
+fn round_trips_element_trees() {
+    let node: Node = Element::new("ul")
+        .prop("style", PropValue::Object(vec![
+            ("color".to_string(), PropValue::from("red")),
+        ].into_iter().collect()))
+        .prop("data-list", PropValue::Array(vec![PropValue::Null, PropValue::Bool(false)]))
+        .child(Element::new("li").child(Node::text("a \"quoted\" <item>")).into())
+        .child(Element::new("li").child(Node::RawHtml("<i>b</i>".to_string())).into())
+        .into();
+    let source = json::to_string(&node).unwrap();
+    assert_eq!(
+        source,
+        r#"{"type":"ul","props":{"style":{"color":"red"},"data-list":[null,false],"children":[{"type":"li","props":{"children":"a \"quoted\" <item>"}},{"type":"li","props":{"dangerouslySetInnerHTML":{"__html":"<i>b</i>"}}}]}}"#
+    );
+    assert_eq!(json::from_str(source.as_str()).unwrap(), node);
+}
+
+#[test]
+fn trusted_html_comes_back_untrusted() {
+    let node: Node = Element::new("div").child(Node::TrustedHtml("<p>ok</p>".to_string())).into();
+    let source = json::to_string(&node).unwrap();
+    assert_eq!(
+        json::from_str(source.as_str()).unwrap(),
+        Element::new("div").child(Node::RawHtml("<p>ok</p>".to_string())).into()
+    );
+}
+
+#[test]
+fn raw_html_must_be_the_only_child() {
+    let node: Node = Element::new("div")
+        .child(Node::text("a"))
+        .child(Node::RawHtml("<b>b</b>".to_string()))
+        .into();
+    assert_eq!(
+        json::to_string(&node).unwrap_err().to_string(),
+        "raw HTML can only be the only child of an element"
+    );
+}
+
+#[test]
+fn reports_malformed_input() {
+    let error = |source: &str| json::from_str(source).unwrap_err().to_string();
+    assert_eq!(error(r#"{"props": {}}"#), "missing field `type` at line 1 column 13");
+    assert_eq!(
+        error(r#"{"type": 1}"#),
+        "invalid type: integer `1`, expected a string at line 1 column 10"
+    );
+    assert_eq!(
+        error(r#"{"type": "div", "props": 1}"#),
+        "invalid type: integer `1`, expected a props object at line 1 column 26"
+    );
+    assert_eq!(error(r#"{"type": "div""#), "EOF while parsing an object at line 1 column 14");
+    assert!(render_json_to_static_markup("<div>").is_err());
+}
+
+// `depth` divs, each with its child in an array.
+fn nested_divs(depth: usize) -> String {
+    let mut json = "\"x\"".to_string();
+    for _ in 0..depth {
+        json = format!(r#"{{"type": "div", "props": {{"children": [{}]}}}}"#, json);
+    }
+    json
+}
+
+#[test]
+fn reads_trees_up_to_the_nesting_limit() {
+    let html = render_json_to_static_markup(&nested_divs(42)).unwrap();
+    assert_eq!(html, format!("{}x{}", "<div>".repeat(42), "</div>".repeat(42)));
+    let node = json::from_reader(nested_divs(42).as_bytes()).unwrap();
+    assert_eq!(json::from_str(&json::to_string(&node).unwrap()).unwrap(), node);
+
+    let deep = nested_divs(43);
+    let error = json::from_str(&deep).unwrap_err().to_string();
+    assert!(
+        error.starts_with(&format!("element tree nested more than {} JSON levels deep at line 1", json::MAX_NESTING)),
+        "{}", error
+    );
+    assert_eq!(json::from_reader(deep.as_bytes()).unwrap_err().to_string(), error);
+}
@@ -0,0 +1,133 @@
+extern crate react_neon_ssr;
+
+use std::time::{Duration, Instant};
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::limits::Limit;
+use react_neon_ssr::{Element, LimitExceeded, Limits, Node, Props, RenderError};
+use react_neon_ssr::{RenderOptions, render_to_string, render_with_options};
+
+fn limited(node: &Node, limits: Limits) -> Result<String, LimitExceeded> {
+    let options = RenderOptions {
+        limits,
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options)
+        .map(|result| result.html)
+        .map_err(|error| match error {
+            RenderError::LimitExceeded(error) => error,
+            error => panic!("unexpected error: {}", error),
+        })
+}
+
+fn nested(depth: usize) -> Node {
+    (0..depth).fold(Node::text("x"), |child, _| Element::new("div").child(child).into())
+}
+
+#[test]
+fn renders_within_the_limits() {
+    let node = nested(10);
+    let html = render_to_string(&node);
+    let limits = Limits {
+        max_depth: Some(10),
+        max_bytes: Some(html.len()),
+        deadline: Some(Instant::now() + Duration::from_secs(60)),
+    };
+    assert_eq!(limited(&node, limits), Ok(html));
+}
+
+#[test]
+fn aborts_past_the_maximum_depth() {
+    let limits = Limits {
+        max_depth: Some(10),
+        ..Limits::default()
+    };
+    let error = limited(&nested(11), limits).unwrap_err();
+    assert_eq!(error.limit, Limit::Depth(10));
+    assert_eq!(error.to_string(), "Render exceeded the maximum depth of 10");
+}
+
+#[test]
+fn stops_infinite_recursion_with_its_component_stack() {
+    component::register("LimitsForever", |_: &Props, _: &[Node]| -> Node {
+        Element::new("LimitsForever").into()
+    });
+    component::register("LimitsPage", |_: &Props, _: &[Node]| -> Node {
+        Element::new("main").child(Element::new("LimitsForever").into()).into()
+    });
+    let limits = Limits {
+        max_depth: Some(5),
+        ..Limits::default()
+    };
+    let error = limited(&Element::new("LimitsPage").into(), limits).unwrap_err();
+    assert_eq!(error.limit, Limit::Depth(5));
+    assert_eq!(error.component_stack, vec![
+        "LimitsForever", "LimitsForever", "LimitsForever", "LimitsPage",
+    ]);
+    assert!(error.to_string().ends_with(
+        "\n    in LimitsForever\n    in LimitsForever\n    in LimitsForever\n    in LimitsPage"
+    ));
+}
+
+#[test]
+fn aborts_past_the_maximum_output_size() {
+    let list = Element::new("ul");
+    let list = (0..100).fold(list, |list, i| {
+        list.child(Element::new("li").child(Node::text(i.to_string())).into())
+    });
+    let node: Node = list.into();
+    let limits = Limits {
+        max_bytes: Some(100),
+        ..Limits::default()
+    };
+    assert_eq!(limited(&node, limits.clone()).unwrap_err().limit, Limit::Bytes(100));
+    // Trailing text is checked too.
+    let text = Node::text("y".repeat(101));
+    assert_eq!(limited(&text, limits).unwrap_err().limit, Limit::Bytes(100));
+}
+
+#[test]
+fn aborts_past_the_deadline() {
+    let limits = Limits {
+        deadline: Some(Instant::now()),
+        ..Limits::default()
+    };
+    assert_eq!(limited(&nested(1), limits).unwrap_err().limit, Limit::Deadline);
+}
+
+#[test]
+fn aborted_renders_are_not_cached() {
+    component::register("LimitsCached", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        nested(5)
+    }));
+    let node: Node = Element::new("section").child(Element::new("LimitsCached").into()).into();
+    let limits = Limits {
+        max_depth: Some(4),
+        ..Limits::default()
+    };
+    assert!(limited(&node, limits).is_err());
+    let divs = "<div>".repeat(5) + "x" + &"</div>".repeat(5);
+    let expected = format!("<section data-reactroot=\"\">{}</section>", divs);
+    assert_eq!(limited(&node, Limits::default()), Ok(expected));
+}
+
+#[test]
+fn limits_apply_to_cached_markup() {
+    component::register("LimitsCachedDeep", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        nested(5)
+    }));
+    let node: Node = Element::new("section").child(Element::new("LimitsCachedDeep").into()).into();
+    assert!(limited(&node, Limits::default()).is_ok());
+    // The section, the component and five divs.
+    let limits = Limits {
+        max_depth: Some(6),
+        ..Limits::default()
+    };
+    let error = limited(&node, limits).unwrap_err();
+    assert_eq!(error.limit, Limit::Depth(6));
+    let limits = Limits {
+        max_depth: Some(7),
+        ..Limits::default()
+    };
+    assert!(limited(&node, limits).is_ok());
+}
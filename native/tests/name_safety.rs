@@ -0,0 +1,72 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::name_safety::{InvalidName, InvalidNameAction, NameKind};
+use react_neon_ssr::{Element, Node, Props, RenderError, RenderOptions, RenderResult, render_to_static_markup, render_with_options};
+
+fn render(node: &Node, action: InvalidNameAction) -> Result<RenderResult, RenderError> {
+    let options = RenderOptions {
+        static_markup: true,
+        invalid_names: Some(action),
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options)
+}
+
+#[test]
+fn leaves_out_invalid_attribute_names() {
+    let node: Node = Element::new("a")
+        .prop("href", "/")
+        .prop("x onmouseover=alert(1) y", "z")
+        .prop("data-ok", "1")
+        .into();
+    // The second time the name is a hit in the illegal name cache.
+    for _ in 0..2 {
+        assert_eq!(render_to_static_markup(&node), "<a href=\"/\" data-ok=\"1\"></a>");
+    }
+    let custom: Node = Element::new("my-element").prop("a\"b", "1").prop("c", "2").into();
+    assert_eq!(render_to_static_markup(&custom), "<my-element c=\"2\"></my-element>");
+}
+
+#[test]
+fn leaves_out_elements_with_invalid_tags() {
+    let node: Node = Element::new("div")
+        .child(Element::new("img src=x onerror=alert(1)").child(Node::text("gone")).into())
+        .child(Element::new("p").into())
+        .into();
+    for _ in 0..2 {
+        assert_eq!(render_to_static_markup(&node), "<div><p></p></div>");
+    }
+    let result = render(&node, InvalidNameAction::Warn).unwrap();
+    assert_eq!(result.html, "<div><p></p></div>");
+    assert_eq!(result.invalid_names, vec![InvalidName {
+        kind: NameKind::Tag,
+        name: "img src=x onerror=alert(1)".to_string(),
+        tag: "img src=x onerror=alert(1)".to_string(),
+        component_stack: vec![],
+    }]);
+}
+
+#[test]
+fn can_fail_the_render_instead() {
+    component::register("NameSafetyLink", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        Element::new("a").prop("on click", "x").into()
+    }));
+    let node: Node = Element::new("NameSafetyLink").into();
+    assert_eq!(render_to_static_markup(&node), "<a></a>");
+    let invalid_name = InvalidName {
+        kind: NameKind::Attribute,
+        name: "on click".to_string(),
+        tag: "a".to_string(),
+        component_stack: vec!["NameSafetyLink".to_string()],
+    };
+    let error = render(&node, InvalidNameAction::Abort).unwrap_err();
+    assert_eq!(error, RenderError::InvalidName(invalid_name.clone()));
+    assert_eq!(error.name(), "InvalidNameError");
+    assert_eq!(error.to_string(), "Invalid attribute name on <a>: `on click`\n    in NameSafetyLink");
+    // Cached markup doesn't hide them.
+    for _ in 0..2 {
+        let result = render(&node, InvalidNameAction::Warn).unwrap();
+        assert_eq!(result.invalid_names, vec![invalid_name.clone()]);
+    }
+}
@@ -0,0 +1,82 @@
+extern crate react_neon_ssr;
+
+use std::time::{Duration, Instant};
+
+use react_neon_ssr::component;
+use react_neon_ssr::element::RenderedComponent;
+use react_neon_ssr::profile::Profile;
+use react_neon_ssr::{Element, Node, Props, RenderOptions, render_with_options};
+
+fn profiled(node: &Node) -> (String, Vec<Profile>) {
+    let options = RenderOptions {
+        static_markup: true,
+        profile: true,
+        ..RenderOptions::default()
+    };
+    let result = render_with_options(node, &options).unwrap();
+    (result.html, result.profile.unwrap())
+}
+
+#[test]
+fn profiles_native_components() {
+    component::register("ProfiledItem", |_: &Props, _: &[Node]| -> Node {
+        Element::new("li").child(Node::text("item")).into()
+    });
+    component::register("ProfiledList", |_: &Props, _: &[Node]| -> Node {
+        Element::new("ul")
+            .child(Element::new("ProfiledItem").into())
+            .child(Element::new("ProfiledItem").into())
+            .into()
+    });
+    let (html, profile) = profiled(&Element::new("ProfiledList").into());
+    assert_eq!(html, "<ul><li>item</li><li>item</li></ul>");
+
+    assert_eq!(profile.len(), 1);
+    let list = &profile[0];
+    assert_eq!((list.name.as_str(), list.calls, list.bytes), ("ProfiledList", 1, html.len()));
+    assert_eq!(list.children.len(), 1);
+    let item = &list.children[0];
+    assert_eq!((item.name.as_str(), item.calls, item.bytes), ("ProfiledItem", 2, 26));
+    assert!(item.total_time <= list.total_time);
+    assert_eq!(list.self_time, list.total_time - item.total_time);
+}
+
+fn rendered(name: &str, elapsed: Duration, node: Node) -> Node {
+    Node::Component(Box::new(RenderedComponent {
+        name: name.to_string(),
+        started: Instant::now(),
+        elapsed,
+        node,
+    }))
+}
+
+#[test]
+fn profiles_js_components_with_their_conversion_time() {
+    let elapsed = Duration::from_millis(5);
+    let node = rendered("App", elapsed, Element::new("div")
+        .child(rendered("Text", elapsed, Node::text("hello")))
+        .child(Element::new("br").into())
+        .into());
+    let (html, profile) = profiled(&node);
+    assert_eq!(html, "<div>hello<br/></div>");
+    assert_eq!(profile[0].name, "App");
+    assert!(profile[0].total_time >= elapsed);
+    assert_eq!(profile[0].children[0].name, "Text");
+    assert_eq!(profile[0].children[0].bytes, "hello".len());
+    assert!(format!("{}", profile[0]).contains("\n  Text x1: "));
+}
+
+#[test]
+fn profiling_does_not_change_the_markup() {
+    let marked: Node = Element::new("pre")
+        .child(rendered("Text", Duration::new(0, 0), Node::text("\nhello")))
+        .into();
+    let unmarked: Node = Element::new("pre").child(Node::text("\nhello")).into();
+    assert_eq!(profiled(&marked).0, profiled(&unmarked).0);
+}
+
+#[test]
+fn profiles_are_opt_in() {
+    let result = render_with_options(&Node::text("x"), &RenderOptions::default()).unwrap();
+    assert_eq!(result.profile, None);
+}
@@ -0,0 +1,102 @@
+extern crate react_neon_ssr;
+
+use std::sync::Arc;
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::sanitize::Sanitizer;
+use react_neon_ssr::{Element, Node, Props, RenderOptions, render_to_static_markup, render_with_options};
+
+const CMS_HTML: &str = "<p onclick=\"steal()\">Hi <a href=\"javascript:steal()\">there</a>\
+                        <script>steal()</script><a href=\"https://example.com\" rel=\"me\">!</a></p>";
+
+fn article(html: Node) -> Node {
+    Element::new("article").child(html).into()
+}
+
+fn sanitized(node: &Node, sanitizer: Sanitizer) -> String {
+    let options = RenderOptions {
+        static_markup: true,
+        sanitizer: Some(Arc::new(sanitizer)),
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options).unwrap().html
+}
+
+#[test]
+fn raw_html_is_only_sanitized_when_asked_to() {
+    let node = article(Node::RawHtml(CMS_HTML.to_string()));
+    assert_eq!(render_to_static_markup(&node), format!("<article>{}</article>", CMS_HTML));
+    assert_eq!(
+        sanitized(&node, Sanitizer::default()),
+        "<article><p>Hi <a rel=\"noopener noreferrer\">there</a>\
+         <a href=\"https://example.com\" rel=\"noopener noreferrer\">!</a></p></article>"
+    );
+    let fragment = Node::Fragment(vec![Node::text("a"), Node::RawHtml("<img src=x onerror=alert(1)>".to_string())]);
+    assert_eq!(sanitized(&fragment, Sanitizer::default()), "a<img src=\"x\">");
+}
+
+#[test]
+fn trusted_html_is_never_sanitized() {
+    let node = article(Node::TrustedHtml(CMS_HTML.to_string()));
+    assert_eq!(
+        sanitized(&node, Sanitizer::default()),
+        format!("<article>{}</article>", CMS_HTML)
+    );
+}
+
+#[test]
+fn configures_tags_attributes_and_schemes() {
+    let sanitizer = Sanitizer {
+        tags: Some(vec!["a".to_string()]),
+        attributes: Some(vec![]),
+        tag_attributes: Some(vec![
+            ("a".to_string(), "href".to_string()),
+            ("a".to_string(), "rel".to_string()),
+        ]),
+        url_schemes: Some(vec!["mailto".to_string()]),
+    };
+    let node = article(Node::RawHtml(CMS_HTML.to_string()));
+    assert_eq!(sanitized(&node, sanitizer), "<article>Hi <a>there</a><a rel=\"me\">!</a></article>");
+
+}
+
+#[test]
+fn scripts_styles_and_handlers_cant_be_allowed() {
+    let sanitizer = Sanitizer {
+        tags: Some(vec!["b".to_string(), "SCRIPT".to_string(), "style".to_string()]),
+        attributes: Some(vec!["title".to_string(), "onclick".to_string(), "Style".to_string()]),
+        tag_attributes: Some(vec![
+            ("b".to_string(), "OnMouseOver".to_string()),
+            ("b".to_string(), "style".to_string()),
+        ]),
+        url_schemes: Some(vec!["javascript".to_string(), "VBScript".to_string()]),
+    };
+    let html = "<b title=\"t\" onclick=\"x()\" onmouseover=\"x()\" style=\"color:red\">b</b>\
+                <script>go()</script><style>b{}</style>";
+    let node = article(Node::RawHtml(html.to_string()));
+    assert_eq!(sanitized(&node, sanitizer), "<article><b title=\"t\">b</b></article>");
+
+    let links = Sanitizer {
+        url_schemes: Some(vec!["javascript".to_string(), "https".to_string()]),
+        ..Sanitizer::default()
+    };
+    let node = article(Node::RawHtml("<a href=\"javascript:go()\">a</a>".to_string()));
+    assert_eq!(sanitized(&node, links), "<article><a rel=\"noopener noreferrer\">a</a></article>");
+}
+
+#[test]
+fn cached_markup_is_kept_apart_per_sanitizer() {
+    component::register("SanitizeCmsBlock", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        article(Node::RawHtml("<b onclick=\"x()\">cms</b>".to_string()))
+    }));
+    let node: Node = Element::new("SanitizeCmsBlock").into();
+    let raw = "<article><b onclick=\"x()\">cms</b></article>";
+    assert_eq!(render_to_static_markup(&node), raw);
+    assert_eq!(sanitized(&node, Sanitizer::default()), "<article><b>cms</b></article>");
+    let no_bold = Sanitizer {
+        tags: Some(vec!["article".to_string()]),
+        ..Sanitizer::default()
+    };
+    assert_eq!(sanitized(&node, no_bold), "<article>cms</article>");
+    assert_eq!(render_to_static_markup(&node), raw);
+}
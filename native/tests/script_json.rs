@@ -0,0 +1,59 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::script_json::{escape_script_json, to_script_json};
+use react_neon_ssr::{Element, Node, PropValue, Props, RenderOptions, render_to_static_markup, render_with_options};
+use react_neon_ssr::sanitize::Sanitizer;
+
+use std::sync::Arc;
+
+fn state() -> PropValue {
+    let mut user = Props::new();
+    user.insert("bio", "</script><script>alert(1)</script> & <!--".into());
+    user.insert("lines", "a\u{2028}b\u{2029}c\n\"q\"\\\u{1}".into());
+    user.insert("visits", PropValue::Number(3.0));
+    user.insert("ratio", PropValue::Number(0.5));
+    user.insert("missing", PropValue::Number(f64::NAN));
+    user.insert("tags", PropValue::Array(vec![PropValue::Bool(true), PropValue::Null]));
+    PropValue::Object(user)
+}
+
+#[test]
+fn serializes_json_that_is_safe_in_a_script() {
+    assert_eq!(
+        to_script_json(&state()),
+        "{\"bio\":\"\\u003c/script\\u003e\\u003cscript\\u003ealert(1)\\u003c/script\\u003e \\u0026 \\u003c!--\",\
+         \"lines\":\"a\\u2028b\\u2029c\\n\\\"q\\\"\\\\\\u0001\",\
+         \"visits\":3,\"ratio\":0.5,\"missing\":null,\"tags\":[true,null]}"
+    );
+    assert_eq!(
+        escape_script_json("{\"a\":\"</script>\u{2028}\"}"),
+        "{\"a\":\"\\u003c/script\\u003e\\u2028\"}"
+    );
+}
+
+#[test]
+fn renders_json_scripts() {
+    let mut data = Props::new();
+    data.insert("main.js", "/static/main.js?a=1&b=2".into());
+    let manifest: Node = Element::new("JsonScript")
+        .prop("global", "assetManifest")
+        .prop("data", PropValue::Object(data))
+        .prop("nonce", "n")
+        .into();
+    let expected = "<script nonce=\"n\">window[\"assetManifest\"] = \
+                    {\"main.js\":\"/static/main.js?a=1\\u0026b=2\"};</script>";
+    assert_eq!(render_to_static_markup(&manifest), expected);
+
+    let state: Node = Element::new("JsonScript").prop("id", "state").prop("data", state()).into();
+    let html = render_to_static_markup(&state);
+    assert!(html.starts_with("<script type=\"application/json\" id=\"state\">{\"bio\":\"\\u003c/script"));
+    assert_eq!(html.matches("</script>").count(), 1);
+
+    // Never taken apart by the sanitizer.
+    let options = RenderOptions {
+        static_markup: true,
+        sanitizer: Some(Arc::new(Sanitizer::default())),
+        ..RenderOptions::default()
+    };
+    assert_eq!(render_with_options(&manifest, &options).unwrap().html, expected);
+}
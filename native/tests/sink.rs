@@ -0,0 +1,82 @@
+extern crate react_neon_ssr;
+
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use react_neon_ssr::component;
+use react_neon_ssr::json;
+use react_neon_ssr::sink::capacity_hint;
+use react_neon_ssr::{ChunkQueue, DomServerRenderer, Element, Node, Props, ReadSize, WriteSink};
+use react_neon_ssr::{render_into, render_route, render_to_string};
+
+fn fixture(name: &str) -> Node {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/conformance")
+        .join(name);
+    json::from_str(fs::read_to_string(path).unwrap().as_str()).unwrap()
+}
+
+// Chunks sent so far, and how many of them were sent before
+// `SinkStreamProbe` rendered.
+static SENT: AtomicUsize = AtomicUsize::new(0);
+static SENT_BEFORE_PROBE: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn chunk_queue_sends_chunks_during_the_render() {
+    component::register("SinkStreamProbe", |_: &Props, _: &[Node]| -> Node {
+        SENT_BEFORE_PROBE.store(SENT.load(Ordering::SeqCst), Ordering::SeqCst);
+        Node::text("probe")
+    });
+    let node = Node::Fragment(vec![fixture("forms.json"), Element::new("SinkStreamProbe").into()]);
+    let mut chunks = Vec::new();
+    let mut queue = ChunkQueue::new(64, |chunk| {
+        SENT.fetch_add(1, Ordering::SeqCst);
+        chunks.push(chunk);
+    });
+    render_into(&node, false, &mut queue);
+    queue.flush();
+    drop(queue);
+    let sent_before_probe = SENT_BEFORE_PROBE.load(Ordering::SeqCst);
+    assert!(sent_before_probe > 0 && sent_before_probe < chunks.len());
+    assert_eq!(SENT.load(Ordering::SeqCst), chunks.len());
+
+    let html = render_to_string(&node);
+    assert!(chunks[..chunks.len() - 1].iter().all(|chunk| chunk.len() >= 64));
+    assert_eq!(chunks.concat(), html);
+}
+
+#[test]
+fn write_sink_writes_the_same_markup() {
+    let node = fixture("escaping.json");
+    let mut sink = WriteSink::new(Vec::new());
+    render_into(&node, false, &mut sink);
+    let bytes = sink.finish().unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), render_to_string(&node));
+}
+
+#[test]
+fn routes_remember_their_output_size() {
+    let node = fixture("styles.json");
+    assert_eq!(capacity_hint("/styles"), 0);
+    let html = render_route("/styles", &node, false);
+    assert_eq!(html, render_to_string(&node));
+    assert_eq!(capacity_hint("/styles"), html.len());
+    assert!(render_route("/styles", &node, false).capacity() >= html.len());
+}
+
+#[test]
+fn reads_the_markup_in_pieces() {
+    let node: Node = Element::new("p").child(Node::text("h\u{e9}llo")).into();
+    let mut renderer = DomServerRenderer::new(&node, true);
+    let mut pieces = Vec::new();
+    while let Some(piece) = renderer.read(ReadSize::Size(4)) {
+        pieces.push(piece);
+    }
+    // The é takes two bytes.
+    assert_eq!(pieces, vec!["<p>h", "\u{e9}ll", "o</p", ">"]);
+
+    let mut renderer = DomServerRenderer::new(&node, true);
+    assert_eq!(renderer.read(ReadSize::Infinity), Some("<p>h\u{e9}llo</p>".to_string()));
+    assert_eq!(renderer.read(ReadSize::Infinity), None);
+}
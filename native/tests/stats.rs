@@ -0,0 +1,76 @@
+extern crate react_neon_ssr;
+
+use std::collections::BTreeMap;
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::stats::RenderStats;
+use react_neon_ssr::{Element, Node, PropValue, Props, RenderOptions, render_with_options};
+
+fn counted(node: &Node) -> (String, RenderStats) {
+    let options = RenderOptions {
+        stats: true,
+        ..RenderOptions::default()
+    };
+    let result = render_with_options(node, &options).unwrap();
+    (result.html, result.stats.unwrap())
+}
+
+fn card(_: &Props, _: &[Node]) -> Node {
+    let mut style = Props::new();
+    style.insert("color", PropValue::from("red"));
+    Element::new("section")
+        .prop("className", "card")
+        .prop("style", PropValue::Object(style))
+        .child(Element::new("h2").child(Node::text("Title")).into())
+        .child(Element::new("div")
+            .child(Node::RawHtml("<i>raw</i>".to_string()))
+            .into())
+        .child(Node::text("a"))
+        .child(Node::text("b"))
+        .into()
+}
+
+#[test]
+fn counts_what_is_rendered() {
+    component::register("StatsCard", card);
+    let node: Node = Element::new("main")
+        .prop("id", "app")
+        .child(Element::new("StatsCard").into())
+        .child(Element::new("StatsCard").into())
+        .into();
+    let (html, stats) = counted(&node);
+
+    assert_eq!(stats.elements, 7);
+    assert_eq!(stats.text_nodes, 6);
+    assert_eq!(stats.max_depth, 3);
+    assert_eq!(stats.style_bytes, 2 * r#" style="color:red""#.len());
+    assert_eq!(stats.inner_html_bytes, 2 * "<i>raw</i>".len());
+    let mut attributes = BTreeMap::new();
+    attributes.insert("className".to_string(), 2);
+    attributes.insert("data-reactroot".to_string(), 1);
+    attributes.insert("id".to_string(), 1);
+    attributes.insert("style".to_string(), 2);
+    assert_eq!(stats.attributes, attributes);
+    assert_eq!(stats.attribute_count(), 6);
+
+    let card_html = html[html.find("<section").unwrap()..html.find("</section>").unwrap() + 10].len();
+    assert_eq!(stats.components, vec![
+        ("StatsCard".to_string(), card_html),
+        ("StatsCard".to_string(), card_html),
+    ]);
+}
+
+#[test]
+fn cached_markup_is_counted_too() {
+    component::register("StatsCachedCard", Pure::new(card));
+    let node: Node = Element::new("div")
+        .child(Element::new("StatsCachedCard").into())
+        .into();
+    // The first render may fill the cache without stats.
+    render_with_options(&node, &RenderOptions::default()).unwrap();
+    let first = counted(&node).1;
+    let second = counted(&node).1;
+    assert_eq!(first, second);
+    assert_eq!(first.elements, 4);
+    assert_eq!(first.max_depth, 3);
+}
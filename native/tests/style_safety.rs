@@ -0,0 +1,106 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::style_safety::{StyleThreat, UnsafeStyle, UnsafeStyleAction, check};
+use react_neon_ssr::{Element, Node, PropValue, Props, RenderError, RenderOptions, RenderResult, render_to_static_markup, render_with_options};
+
+fn styled(styles: &[(&str, &str)]) -> Node {
+    let mut style = Props::new();
+    for &(name, value) in styles {
+        style.insert(name, PropValue::String(value.to_string()));
+    }
+    Element::new("div").prop("style", PropValue::Object(style)).into()
+}
+
+fn render(node: &Node, action: UnsafeStyleAction) -> Result<RenderResult, RenderError> {
+    let options = RenderOptions {
+        static_markup: true,
+        style_safety: Some(action),
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options)
+}
+
+#[test]
+fn finds_unsafe_declarations() {
+    assert_eq!(check("color", "red"), None);
+    assert_eq!(check("fontFamily", "\"Helvetica Neue\", 'Arial'"), None);
+    assert_eq!(check("content", "'a;b{}'"), None);
+    assert_eq!(check("backgroundImage", "url(/a.png)"), None);
+    assert_eq!(check("scrollBehavior", "smooth"), None);
+
+    assert_eq!(check("color", "red;position:fixed"), Some(StyleThreat::Syntax));
+    assert_eq!(check("color", "red}body{x:y"), Some(StyleThreat::Syntax));
+    assert_eq!(check("fontFamily", "'Arial"), Some(StyleThreat::Syntax));
+    assert_eq!(check("width", "EXPRESSION(alert(1))"), Some(StyleThreat::Expression));
+    assert_eq!(check("width", "ex/**/pr\\65 ssion(alert(1))"), Some(StyleThreat::Expression));
+    assert_eq!(check("width", "expre\\ssion(alert(1))"), Some(StyleThreat::Expression));
+    assert_eq!(check("background", "url( 'javascript:alert(1)')"), Some(StyleThreat::ScriptUrl));
+    assert_eq!(check("background", "url(\\6a avascript:alert(1))"), Some(StyleThreat::ScriptUrl));
+    assert_eq!(check("MozBinding", "url(x.xml#xss)"), Some(StyleThreat::Binding));
+    assert_eq!(check("behavior", "url(x.htc)"), Some(StyleThreat::Binding));
+
+    assert_eq!(check("--custom_prop-2", "1"), None);
+    assert_eq!(check("WebkitTransition", "none"), None);
+    for name in &["x;behavior", "color:red;-moz-binding", "a{", "a}", "a\"b", "a'b", "a b", "a\n", ""] {
+        assert_eq!(check(name, "url(x.htc)"), Some(StyleThreat::Syntax), "{:?}", name);
+    }
+}
+
+#[test]
+fn rejects_declarations_smuggled_in_the_property() {
+    let node = styled(&[("x;behavior", "url(x.htc)"), ("color:red;-moz-binding", "url(x.xml#xss)")]);
+    match render(&node, UnsafeStyleAction::Abort) {
+        Err(RenderError::UnsafeStyle(error)) => {
+            assert_eq!(error.property, "x;behavior");
+            assert_eq!(error.threat, StyleThreat::Syntax);
+        },
+        result => panic!("expected an unsafe style, got {:?}", result),
+    }
+    let result = render(&node, UnsafeStyleAction::Drop).unwrap();
+    assert_eq!(result.html, "<div></div>");
+    assert_eq!(result.unsafe_styles.len(), 2);
+}
+
+#[test]
+fn drops_or_rejects_unsafe_declarations() {
+    let node = styled(&[("color", "red"), ("width", "expression(alert(1))"), ("margin", "0")]);
+    assert_eq!(
+        render_to_static_markup(&node),
+        "<div style=\"color:red;width:expression(alert(1));margin:0\"></div>"
+    );
+    let unsafe_style = UnsafeStyle {
+        tag: "div".to_string(),
+        property: "width".to_string(),
+        value: "expression(alert(1))".to_string(),
+        threat: StyleThreat::Expression,
+        component_stack: vec![],
+    };
+    let result = render(&node, UnsafeStyleAction::Drop).unwrap();
+    assert_eq!(result.html, "<div style=\"color:red;margin:0\"></div>");
+    assert_eq!(result.unsafe_styles, vec![unsafe_style.clone()]);
+    assert_eq!(
+        render(&node, UnsafeStyleAction::Abort),
+        Err(RenderError::UnsafeStyle(unsafe_style))
+    );
+
+    let node = styled(&[("color", "red;x:y")]);
+    assert_eq!(render(&node, UnsafeStyleAction::Drop).unwrap().html, "<div></div>");
+}
+
+#[test]
+fn cached_markup_is_checked_too() {
+    component::register("StyleSafetyBanner", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        styled(&[("background", "url(javascript:alert(1))")])
+    }));
+    let node: Node = Element::new("StyleSafetyBanner").into();
+    assert_eq!(
+        render_to_static_markup(&node),
+        "<div style=\"background:url(javascript:alert(1))\"></div>"
+    );
+    for _ in 0..2 {
+        let result = render(&node, UnsafeStyleAction::Drop).unwrap();
+        assert_eq!(result.html, "<div></div>");
+        assert_eq!(result.unsafe_styles[0].component_stack, vec!["StyleSafetyBanner".to_string()]);
+    }
+}
@@ -0,0 +1,69 @@
+extern crate react_neon_ssr;
+extern crate serde_json;
+
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use react_neon_ssr::component;
+use react_neon_ssr::element::RenderedComponent;
+use react_neon_ssr::trace::{Phase, Trace};
+use react_neon_ssr::{Element, Node, Props, RenderOptions, render_with_options};
+
+fn traced(node: &Node) -> Trace {
+    let options = RenderOptions {
+        trace: true,
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options).unwrap().trace.unwrap()
+}
+
+#[test]
+fn traces_components_and_elements() {
+    component::register("TracedBadge", |_: &Props, _: &[Node]| -> Node {
+        Element::new("span").child(Node::text("new")).into()
+    });
+    let started = Instant::now();
+    let node = Node::Component(Box::new(RenderedComponent {
+        name: "App".to_string(),
+        started,
+        elapsed: Duration::from_millis(1),
+        node: Element::new("div").child(Element::new("TracedBadge").into()).into(),
+    }));
+    let trace = traced(&node);
+    let events: Vec<(&str, &str, Phase)> = trace.events
+        .iter()
+        .map(|event| (event.name.as_str(), event.category, event.phase))
+        .collect();
+    assert_eq!(events, vec![
+        ("App", "convert", Phase::Complete(Duration::from_millis(1))),
+        ("App", "component", Phase::Begin),
+        ("div", "dom", Phase::Begin),
+        ("TracedBadge", "component", Phase::Begin),
+        ("span", "dom", Phase::Begin),
+        ("span", "dom", Phase::End),
+        ("TracedBadge", "component", Phase::End),
+        ("div", "dom", Phase::End),
+        ("App", "component", Phase::End),
+    ]);
+    assert_eq!(trace.events[0].timestamp, Duration::new(0, 0));
+    assert!(trace.events.windows(2).skip(1).all(|w| w[0].timestamp <= w[1].timestamp));
+}
+
+#[test]
+fn writes_chrome_trace_json() {
+    let trace = traced(&Element::new("p").child(Node::text("hi")).into());
+    let path = env::temp_dir().join(format!("react-neon-ssr-trace-{}.json", std::process::id()));
+    trace.write_to_file(&path).unwrap();
+    let written = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(written, trace.to_json());
+    let json: serde_json::Value = serde_json::from_str(written.as_str()).unwrap();
+    let events = json["traceEvents"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["name"], "p");
+    assert_eq!(events[0]["cat"], "dom");
+    assert_eq!(events[0]["ph"], "B");
+    assert_eq!(events[1]["ph"], "E");
+    assert!(events[0]["ts"].is_number() && events[0]["pid"] == 1);
+}
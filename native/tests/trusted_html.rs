@@ -0,0 +1,128 @@
+extern crate react_neon_ssr;
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::trusted_html::{HtmlInjection, UntrustedHtml};
+use react_neon_ssr::{Element, Node, Props, RenderError, RenderOptions, render_to_static_markup, render_with_options};
+
+fn inner_html(tag: &str, html: Node) -> Node {
+    Element::new(tag).child(html).into()
+}
+
+fn trusted_only(node: &Node) -> Result<String, RenderError> {
+    let options = RenderOptions {
+        static_markup: true,
+        trusted_html_only: true,
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options).map(|result| result.html)
+}
+
+fn injections(node: &Node) -> Vec<HtmlInjection> {
+    let options = RenderOptions {
+        static_markup: true,
+        html_injections: true,
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options).unwrap().html_injections.unwrap()
+}
+
+#[test]
+fn refuses_untrusted_html_when_asked_to() {
+    component::register("TrustedHtmlComment", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        inner_html("p", Node::RawHtml("<b>hi</b>".to_string()))
+    }));
+    let node: Node = Element::new("section")
+        .child(inner_html("div", Node::TrustedHtml("<i>ok</i>".to_string())))
+        .child(Element::new("TrustedHtmlComment").into())
+        .into();
+    assert_eq!(
+        trusted_only(&node),
+        Err(RenderError::UntrustedHtml(UntrustedHtml {
+            tag: Some("p".to_string()),
+            component_stack: vec!["TrustedHtmlComment".to_string()],
+        }))
+    );
+    assert_eq!(
+        render_to_static_markup(&node),
+        "<section><div><i>ok</i></div><p><b>hi</b></p></section>"
+    );
+    let error = trusted_only(&node).unwrap_err();
+    assert_eq!(error.name(), "UntrustedHtmlError");
+    assert_eq!(
+        error.to_string(),
+        "Refused untrusted HTML in <p>: only HTML marked with trustHTML may be set \
+         with dangerouslySetInnerHTML\n    in TrustedHtmlComment"
+    );
+
+    let trusted = inner_html("div", Node::TrustedHtml("<i>ok</i>".to_string()));
+    assert_eq!(trusted_only(&trusted).unwrap(), "<div><i>ok</i></div>");
+    let loose = Node::Fragment(vec![Node::RawHtml("<hr>".to_string())]);
+    assert_eq!(
+        trusted_only(&loose),
+        Err(RenderError::UntrustedHtml(UntrustedHtml { tag: None, component_stack: vec![] }))
+    );
+}
+
+#[test]
+fn reports_the_components_that_inject_html() {
+    component::register("TrustedHtmlPost", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        inner_html("article", Node::TrustedHtml("<p>post</p>".to_string()))
+    }));
+    component::register("TrustedHtmlAd", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        inner_html("aside", Node::RawHtml("<img src=x>".to_string()))
+    }));
+    component::register("TrustedHtmlPage", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        Element::new("main")
+            .child(Element::new("TrustedHtmlPost").into())
+            .child(Element::new("TrustedHtmlAd").into())
+            .child(inner_html("span", Node::text("text")))
+            .into()
+    }));
+    let node: Node = Element::new("TrustedHtmlPage").into();
+    let expected = vec![
+        HtmlInjection {
+            tag: Some("article".to_string()),
+            trusted: true,
+            bytes: 11,
+            component_stack: vec!["TrustedHtmlPost".to_string(), "TrustedHtmlPage".to_string()],
+        },
+        HtmlInjection {
+            tag: Some("aside".to_string()),
+            trusted: false,
+            bytes: 11,
+            component_stack: vec!["TrustedHtmlAd".to_string(), "TrustedHtmlPage".to_string()],
+        },
+    ];
+    assert_eq!(injections(&node), expected);
+    // Cached markup would hide them.
+    assert_eq!(injections(&node), expected);
+}
+
+#[test]
+fn reports_html_in_markup_cached_without_the_report() {
+    component::register("TrustedHtmlTeaser", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        inner_html("p", Node::TrustedHtml("<b>teaser</b>".to_string()))
+    }));
+    component::register("TrustedHtmlFeed", |_: &Props, _: &[Node]| -> Node {
+        Element::new("div").child(Element::new("TrustedHtmlTeaser").into()).into()
+    });
+    let node: Node = Element::new("TrustedHtmlFeed").into();
+    assert_eq!(render_to_static_markup(&node), "<div><p><b>teaser</b></p></div>");
+    assert_eq!(injections(&node), vec![HtmlInjection {
+        tag: Some("p".to_string()),
+        trusted: true,
+        bytes: 13,
+        component_stack: vec!["TrustedHtmlTeaser".to_string(), "TrustedHtmlFeed".to_string()],
+    }]);
+}
+
+#[test]
+fn cached_untrusted_html_is_still_refused() {
+    component::register("TrustedHtmlWidget", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        inner_html("div", Node::RawHtml("<b>w</b>".to_string()))
+    }));
+    let node: Node = Element::new("TrustedHtmlWidget").into();
+    assert_eq!(render_to_static_markup(&node), "<div><b>w</b></div>");
+    assert!(trusted_only(&node).is_err());
+    assert_eq!(render_to_static_markup(&node), "<div><b>w</b></div>");
+}
@@ -0,0 +1,212 @@
+extern crate react_neon_ssr;
+
+use std::sync::{Arc, Mutex};
+
+use react_neon_ssr::component::{self, Pure};
+use react_neon_ssr::{Element, Node, PropValue, Props, RenderError, RenderOptions, RenderResult};
+use react_neon_ssr::{UnsafeUrlAction, UrlPolicy, render_to_static_markup, render_with_options};
+
+fn link(href: &str) -> Node {
+    Element::new("a").prop("href", href).child(Node::text("x")).into()
+}
+
+fn with_policy(node: &Node, policy: UrlPolicy) -> Result<RenderResult, RenderError> {
+    let options = RenderOptions {
+        static_markup: true,
+        url_policy: Some(Arc::new(policy)),
+        ..RenderOptions::default()
+    };
+    render_with_options(node, &options)
+}
+
+#[test]
+fn drops_script_urls_by_default() {
+    let unsafe_urls = [
+        "javascript:alert(1)",
+        "JavaScript:alert(1)",
+        " \u{1}java\tscript:alert(1)",
+        "vbscript:msgbox(1)",
+        "data:text/html,<script>alert(1)</script>",
+        "data:image/svg+xml,<svg onload=alert(1)>",
+    ];
+    for url in unsafe_urls.iter() {
+        assert_eq!(render_to_static_markup(&link(url)), "<a>x</a>", "{:?}", url);
+    }
+    let safe_urls = [
+        "https://example.com/a?b=javascript:c",
+        "/search?q=javascript:",
+        "#javascript:",
+        "mailto:someone@example.com",
+        "data:image/png;base64,iVBORw0KGgo=",
+        "javascript",
+    ];
+    for url in safe_urls.iter() {
+        let html = render_to_static_markup(&link(url));
+        assert!(html.starts_with("<a href=\""), "{:?}", url);
+    }
+}
+
+#[test]
+fn checks_every_url_attribute() {
+    let node: Node = Element::new("form")
+        .prop("action", "javascript:a()")
+        .child(Element::new("button").prop("formAction", "javascript:b()").into())
+        .child(Element::new("img").prop("src", "javascript:c()").prop("alt", "javascript:d()").into())
+        .child(Element::new("svg")
+            .child(Element::new("a").prop("xlinkHref", "javascript:e()").into())
+            .into())
+        .child(Element::new("x-link").prop("href", "javascript:f()").into())
+        .into();
+    assert_eq!(
+        render_to_static_markup(&node),
+        "<form><button></button><img alt=\"javascript:d()\"/><svg><a></a></svg><x-link></x-link></form>"
+    );
+}
+
+#[test]
+fn replaces_urls_outside_the_allowed_schemes() {
+    component::register("UrlPolicyProfile", |props: &Props, _: &[Node]| -> Node {
+        let website = props.get("website").unwrap().to_string();
+        link(website.as_str())
+    });
+    let policy = UrlPolicy {
+        allowed_schemes: Some(vec!["https".to_string()]),
+        action: UnsafeUrlAction::Replace("about:blank".to_string()),
+        report: None,
+    };
+    let node: Node = Element::new("UrlPolicyProfile").prop("website", "ftp://example.com").into();
+    let result = with_policy(&node, policy.clone()).unwrap();
+    assert_eq!(result.html, "<a href=\"about:blank\">x</a>");
+    assert_eq!(result.unsafe_urls.len(), 1);
+    let unsafe_url = &result.unsafe_urls[0];
+    assert_eq!(
+        (unsafe_url.tag.as_str(), unsafe_url.attribute.as_str(), unsafe_url.url.as_str()),
+        ("a", "href", "ftp://example.com")
+    );
+    assert_eq!(unsafe_url.component_stack, vec!["UrlPolicyProfile"]);
+
+    let node: Node = Element::new("UrlPolicyProfile").prop("website", "https://example.com").into();
+    let result = with_policy(&node, policy).unwrap();
+    assert_eq!(result.html, "<a href=\"https://example.com\">x</a>");
+    assert!(result.unsafe_urls.is_empty());
+}
+
+#[test]
+fn checks_url_attributes_whatever_their_case() {
+    let policy = || UrlPolicy {
+        action: UnsafeUrlAction::Abort,
+        ..UrlPolicy::default()
+    };
+    let names = [("a", "HREF"), ("a", "Href"), ("button", "FormAction"), ("img", "SRC"),
+                 ("img", "SrcSet"), ("x-link", "HREF"), ("x-link", "xlink:HREF")];
+    for &(tag, name) in names.iter() {
+        let node: Node = Element::new(tag).prop(name, "javascript:alert(1)").into();
+        match with_policy(&node, policy()) {
+            Err(RenderError::UnsafeUrl(error)) => assert_eq!(error.attribute, name),
+            result => panic!("<{} {}>: expected an unsafe URL, got {:?}", tag, name, result),
+        }
+    }
+    let node: Node = Element::new("x-link").prop("HREF", "/ok").into();
+    assert_eq!(with_policy(&node, policy()).unwrap().html, "<x-link HREF=\"/ok\"></x-link>");
+}
+
+#[test]
+fn aborts_the_render_after_reporting() {
+    component::register("UrlPolicyComment", |_: &Props, _: &[Node]| -> Node {
+        Element::new("p").child(link("javascript:alert(document.cookie)")).into()
+    });
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let report = reported.clone();
+    let policy = UrlPolicy {
+        action: UnsafeUrlAction::Abort,
+        report: Some(Arc::new(move |unsafe_url| {
+            report.lock().unwrap().push(unsafe_url.url.clone());
+        })),
+        ..UrlPolicy::default()
+    };
+    let node: Node = Element::new("main").child(Element::new("UrlPolicyComment").into()).into();
+    let error = match with_policy(&node, policy) {
+        Err(RenderError::UnsafeUrl(error)) => error,
+        result => panic!("expected an unsafe URL, got {:?}", result),
+    };
+    assert_eq!(error.component_stack, vec!["UrlPolicyComment"]);
+    assert_eq!(
+        error.to_string(),
+        "Blocked an unsafe URL in the `href` attribute of <a>: \
+         javascript:alert(document.cookie)\n    in UrlPolicyComment"
+    );
+    assert_eq!(*reported.lock().unwrap(), vec!["javascript:alert(document.cookie)"]);
+}
+
+#[test]
+fn cached_components_report_on_every_render() {
+    component::register("UrlPolicyCached", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        link("vbscript:x")
+    }));
+    let node: Node = Element::new("UrlPolicyCached").into();
+    for _ in 0..2 {
+        let result = with_policy(&node, UrlPolicy::default()).unwrap();
+        assert_eq!(result.html, "<a>x</a>");
+        assert_eq!(result.unsafe_urls.len(), 1);
+    }
+}
+
+#[test]
+fn checks_what_is_written_whatever_the_value() {
+    let script = PropValue::String("javascript:alert(1)".to_string());
+    let node: Node = Element::new("div")
+        .child(Element::new("a").prop("href", PropValue::Array(vec![script.clone()])).into())
+        .child(Element::new("a").prop("href", PropValue::Array(vec![
+            PropValue::Array(vec![PropValue::String("vbscript:x".to_string())]),
+        ])).into())
+        .child(Element::new("img").prop("src", PropValue::Number(1.0)).into())
+        .into();
+    let result = with_policy(&node, UrlPolicy::default()).unwrap();
+    assert_eq!(result.html, "<div><a></a><a></a><img src=\"1\"/></div>");
+    let urls: Vec<&str> = result.unsafe_urls.iter().map(|url| url.url.as_str()).collect();
+    assert_eq!(urls, vec!["javascript:alert(1)", "vbscript:x"]);
+}
+
+#[test]
+fn checks_every_url_in_srcset_and_ping() {
+    let node: Node = Element::new("div")
+        .child(Element::new("img")
+            .prop("srcSet", "/a.png 1x, /b.png 2x,javascript:c() 3x")
+            .into())
+        .child(Element::new("img")
+            .prop("srcSet", "data:image/png;base64,iVBO=,  /b.png 2x")
+            .into())
+        .child(Element::new("a").prop("ping", "/track javascript:d()").into())
+        .child(Element::new("object").prop("data", "javascript:e()").into())
+        .child(Element::new("html").prop("manifest", "javascript:f()").into())
+        .child(Element::new("body").prop("background", "javascript:g()").into())
+        .into();
+    let result = with_policy(&node, UrlPolicy::default()).unwrap();
+    assert_eq!(
+        result.html,
+        "<div><img/><img srcSet=\"data:image/png;base64,iVBO=,  /b.png 2x\"/><a></a>\
+         <object></object><html></html><body></body></div>"
+    );
+    let urls: Vec<&str> = result.unsafe_urls.iter().map(|url| url.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec!["javascript:c()", "javascript:d()", "javascript:e()", "javascript:f()", "javascript:g()"]
+    );
+}
+
+#[test]
+fn cached_markup_is_keyed_by_the_policy() {
+    component::register("UrlPolicyFtpLink", Pure::new(|_: &Props, _: &[Node]| -> Node {
+        link("ftp://example.com")
+    }));
+    let node: Node = Element::new("UrlPolicyFtpLink").into();
+    let loose = with_policy(&node, UrlPolicy::default()).unwrap();
+    assert_eq!(loose.html, "<a href=\"ftp://example.com\">x</a>");
+    let strict = UrlPolicy {
+        allowed_schemes: Some(vec!["https".to_string()]),
+        ..UrlPolicy::default()
+    };
+    let result = with_policy(&node, strict).unwrap();
+    assert_eq!(result.html, "<a>x</a>");
+    assert_eq!(result.unsafe_urls.len(), 1);
+}
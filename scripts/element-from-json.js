@@ -0,0 +1,18 @@
+// Turns a JSON element tree (see native/src/json.rs) back into React
+// elements. The format is what `JSON.stringify(element)` produces, so only
+// `type` and `props` matter.
+const React = require('react');
+
+module.exports = function elementFromJSON(node) {
+  if (Array.isArray(node)) {
+    return node.map(elementFromJSON);
+  }
+  if (node === null || typeof node !== 'object') {
+    return node;
+  }
+  const props = Object.assign({}, node.props);
+  if (props.children !== undefined) {
+    props.children = elementFromJSON(props.children);
+  }
+  return React.createElement(node.type, props);
+};
@@ -0,0 +1,22 @@
+// Regenerates the expected markup for the native conformance tests from
+// react-dom/server. Run from the repository root after adding a case to
+// native/tests/fixtures/conformance:
+//
+//   node scripts/generate-conformance-fixtures.js
+const fs = require('fs');
+const path = require('path');
+const ReactDOMServer = require('react-dom/server');
+const elementFromJSON = require('./element-from-json');
+
+const dir = path.join(__dirname, '..', 'native', 'tests', 'fixtures', 'conformance');
+
+fs.readdirSync(dir)
+  .filter(name => name.endsWith('.json'))
+  .sort()
+  .forEach(name => {
+    const base = path.join(dir, path.basename(name, '.json'));
+    const tree = elementFromJSON(JSON.parse(fs.readFileSync(base + '.json', 'utf8')));
+    fs.writeFileSync(base + '.html', ReactDOMServer.renderToString(tree) + '\n');
+    fs.writeFileSync(base + '.static.html', ReactDOMServer.renderToStaticMarkup(tree) + '\n');
+    console.log(name);
+  });
@@ -0,0 +1,26 @@
+// Reference renderer for the native differential tests. Reads one JSON
+// element tree per line on stdin and answers each with one line of JSON:
+// `{"html": ..., "static": ...}`, or `{"error": ...}` when react-dom throws.
+const readline = require('readline');
+const ReactDOMServer = require('react-dom/server');
+const elementFromJSON = require('./element-from-json');
+
+// Keep dev-mode warnings off stdout, it is the protocol channel.
+console.error = () => {};
+console.warn = () => {};
+
+readline
+  .createInterface({ input: process.stdin })
+  .on('line', line => {
+    let result;
+    try {
+      const element = elementFromJSON(JSON.parse(line));
+      result = {
+        html: ReactDOMServer.renderToString(element),
+        static: ReactDOMServer.renderToStaticMarkup(element),
+      };
+    } catch (err) {
+      result = { error: String(err && err.message) };
+    }
+    process.stdout.write(JSON.stringify(result) + '\n');
+  });
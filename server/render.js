@@ -1,6 +1,6 @@
 import React from 'react';
 import { Suite } from 'benchmark';
-import {renderToString as rustRenderToString } from '../native';
+import {renderDocument, renderToString as rustRenderToString } from '../native';
 import {renderToString as jsRenderToString} from 'react-dom/server';
 
 import App from '../src/components/App';
@@ -17,7 +17,7 @@ if (process.env.NODE_ENV === 'development') {
 }
 
 export default function render() {
-  const app = <App assets={assets} />;
+  const app = <App />;
 
   var html = jsRenderToString(app);
   var html = rustRenderToString(app);
@@ -35,7 +35,16 @@ export default function render() {
     })
     .run({ 'async': true });
 
-  // There's no way to render a doctype in React so prepend manually.
-  // Also append a bootstrap script tag.
-  return '<!DOCTYPE html>' + html;
+  // The client hydrates the app into #root.
+  return renderDocument(<div id="root">{app}</div>, {
+    htmlAttributes: {lang: 'en'},
+    head: [
+      <meta charSet="utf-8" />,
+      <meta name="viewport" content="width=device-width, initial-scale=1" />,
+      <link rel="shortcut icon" href="favicon.ico" />,
+      <title>Hello World</title>,
+    ],
+    // The stylesheet and the deferred script of the `main` entrypoint.
+    assets: {manifest: assets, publicPath: '/'},
+  });
 }
@@ -6,7 +6,7 @@ import Page from './Page';
 export default class App extends Component {
   render() {
     return (
-      <Chrome title="Hello World" assets={this.props.assets}>
+      <Chrome>
         <div>
           <h1>Hello World</h1>
           <Page />
@@ -2,33 +2,19 @@ import React, {Component} from 'react';
 
 import './Chrome.css';
 
+// The page around the content. The document itself, with the head and the
+// scripts, is rendered by the server with `renderDocument`.
 export default class Chrome extends Component {
   render() {
-    const assets = this.props.assets;
     return (
-      <html lang="en">
-        <head>
-          <meta charSet="utf-8" />
-          <meta name="viewport" content="width=device-width, initial-scale=1" />
-          <link rel="shortcut icon" href="favicon.ico" />
-          <link rel="stylesheet" href={assets['main.css']} />
-          <title>{this.props.title}</title>
-        </head>
-        <body>
-          <noscript
-            dangerouslySetInnerHTML={{
-              __html: `<b>Enable JavaScript to run this app.</b>`,
-            }}
-          />
-          {this.props.children}
-          <script
-            dangerouslySetInnerHTML={{
-              __html: `assetManifest = ${JSON.stringify(assets)};`,
-            }}
-          />
-          <script src={assets['main.js']} />
-        </body>
-      </html>
+      <div>
+        <noscript
+          dangerouslySetInnerHTML={{
+            __html: `<b>Enable JavaScript to run this app.</b>`,
+          }}
+        />
+        {this.props.children}
+      </div>
     );
   }
 }